@@ -1,5 +1,5 @@
 use exponent_time_curve::num::Num;
-use precise_number::Number;
+use precise_number::{Number, SignedNumber};
 use rust_decimal::{
     prelude::{FromPrimitive, One, ToPrimitive},
     Decimal, MathematicalOps,
@@ -14,6 +14,10 @@ pub struct DNum {
 // The maximum size of a Decimal is 96 bits
 const MAX_U96: u128 = (1 << 96) - 1;
 
+// Stop the exp series once terms fall below the smallest Decimal scale
+// The rust_decimal default of 2e-7 is too coarse for curve math
+const EXP_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 28);
+
 impl DNum {
     pub fn deserialize(bs: &[u8]) -> Self {
         let ar: [u8; 16] = match bs.try_into() {
@@ -72,6 +76,21 @@ impl DNum {
         int_part + frac_part
     }
 
+    pub fn from_signed_number(sn: &SignedNumber) -> Self {
+        let magnitude = Self::from_precise_number(&sn.abs());
+        if sn.is_negative() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn to_signed_number(&self) -> SignedNumber {
+        let magnitude = self.abs().to_precise_number();
+        SignedNumber::from_parts(magnitude, self.is_sign_negative())
+            .expect("DNum too large for SignedNumber")
+    }
+
     pub fn abs(self) -> Self {
         if self.value.is_sign_negative() {
             Self { value: -self.value }
@@ -87,6 +106,30 @@ impl DNum {
     pub fn is_sign_positive(&self) -> bool {
         self.value.is_sign_positive()
     }
+
+    pub fn floor(self) -> Self {
+        Self {
+            value: self.value.floor(),
+        }
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        self.value.to_f64()
+    }
+
+    /// Convert to u64, returning None if the value is negative or too large
+    pub fn checked_to_u64(&self) -> Option<u64> {
+        if self.value.is_sign_negative() {
+            return None;
+        }
+
+        self.value.to_u64()
+    }
+
+    /// Convert to i64, returning None if the value is too large
+    pub fn checked_to_i64(&self) -> Option<i64> {
+        self.value.to_i64()
+    }
 }
 
 impl From<i64> for DNum {
@@ -196,21 +239,24 @@ fn checked_ln(d: &Decimal) -> Option<Decimal> {
         x *= Decimal::E;
         count -= 1;
     }
-    x -= Decimal::ONE;
-    if x.is_zero() {
+    if x.is_one() {
         return Some(Decimal::new(count, 0));
     }
+
+    // ln(x) = 2 * atanh((x - 1) / (x + 1)), which converges much faster than the ln(1 + x) series
+    let z = (x - Decimal::ONE) / (x + Decimal::ONE);
+    let z_squared = z * z;
     let mut result = Decimal::ZERO;
     let mut iteration = 0;
-    let mut y = Decimal::ONE;
+    let mut y = z;
     let mut last = Decimal::ONE;
-    while last != result && iteration < 50 {
-        iteration += 1;
+    while last != result && iteration < 100 {
         last = result;
-        y *= -x;
-        result += y / Decimal::new(iteration, 0);
+        result += y / Decimal::new(2 * iteration + 1, 0);
+        y *= z_squared;
+        iteration += 1;
     }
-    Some(Decimal::new(count, 0) - result)
+    Some(Decimal::new(count, 0) + Decimal::TWO * result)
 }
 
 fn ln(d: &Decimal) -> Decimal {
@@ -237,7 +283,7 @@ impl Num for DNum {
 
     fn exp(&self) -> Self {
         Self {
-            value: self.value.exp(),
+            value: self.value.exp_with_tolerance(EXP_TOLERANCE),
         }
     }
 
@@ -6,6 +6,10 @@ use spl_math::{
 };
 use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
 
+mod signed;
+
+pub use signed::SignedNumber;
+
 /// High precision number, stored as 4 u64 words in little endian
 #[derive(
     Default, Clone, Debug, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize, Zeroable, Pod,
@@ -0,0 +1,295 @@
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use bytemuck::{Pod, Zeroable};
+use spl_math::{precise_number, uint::U256};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::Number;
+
+/// Signed high precision number with 1e12 precision, stored as a two's complement U256 in 4 u64 words in little endian
+#[derive(
+    Default, Clone, Debug, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize, Zeroable, Pod,
+)]
+#[repr(C)]
+pub struct SignedNumber([u64; 4]);
+
+impl core::fmt::Display for SignedNumber {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.is_negative() {
+            write!(f, "-{}", self.abs())
+        } else {
+            write!(f, "{}", self.abs())
+        }
+    }
+}
+
+impl From<Number> for SignedNumber {
+    fn from(value: Number) -> Self {
+        Self::from_parts(value, false).expect("Number too large for SignedNumber")
+    }
+}
+
+impl From<i64> for SignedNumber {
+    fn from(value: i64) -> Self {
+        Self::from_parts(
+            Number::from_natural_u64(value.unsigned_abs()),
+            value.is_negative(),
+        )
+        .unwrap()
+    }
+}
+
+impl TryFrom<SignedNumber> for Number {
+    type Error = ();
+
+    /// Fails if the value is negative
+    fn try_from(value: SignedNumber) -> Result<Self, Self::Error> {
+        if value.is_negative() {
+            Err(())
+        } else {
+            Ok(Number(value.0))
+        }
+    }
+}
+
+impl SignedNumber {
+    // The byte size of SignedNumber
+    pub const SIZEOF: usize = 32;
+
+    pub const ZERO: Self = Self([0, 0, 0, 0]);
+
+    pub const ONE: Self = Self(Number::ONE.0);
+
+    /// Build from a magnitude and a sign, failing if the magnitude does not fit in 255 bits
+    pub fn from_parts(magnitude: Number, negative: bool) -> Option<Self> {
+        let value = U256(magnitude.0);
+        if value.bit(255) {
+            return None;
+        }
+
+        if negative {
+            Some(Self((!value).overflowing_add(U256::one()).0 .0))
+        } else {
+            Some(Self(value.0))
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        U256(self.0).bit(255)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// Absolute value as an unsigned Number
+    pub fn abs(&self) -> Number {
+        let value = U256(self.0);
+        if self.is_negative() {
+            Number((!value).overflowing_add(U256::one()).0 .0)
+        } else {
+            Number(value.0)
+        }
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Self::from_parts(self.abs(), !self.is_negative())
+    }
+
+    pub fn checked_add(&self, x: &Self) -> Option<Self> {
+        let (value, _) = U256(self.0).overflowing_add(U256(x.0));
+        let sum = Self(value.0);
+
+        // overflow only happens when both operands have the same sign, and the sum has the other sign
+        if self.is_negative() == x.is_negative() && sum.is_negative() != self.is_negative() {
+            return None;
+        }
+
+        Some(sum)
+    }
+
+    pub fn checked_sub(&self, x: &Self) -> Option<Self> {
+        self.checked_add(&x.checked_neg()?)
+    }
+
+    /// Multiply, rounding the magnitude the same way as Number
+    pub fn checked_mul(&self, x: &Self) -> Option<Self> {
+        let magnitude = self.abs().checked_mul(&x.abs())?;
+        Self::from_parts(magnitude, self.is_negative() != x.is_negative())
+    }
+
+    /// Divide, rounding the magnitude the same way as Number
+    pub fn checked_div(&self, x: &Self) -> Option<Self> {
+        let magnitude = self.abs().checked_div(&x.abs())?;
+        Self::from_parts(magnitude, self.is_negative() != x.is_negative())
+    }
+
+    /// Round toward -infinity
+    pub fn floor(&self) -> Self {
+        let (int, has_frac) = self.split_magnitude();
+        let int = if self.is_negative() && has_frac {
+            int + U256::one()
+        } else {
+            int
+        };
+
+        Self::from_parts(
+            Number((int * U256::from(precise_number::ONE)).0),
+            self.is_negative(),
+        )
+        .expect("SignedNumber floor overflow")
+    }
+
+    /// Round toward +infinity
+    pub fn ceil(&self) -> Self {
+        self.checked_neg()
+            .and_then(|n| n.floor().checked_neg())
+            .expect("SignedNumber ceil overflow")
+    }
+
+    /// Round toward -infinity, and convert to i64
+    pub fn floor_i64(&self) -> i64 {
+        self.floor()
+            .to_i64()
+            .expect("SignedNumber too large for i64")
+    }
+
+    /// Round toward +infinity, and convert to i64
+    pub fn ceil_i64(&self) -> i64 {
+        self.ceil()
+            .to_i64()
+            .expect("SignedNumber too large for i64")
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        let magnitude = self.abs().to_f64()?;
+        if self.is_negative() {
+            Some(-magnitude)
+        } else {
+            Some(magnitude)
+        }
+    }
+
+    /// Integer part and whether there is a fractional part, of the magnitude
+    fn split_magnitude(&self) -> (U256, bool) {
+        let magnitude = U256(self.abs().0);
+        let one = U256::from(precise_number::ONE);
+        (magnitude / one, !(magnitude % one).is_zero())
+    }
+
+    /// Convert a whole number to i64
+    fn to_i64(self) -> Option<i64> {
+        let (int, _) = self.split_magnitude();
+        if int.bits() > 64 {
+            return None;
+        }
+
+        let int = int.as_u64();
+        if self.is_negative() {
+            0i64.checked_sub_unsigned(int)
+        } else {
+            int.try_into().ok()
+        }
+    }
+}
+
+impl Add<SignedNumber> for SignedNumber {
+    type Output = Self;
+
+    fn add(self, rhs: SignedNumber) -> Self {
+        self.checked_add(&rhs).unwrap()
+    }
+}
+
+impl Sub<SignedNumber> for SignedNumber {
+    type Output = Self;
+
+    fn sub(self, rhs: SignedNumber) -> Self {
+        self.checked_sub(&rhs).unwrap()
+    }
+}
+
+impl Mul<SignedNumber> for SignedNumber {
+    type Output = Self;
+
+    fn mul(self, rhs: SignedNumber) -> Self {
+        self.checked_mul(&rhs).unwrap()
+    }
+}
+
+impl Div<SignedNumber> for SignedNumber {
+    type Output = Self;
+
+    fn div(self, rhs: SignedNumber) -> Self {
+        self.checked_div(&rhs).unwrap()
+    }
+}
+
+impl Neg for SignedNumber {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().unwrap()
+    }
+}
+
+impl PartialOrd for SignedNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SignedNumber {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .is_negative()
+            .cmp(&self.is_negative())
+            .then_with(|| U256(self.0).cmp(&U256(other.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn n(num: u128, den: u128) -> Number {
+        Number::from_ratio(num, den)
+    }
+
+    #[test]
+    fn rounds_toward_infinities() {
+        let x = SignedNumber::from(n(845, 100));
+        assert_eq!(x.floor_i64(), 8);
+        assert_eq!(x.ceil_i64(), 9);
+        assert_eq!((-x).floor_i64(), -9);
+        assert_eq!((-x).ceil_i64(), -8);
+
+        let whole = SignedNumber::from(-7i64);
+        assert_eq!(whole.floor_i64(), -7);
+        assert_eq!(whole.ceil_i64(), -7);
+    }
+
+    #[test]
+    fn arithmetic_and_ordering() {
+        let a = SignedNumber::from(-3i64);
+        let b = SignedNumber::from(5i64);
+
+        assert_eq!(a + b, SignedNumber::from(2i64));
+        assert_eq!(a - b, SignedNumber::from(-8i64));
+        assert_eq!(a * b, SignedNumber::from(-15i64));
+        assert_eq!(a * a, SignedNumber::from(9i64));
+        assert_eq!((b / a).floor_i64(), -2);
+        assert!(a < SignedNumber::ZERO && SignedNumber::ZERO < b && a < b);
+        assert_eq!(a.abs(), Number::from_natural_u64(3));
+        assert!(Number::try_from(a).is_err());
+        assert_eq!(Number::try_from(b), Ok(Number::from_natural_u64(5)));
+    }
+
+    #[test]
+    fn checks_overflow() {
+        let max = SignedNumber::from_parts(Number(((U256::one() << 255) - 1).0), false).unwrap();
+        assert!(SignedNumber::from_parts(Number((U256::one() << 255).0), false).is_none());
+        assert!(max.checked_add(&SignedNumber::ONE).is_none());
+        assert!((-max).checked_sub(&SignedNumber::ONE).is_none());
+        assert_eq!(SignedNumber::from(i64::MIN).floor_i64(), i64::MIN);
+    }
+}
@@ -0,0 +1,39 @@
+use exponent_core::state::MarketFinancials;
+use exponent_time_curve::analytics;
+use precise_number::Number;
+
+pub use exponent_time_curve::analytics::{MarketRateRisk, RateRisk};
+
+use crate::Simulator;
+
+/// Rate risk of a market's PT, YT and LP tokens, priced in the underlying asset
+pub fn market_rate_risk(
+    financials: &MarketFinancials,
+    sy_exchange_rate: Number,
+    now: u64,
+    lp_supply: u64,
+) -> MarketRateRisk {
+    let asset_balance =
+        (Number::from_natural_u64(financials.sy_balance) * sy_exchange_rate).floor_u64();
+
+    analytics::market_rate_risk(
+        financials.pt_balance,
+        asset_balance,
+        lp_supply,
+        financials.rate_scalar_root,
+        financials.last_ln_implied_rate,
+        financials.expiration_ts.saturating_sub(now),
+    )
+}
+
+impl Simulator {
+    /// Rate risk of the market at the current timestamp and SY exchange rate
+    pub fn rate_risk(&self) -> MarketRateRisk {
+        market_rate_risk(
+            &self.market.financials,
+            self.sy_exchange_rate(),
+            self.now(),
+            self.lp_supply,
+        )
+    }
+}
@@ -0,0 +1,116 @@
+//! Off-chain replay of Exponent market and vault instructions.
+//!
+//! State is loaded from account snapshots and mutated with the same functions the program uses,
+//! so results match the program down to the rounding.
+//!
+//! Each operation is applied atomically: if it fails, the simulator state is left untouched.
+//! Failed checks are returned as the same errors the program returns.
+
+use anchor_lang::prelude::*;
+use exponent_core::state::{MarketTwo, Vault, YieldTokenPosition};
+use precise_number::Number;
+use sy_common::SyState;
+
+mod analytics;
+mod market;
+mod vault;
+
+pub use analytics::*;
+pub use market::*;
+pub use vault::*;
+
+/// Market, vault and SY state needed to replay market and vault instructions
+#[derive(Clone)]
+pub struct Simulator {
+    pub market: MarketTwo,
+
+    pub vault: Vault,
+
+    /// The vault's own yield position, which tracks YT not deposited by users
+    pub vault_yield_position: YieldTokenPosition,
+
+    /// Supply of the market's LP mint
+    pub lp_supply: u64,
+
+    /// State returned by the SY program
+    pub sy_state: SyState,
+
+    /// Unix timestamp the program would read from the clock
+    pub unix_timestamp: u32,
+}
+
+impl Simulator {
+    pub fn new(
+        market: MarketTwo,
+        vault: Vault,
+        vault_yield_position: YieldTokenPosition,
+        lp_supply: u64,
+        sy_state: SyState,
+        unix_timestamp: u32,
+    ) -> Self {
+        Self {
+            market,
+            vault,
+            vault_yield_position,
+            lp_supply,
+            sy_state,
+            unix_timestamp,
+        }
+    }
+
+    /// Build a simulator from raw account data
+    /// - `market`, `vault` and `vault_yield_position` are account data, including the discriminator
+    /// - `sy_state` is the borsh encoded return data of the SY program's get_sy_state
+    pub fn from_snapshots(
+        market: &[u8],
+        vault: &[u8],
+        vault_yield_position: &[u8],
+        lp_supply: u64,
+        sy_state: &[u8],
+        unix_timestamp: u32,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            load_account(market)?,
+            load_account(vault)?,
+            load_account(vault_yield_position)?,
+            lp_supply,
+            load_sy_state(sy_state)?,
+            unix_timestamp,
+        ))
+    }
+
+    pub fn sy_exchange_rate(&self) -> Number {
+        self.sy_state.exchange_rate
+    }
+
+    /// Set the SY state, eg. to replay an exchange rate change
+    pub fn set_sy_state(&mut self, sy_state: SyState) {
+        self.sy_state = sy_state;
+    }
+
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: u32) {
+        self.unix_timestamp = unix_timestamp;
+    }
+
+    fn now(&self) -> u64 {
+        self.unix_timestamp as u64
+    }
+
+    /// Run an operation against a copy of the state, and keep the result only if it succeeds
+    fn transact<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let mut next = self.clone();
+        let r = f(&mut next)?;
+        *self = next;
+        Ok(r)
+    }
+}
+
+/// Deserialize an account from its data, including the discriminator
+pub fn load_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Deserialize the SY state returned by the SY program
+pub fn load_sy_state(data: &[u8]) -> Result<SyState> {
+    SyState::try_from_slice(data).map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+}
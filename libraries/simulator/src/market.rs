@@ -0,0 +1,617 @@
+use anchor_lang::prelude::*;
+use exponent_core::{
+    error::ExponentCoreError,
+    handlers::{
+        handle_buy_yt_borrow, handle_buy_yt_exact_sy_in, handle_buy_yt_repay, handle_pt_to_sell,
+        handle_sell_yt_borrow, handle_sell_yt_leftover, handle_trade_pt, handle_trade_pt_exact_sy,
+        handle_trade_pt_to_rate, validate_buy_yt, validate_deposit_liquidity, validate_sell_yt,
+        validate_trade_direction, validate_trade_pt_exact_sy, validate_withdraw_liquidity,
+    },
+    state::*,
+};
+use precise_number::Number;
+use sy_common::PositionState;
+
+use crate::Simulator;
+
+/// Result of buying YT, mirroring the amounts in the program's BuyYtEvent
+#[derive(Debug, Clone, Copy)]
+pub struct BuyYtResult {
+    pub yt_out: u64,
+    pub sy_to_strip: u64,
+    pub sy_borrowed: u64,
+    pub pt_out: u64,
+    pub sy_repaid: u64,
+}
+
+/// Result of selling YT, mirroring the amounts in the program's SellYtEvent
+#[derive(Debug, Clone, Copy)]
+pub struct SellYtResult {
+    pub amount_sy_received_from_merge: u64,
+    pub amount_sy_spent_buying_pt: u64,
+    pub amount_sy_out: u64,
+}
+
+impl Simulator {
+    /// Trade PT against the market
+    /// - `net_trader_pt` is positive when buying PT and negative when selling PT
+    /// - `sy_constraint` is the max SY spent (negative) when buying, or min SY received when selling
+    pub fn trade_pt(&mut self, net_trader_pt: i64, sy_constraint: i64) -> Result<TradeResult> {
+        self.transact(|s| s.do_trade_pt(net_trader_pt, sy_constraint))
+    }
+
+    /// Buy PT with an exact amount of SY
+    pub fn trade_pt_exact_sy(&mut self, sy_in: u64, min_pt_out: u64) -> Result<TradeResult> {
+        self.transact(|s| {
+            validate_trade_pt_exact_sy(&s.market)?;
+
+            let now = s.now();
+            require!(s.market.is_active(now), ExponentCoreError::MarketExpired);
+
+            let (net_trader_pt, sy_constraint) =
+                handle_trade_pt_exact_sy(&s.market, s.sy_exchange_rate(), now, sy_in, min_pt_out)?;
+
+            s.do_trade_pt(net_trader_pt, sy_constraint)
+        })
+    }
+
+    /// Trade PT until the market's implied rate reaches `target_ln_implied_rate`
+    /// - `price_limit` is the most SY paid per PT when buying, or the least SY received per PT when selling
+    pub fn trade_pt_to_rate(
+        &mut self,
+        target_ln_implied_rate: f64,
+        price_limit: Number,
+    ) -> Result<TradeResult> {
+        self.transact(|s| {
+            let now = s.now();
+            require!(s.market.is_active(now), ExponentCoreError::MarketExpired);
+
+            let (net_trader_pt, sy_constraint) = handle_trade_pt_to_rate(
+                &s.market,
+                s.sy_exchange_rate(),
+                now,
+                target_ln_implied_rate,
+                price_limit,
+            )?;
+
+            s.do_trade_pt(net_trader_pt, sy_constraint)
+        })
+    }
+
+    /// Buy an exact amount of YT, spending at most `sy_in`
+    pub fn buy_yt(&mut self, sy_in: u64, yt_out: u64) -> Result<BuyYtResult> {
+        self.transact(|s| {
+            validate_buy_yt(&s.market)?;
+            s.do_buy_yt(sy_in, yt_out)
+        })
+    }
+
+    /// Buy as much YT as an exact amount of SY allows
+    pub fn buy_yt_exact_sy_in(&mut self, sy_in: u64, min_yt_out: u64) -> Result<BuyYtResult> {
+        self.transact(|s| {
+            validate_buy_yt(&s.market)?;
+
+            let now = s.now();
+            require!(s.market.is_active(now), ExponentCoreError::MarketExpired);
+
+            let (sy_spend, yt_out) =
+                handle_buy_yt_exact_sy_in(&s.market, s.sy_exchange_rate(), now, sy_in, min_yt_out)?;
+
+            s.do_buy_yt(sy_spend, yt_out)
+        })
+    }
+
+    /// Sell YT for SY
+    pub fn sell_yt(&mut self, yt_in: u64, min_sy_out: u64) -> Result<SellYtResult> {
+        self.transact(|s| {
+            validate_sell_yt(&s.market)?;
+
+            handle_sell_yt_borrow(&s.market, yt_in)?;
+
+            let sy_recv = s.do_merge(yt_in)?;
+
+            let sy_constraint: i64 = sy_recv
+                .try_into()
+                .map_err(|_| ExponentCoreError::MathOverflow)?;
+            let pt_buy: i64 = yt_in
+                .try_into()
+                .map_err(|_| ExponentCoreError::MathOverflow)?;
+
+            s.market.is_current_flash_swap = true;
+            let trade_result = s.do_trade_pt(pt_buy, -sy_constraint)?;
+            s.market.is_current_flash_swap = false;
+
+            let sy_spent = trade_result.net_trader_sy.unsigned_abs();
+
+            let sy_leftover = handle_sell_yt_leftover(sy_recv, sy_spent, min_sy_out)?;
+
+            Ok(SellYtResult {
+                amount_sy_received_from_merge: sy_recv,
+                amount_sy_spent_buying_pt: sy_spent,
+                amount_sy_out: sy_leftover,
+            })
+        })
+    }
+
+    /// Deposit PT & SY into the market for LP tokens
+    pub fn deposit_liquidity(
+        &mut self,
+        pt_intent: u64,
+        sy_intent: u64,
+        min_lp_out: u64,
+    ) -> Result<LiqAddResult> {
+        self.transact(|s| {
+            validate_deposit_liquidity(&s.market, s.now())?;
+
+            let curve_kind = s.market.curve_kind;
+            let r =
+                s.market
+                    .financials
+                    .add_liquidity(curve_kind, sy_intent, pt_intent, s.lp_supply)?;
+
+            if r.lp_out < min_lp_out {
+                return Err(ExponentCoreError::MinLpOutNotMet.into());
+            }
+
+            s.market.liquidity_net_balance_limits.verify_limits(
+                s.unix_timestamp,
+                s.lp_supply,
+                r.lp_out as i64,
+            )?;
+
+            s.lp_supply = s
+                .lp_supply
+                .checked_add(r.lp_out)
+                .ok_or(ExponentCoreError::MathOverflow)?;
+
+            if !s.market.check_supply_lp(s.lp_supply) {
+                return Err(ExponentCoreError::LpSupplyMaximumExceeded.into());
+            }
+
+            Ok(r)
+        })
+    }
+
+    /// Provide liquidity with PT only, selling part of it for SY first
+    /// Staking the LP tokens is left to `deposit_lp`
+    pub fn deposit_liquidity_single_pt(
+        &mut self,
+        amount_pt: u64,
+        min_lp_out: u64,
+    ) -> Result<LiqAddResult> {
+        self.transact(|s| {
+            let now = s.now();
+            validate_deposit_liquidity(&s.market, now)?;
+
+            let pt_to_sell = handle_pt_to_sell(&s.market, s.sy_exchange_rate(), now, amount_pt)?;
+
+            let pt_sell: i64 = pt_to_sell
+                .try_into()
+                .map_err(|_| ExponentCoreError::MathOverflow)?;
+
+            let trade = s.do_trade_pt(-pt_sell, 0)?;
+
+            s.deposit_liquidity(
+                amount_pt - pt_to_sell,
+                trade.net_trader_sy as u64,
+                min_lp_out,
+            )
+        })
+    }
+
+    /// Burn LP tokens for PT & SY
+    pub fn withdraw_liquidity(
+        &mut self,
+        lp_in: u64,
+        min_pt_out: u64,
+        min_sy_out: u64,
+    ) -> Result<LiqRmResult> {
+        self.transact(|s| {
+            validate_withdraw_liquidity(&mut s.market, s.unix_timestamp, s.lp_supply, lp_in)?;
+
+            let curve_kind = s.market.curve_kind;
+            let r = s
+                .market
+                .financials
+                .rm_liquidity(curve_kind, lp_in, s.lp_supply)?;
+
+            if r.sy_out < min_sy_out {
+                return Err(ExponentCoreError::MinSyOutNotMet.into());
+            }
+
+            if r.pt_out < min_pt_out {
+                return Err(ExponentCoreError::MinPtOutNotMet.into());
+            }
+
+            s.lp_supply -= lp_in;
+
+            Ok(r)
+        })
+    }
+
+    /// Stake LP tokens into a position
+    /// - `position_state` is the market's position in the SY program, used to index SY emissions
+    pub fn deposit_lp(
+        &mut self,
+        lp_position: &mut LpPosition,
+        position_state: &PositionState,
+        amount: u64,
+    ) -> Result<()> {
+        self.stage_lp_position(lp_position, position_state)?;
+
+        self.market.lp_escrow_amount = self
+            .market
+            .lp_escrow_amount
+            .checked_add(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        lp_position.add_lp(amount)
+    }
+
+    /// Unstake LP tokens from a position
+    /// - `position_state` is the market's position in the SY program, used to index SY emissions
+    pub fn withdraw_lp(
+        &mut self,
+        lp_position: &mut LpPosition,
+        position_state: &PositionState,
+        amount: u64,
+    ) -> Result<()> {
+        self.stage_lp_position(lp_position, position_state)?;
+
+        lp_position.rm_lp(amount)?;
+
+        self.market.lp_escrow_amount = self
+            .market
+            .lp_escrow_amount
+            .checked_sub(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    fn stage_lp_position(
+        &mut self,
+        lp_position: &mut LpPosition,
+        position_state: &PositionState,
+    ) -> Result<()> {
+        let current_lp_escrow_amount = self.market.lp_escrow_amount;
+        self.market
+            .update_emissions_from_position_state(position_state, current_lp_escrow_amount);
+
+        self.market
+            .lp_farm
+            .increase_share_indexes(self.unix_timestamp, current_lp_escrow_amount);
+
+        lp_position.stage_all(
+            &self.market.emissions.get_last_seen_indices(),
+            &self.market.lp_farm.get_last_seen_indices(),
+        )
+    }
+
+    /// Borrow, strip, and sell the PT back into the market, as buy_yt does
+    fn do_buy_yt(&mut self, sy_in: u64, yt_out: u64) -> Result<BuyYtResult> {
+        let (sy_to_strip, sy_to_borrow) =
+            handle_buy_yt_borrow(self.sy_exchange_rate(), sy_in, yt_out)?;
+
+        let pt_out = self.do_strip(sy_to_strip)?;
+
+        self.market.is_current_flash_swap = true;
+        let pt_sell: i64 = pt_out
+            .try_into()
+            .map_err(|_| ExponentCoreError::MathOverflow)?;
+        let sy_min_out: i64 = sy_to_borrow
+            .try_into()
+            .map_err(|_| ExponentCoreError::MathOverflow)?;
+        let trade_result = self.do_trade_pt(-pt_sell, sy_min_out)?;
+        self.market.is_current_flash_swap = false;
+
+        let received_sy = trade_result.net_trader_sy as u64;
+
+        // the leftover dust is gifted to the market's escrow, without changing its tracked balance
+        let sy_repaid = handle_buy_yt_repay(received_sy, sy_to_borrow)?;
+
+        Ok(BuyYtResult {
+            yt_out,
+            sy_to_strip,
+            sy_borrowed: sy_to_borrow,
+            pt_out,
+            sy_repaid,
+        })
+    }
+
+    fn do_trade_pt(&mut self, net_trader_pt: i64, sy_constraint: i64) -> Result<TradeResult> {
+        validate_trade_direction(&self.market, net_trader_pt)?;
+
+        let now = self.now();
+        require!(self.market.is_active(now), ExponentCoreError::MarketExpired);
+
+        let sy_exchange_rate = self.sy_exchange_rate();
+        handle_trade_pt(
+            &mut self.market,
+            sy_exchange_rate,
+            now,
+            net_trader_pt,
+            sy_constraint,
+        )
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use exponent_core::utils::py_to_sy;
+    use sy_common::SyState;
+
+    const NOW: u32 = 1_700_000_000;
+    const SEC_REMAINING: u32 = 180 * 86_400;
+
+    fn sy_exchange_rate() -> Number {
+        Number::from_ratio(11, 10)
+    }
+
+    fn market() -> MarketTwo {
+        MarketTwo {
+            address_lookup_table: Pubkey::default(),
+            mint_pt: Pubkey::default(),
+            mint_sy: Pubkey::default(),
+            vault: Pubkey::default(),
+            mint_lp: Pubkey::default(),
+            token_lp_escrow: Pubkey::default(),
+            token_pt_escrow: Pubkey::default(),
+            token_sy_escrow: Pubkey::default(),
+            token_fee_treasury_sy: Pubkey::default(),
+            fee_treasury_sy_bps: 2_000,
+            self_address: Pubkey::default(),
+            signer_bump: [0],
+            status_flags: ALL_FLAGS,
+            sy_program: Pubkey::default(),
+            financials: MarketFinancials {
+                expiration_ts: (NOW + SEC_REMAINING) as u64,
+                pt_balance: 1_000_000_000_000,
+                sy_balance: 800_000_000_000,
+                ln_fee_rate_root: 0.001,
+                last_ln_implied_rate: 0.08,
+                rate_scalar_root: 50.0,
+            },
+            emissions: MarketEmissions::default(),
+            lp_farm: LpFarm::default(),
+            max_lp_supply: u64::MAX,
+            lp_escrow_amount: 0,
+            cpi_accounts: Default::default(),
+            is_current_flash_swap: false,
+            liquidity_net_balance_limits: LiquidityNetBalanceLimits {
+                max_net_balance_change_negative_percentage: 10000,
+                max_net_balance_change_positive_percentage: u32::MAX,
+                window_start_timestamp: NOW,
+                window_duration_seconds: 0,
+                window_start_net_balance: 0,
+            },
+            seed_id: [1],
+            curve_kind: CurveKind::Logit,
+            dynamic_fee: DynamicFee::default(),
+            yt_fee: None,
+            rate_oracle: RateOracle::default(),
+        }
+    }
+
+    pub(crate) fn simulator() -> Simulator {
+        let vault = Vault {
+            start_ts: NOW - 1_000,
+            duration: SEC_REMAINING + 1_000,
+            last_seen_sy_exchange_rate: sy_exchange_rate(),
+            all_time_high_sy_exchange_rate: sy_exchange_rate(),
+            final_sy_exchange_rate: sy_exchange_rate(),
+            status: STATUS_CAN_STRIP | STATUS_CAN_MERGE,
+            max_py_supply: u64::MAX,
+            ..Default::default()
+        };
+
+        Simulator::new(
+            market(),
+            vault,
+            YieldTokenPosition::default(),
+            1_000_000_000_000,
+            SyState {
+                exchange_rate: sy_exchange_rate(),
+                emission_indexes: vec![],
+            },
+            NOW,
+        )
+    }
+
+    /// The market after trading directly against its state, with the PT amount the program settled
+    fn expected_market(
+        market: &MarketTwo,
+        net_trader_pt: i64,
+        is_current_flash_swap: bool,
+    ) -> (MarketTwo, TradeResult) {
+        let mut market = market.clone();
+        let now = NOW as u64;
+
+        market.record_rate_observation(now);
+        let fees = market.trade_fees(now);
+        let trade_result = market
+            .financials
+            .trade_pt(
+                market.curve_kind,
+                sy_exchange_rate(),
+                net_trader_pt,
+                now,
+                is_current_flash_swap,
+                fees,
+            )
+            .unwrap();
+
+        (market, trade_result)
+    }
+
+    fn assert_same_financials(actual: &MarketTwo, expected: &MarketTwo) {
+        assert_eq!(actual.financials.pt_balance, expected.financials.pt_balance);
+        assert_eq!(actual.financials.sy_balance, expected.financials.sy_balance);
+        assert_eq!(
+            actual.financials.last_ln_implied_rate,
+            expected.financials.last_ln_implied_rate
+        );
+        assert!(!actual.is_current_flash_swap);
+    }
+
+    #[test]
+    fn trade_pt_matches_market_state() {
+        for net_trader_pt in [1_000_000, 5_000_000_000, -1_000_000, -5_000_000_000] {
+            let mut sim = simulator();
+            let (market, expected) = expected_market(&sim.market, net_trader_pt, false);
+
+            let trade = sim.trade_pt(net_trader_pt, i64::MIN).unwrap();
+
+            assert_eq!(trade, expected);
+            assert_same_financials(&sim.market, &market);
+        }
+    }
+
+    #[test]
+    fn trade_pt_exact_sy_matches_market_state() {
+        for sy_in in [1_000_000, 5_000_000_000] {
+            let mut sim = simulator();
+            let now = NOW as u64;
+            let pt_out = sim.market.financials.pt_out_for_sy_in(
+                sim.market.curve_kind,
+                sy_exchange_rate(),
+                sy_in,
+                now,
+                false,
+                sim.market.trade_fees(now),
+            );
+            let (market, expected) = expected_market(&sim.market, pt_out as i64, false);
+
+            let trade = sim.trade_pt_exact_sy(sy_in, pt_out).unwrap();
+
+            assert_eq!(trade, expected);
+            assert!(trade.net_trader_sy.unsigned_abs() <= sy_in);
+            assert_same_financials(&sim.market, &market);
+        }
+    }
+
+    #[test]
+    fn trade_pt_to_rate_matches_market_state() {
+        for target_ln_implied_rate in [0.06, 0.1] {
+            let mut sim = simulator();
+            let net_trader_pt = sim.market.financials.net_trader_pt_for_ln_implied_rate(
+                sim.market.curve_kind,
+                sy_exchange_rate(),
+                target_ln_implied_rate,
+                NOW as u64,
+                false,
+            );
+            let (market, expected) = expected_market(&sim.market, net_trader_pt, false);
+
+            let price_limit = if net_trader_pt > 0 {
+                Number::from_natural_u64(1)
+            } else {
+                Number::ZERO
+            };
+            let trade = sim
+                .trade_pt_to_rate(target_ln_implied_rate, price_limit)
+                .unwrap();
+
+            assert_eq!(trade, expected);
+            assert_same_financials(&sim.market, &market);
+            // off by the treasury's cut of the fee, which the solve leaves in the pool
+            let ln_implied_rate = sim.market.financials.last_ln_implied_rate;
+            assert!((ln_implied_rate - target_ln_implied_rate).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn buy_yt_exact_sy_in_matches_market_state() {
+        for sy_in in [10_000_000, 1_000_000_000] {
+            let mut sim = simulator();
+            let now = NOW as u64;
+            let fees = sim.market.trade_fees(now);
+            let financials = &sim.market.financials;
+            let yt_out = financials.yt_out_for_sy_in(
+                sim.market.curve_kind,
+                sy_exchange_rate(),
+                sy_in,
+                now,
+                fees,
+            );
+            let sy_spend = financials
+                .sy_in_for_yt_out(sim.market.curve_kind, sy_exchange_rate(), yt_out, now, fees)
+                .unwrap();
+
+            let r = sim.buy_yt_exact_sy_in(sy_in, yt_out).unwrap();
+            let (market, expected) = expected_market(&market(), -(r.pt_out as i64), true);
+
+            assert_eq!(r.yt_out, yt_out);
+            assert_eq!(r.sy_to_strip - r.sy_borrowed, sy_spend);
+            assert!(sy_spend <= sy_in);
+            assert_eq!(r.sy_repaid, expected.net_trader_sy as u64);
+            assert_same_financials(&sim.market, &market);
+            assert_eq!(sim.vault_yield_position.yt_balance, r.pt_out);
+        }
+    }
+
+    #[test]
+    fn sell_yt_matches_market_state() {
+        let yt_in = 2_000_000_000;
+
+        let mut sim = simulator();
+        sim.strip(py_to_sy(sy_exchange_rate(), 3 * yt_in)).unwrap();
+        let (market, expected) = expected_market(&sim.market, yt_in as i64, true);
+
+        let r = sim.sell_yt(yt_in, 0).unwrap();
+
+        assert_eq!(
+            r.amount_sy_spent_buying_pt,
+            expected.net_trader_sy.unsigned_abs()
+        );
+        assert_eq!(
+            r.amount_sy_out,
+            r.amount_sy_received_from_merge - r.amount_sy_spent_buying_pt
+        );
+        assert_same_financials(&sim.market, &market);
+    }
+
+    #[test]
+    fn deposit_liquidity_single_pt_matches_market_state() {
+        let amount_pt = 1_000_000_000;
+
+        let mut sim = simulator();
+        let pt_to_sell =
+            handle_pt_to_sell(&sim.market, sy_exchange_rate(), NOW as u64, amount_pt).unwrap();
+        let (mut market, trade) = expected_market(&sim.market, -(pt_to_sell as i64), false);
+        let expected = market
+            .financials
+            .add_liquidity(
+                market.curve_kind,
+                trade.net_trader_sy as u64,
+                amount_pt - pt_to_sell,
+                sim.lp_supply,
+            )
+            .unwrap();
+
+        let r = sim.deposit_liquidity_single_pt(amount_pt, 0).unwrap();
+
+        assert_eq!(r.lp_out, expected.lp_out);
+        assert_eq!(r.pt_in, expected.pt_in);
+        assert_eq!(r.sy_in, expected.sy_in);
+        assert_same_financials(&sim.market, &market);
+    }
+
+    #[test]
+    fn failed_operations_leave_state_untouched() {
+        let mut sim = simulator();
+        let before = sim.market.financials.clone();
+
+        assert!(sim.trade_pt_exact_sy(1_000_000, u64::MAX).is_err());
+        assert!(sim.buy_yt_exact_sy_in(1_000_000, u64::MAX).is_err());
+        assert!(sim.sell_yt(1_000_000, 0).is_err());
+
+        assert_eq!(sim.market.financials.pt_balance, before.pt_balance);
+        assert_eq!(sim.market.financials.sy_balance, before.sy_balance);
+        assert_eq!(
+            sim.market.financials.last_ln_implied_rate,
+            before.last_ln_implied_rate
+        );
+    }
+}
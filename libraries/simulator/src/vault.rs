@@ -0,0 +1,402 @@
+use amount_value::Amount;
+use anchor_lang::prelude::*;
+use exponent_core::{
+    handlers::{
+        handle_collect_interest, handle_compound_interest, handle_deposit_yt,
+        handle_emergency_withdraw_interest, handle_emergency_withdraw_pt, handle_merge,
+        handle_redeem_pt, handle_split_yield_position, handle_stage_yt_yield, handle_strip,
+        handle_withdraw_yt, validate_collect_interest, validate_compound_interest,
+        validate_deposit_yt, validate_emergency_withdraw, validate_merge, validate_redeem_pt,
+        validate_split_yield_position, validate_strip, validate_withdraw_yt,
+        CompoundInterestAmounts,
+    },
+    state::*,
+};
+
+use crate::Simulator;
+
+/// Result of collecting interest from a yield position
+#[derive(Debug, Clone, Copy)]
+pub struct CollectInterestResult {
+    pub amount_to_user: u64,
+    pub amount_to_treasury: u64,
+}
+
+impl Simulator {
+    /// Strip SY into PT & YT
+    /// Returns the amount of PT & YT minted
+    pub fn strip(&mut self, amount_sy: u64) -> Result<u64> {
+        self.transact(|s| s.do_strip(amount_sy))
+    }
+
+    /// Merge PT & YT into SY
+    /// Returns the amount of SY redeemed
+    pub fn merge(&mut self, amount_py: u64) -> Result<u64> {
+        self.transact(|s| s.do_merge(amount_py))
+    }
+
+    /// Redeem PT alone for SY once the vault has expired
+    /// Returns the amount of SY redeemed
+    pub fn redeem_pt(&mut self, amount_pt: u64) -> Result<u64> {
+        self.transact(|s| {
+            validate_redeem_pt(&s.vault, s.unix_timestamp, amount_pt)?;
+
+            handle_redeem_pt(&mut s.vault, s.unix_timestamp, &s.sy_state, amount_pt)
+        })
+    }
+
+    /// Stage the yield earned by a user's YT position
+    pub fn stage_yield(&mut self, user_yield_position: &mut YieldTokenPosition) -> Result<()> {
+        with_position(user_yield_position, |user| {
+            self.transact(|s| {
+                handle_stage_yt_yield(
+                    &mut s.vault,
+                    &mut s.vault_yield_position,
+                    user,
+                    &s.sy_state,
+                    s.unix_timestamp,
+                )
+            })
+        })
+    }
+
+    /// Move YT from the vault's position into a user's position
+    pub fn deposit_yt(
+        &mut self,
+        user_yield_position: &mut YieldTokenPosition,
+        amount: u64,
+    ) -> Result<()> {
+        with_position(user_yield_position, |user| {
+            self.transact(|s| {
+                validate_deposit_yt(&s.vault, s.unix_timestamp)?;
+
+                handle_deposit_yt(
+                    &mut s.vault,
+                    &mut s.vault_yield_position,
+                    user,
+                    &s.sy_state,
+                    s.unix_timestamp,
+                    amount,
+                )
+            })
+        })
+    }
+
+    /// Move YT from a user's position back to the vault's position
+    pub fn withdraw_yt(
+        &mut self,
+        user_yield_position: &mut YieldTokenPosition,
+        amount: u64,
+    ) -> Result<()> {
+        with_position(user_yield_position, |user| {
+            self.transact(|s| {
+                validate_withdraw_yt(&s.vault, s.unix_timestamp)?;
+
+                handle_withdraw_yt(
+                    &mut s.vault,
+                    &mut s.vault_yield_position,
+                    user,
+                    &s.sy_state,
+                    s.unix_timestamp,
+                    amount,
+                )
+            })
+        })
+    }
+
+    /// Move `amount` YT, with the same share of staged earnings, from one user's position into another's
+    pub fn split_yield_position(
+        &mut self,
+        src_yield_position: &mut YieldTokenPosition,
+        dst_yield_position: &mut YieldTokenPosition,
+        amount: u64,
+    ) -> Result<()> {
+        with_position(src_yield_position, |src| {
+            with_position(dst_yield_position, |dst| {
+                self.transact(|s| {
+                    validate_split_yield_position(&s.vault)?;
+
+                    handle_split_yield_position(
+                        &mut s.vault,
+                        &mut s.vault_yield_position,
+                        src,
+                        dst,
+                        &s.sy_state,
+                        s.unix_timestamp,
+                        amount,
+                    )
+                })
+            })
+        })
+    }
+
+    /// Collect staged interest from a user's YT position
+    /// Like the program, this does not stage new yield; call `stage_yield` first
+    pub fn collect_interest(
+        &mut self,
+        user_yield_position: &mut YieldTokenPosition,
+        amount: Amount,
+    ) -> Result<CollectInterestResult> {
+        with_position(user_yield_position, |user| {
+            self.transact(|s| {
+                validate_collect_interest(&s.vault)?;
+
+                let amount_staged = amount.to_u64(user.interest.staged)?;
+
+                s.vault.claim_limits.verify_claim_limits(
+                    s.vault.staged_interest_sy(amount_staged),
+                    s.unix_timestamp,
+                )?;
+
+                user.earn_all_with_tracking(&mut s.vault)?;
+
+                let (amount_to_user, amount_to_treasury) =
+                    handle_collect_interest(&mut s.vault, user, amount_staged)?;
+
+                Ok(CollectInterestResult {
+                    amount_to_user,
+                    amount_to_treasury,
+                })
+            })
+        })
+    }
+
+    /// Burn PT, with its YT while the vault is active, without refreshing the SY state
+    /// Returns the amount of SY withdrawn
+    pub fn emergency_withdraw_pt(&mut self, amount_pt: u64) -> Result<u64> {
+        self.transact(|s| {
+            validate_emergency_withdraw(&s.vault)?;
+
+            handle_emergency_withdraw_pt(
+                &mut s.vault,
+                &mut s.vault_yield_position,
+                s.unix_timestamp,
+                amount_pt,
+            )
+        })
+    }
+
+    /// Withdraw staged interest from a user's YT position without refreshing the SY state
+    pub fn emergency_withdraw_interest(
+        &mut self,
+        user_yield_position: &mut YieldTokenPosition,
+        amount: Amount,
+    ) -> Result<CollectInterestResult> {
+        with_position(user_yield_position, |user| {
+            self.transact(|s| {
+                validate_emergency_withdraw(&s.vault)?;
+
+                let amount_staged = amount.to_u64(user.interest.staged)?;
+
+                let (amount_to_user, amount_to_treasury) = handle_emergency_withdraw_interest(
+                    &mut s.vault,
+                    user,
+                    amount_staged,
+                    s.unix_timestamp,
+                )?;
+
+                Ok(CollectInterestResult {
+                    amount_to_user,
+                    amount_to_treasury,
+                })
+            })
+        })
+    }
+
+    /// Compound all of a user's interest back into their YT position, paying the keeper `keeper_tip_bps`
+    pub fn compound_interest(
+        &mut self,
+        user_yield_position: &mut YieldTokenPosition,
+        keeper_tip_bps: u16,
+    ) -> Result<CompoundInterestAmounts> {
+        with_position(user_yield_position, |user| {
+            self.transact(|s| {
+                validate_compound_interest(&s.vault, s.unix_timestamp)?;
+
+                CompoundingConfig::validate_keeper_tip(keeper_tip_bps)?;
+
+                handle_compound_interest(
+                    &mut s.vault,
+                    &mut s.vault_yield_position,
+                    user,
+                    s.unix_timestamp,
+                    &s.sy_state,
+                    keeper_tip_bps,
+                )
+            })
+        })
+    }
+
+    pub(crate) fn do_strip(&mut self, amount_sy: u64) -> Result<u64> {
+        validate_strip(&self.vault, self.unix_timestamp, amount_sy)?;
+
+        handle_strip(
+            &mut self.vault,
+            &mut self.vault_yield_position,
+            self.unix_timestamp,
+            &self.sy_state,
+            amount_sy,
+        )
+    }
+
+    pub(crate) fn do_merge(&mut self, amount_py: u64) -> Result<u64> {
+        validate_merge(&self.vault, amount_py)?;
+
+        handle_merge(
+            &mut self.vault,
+            &mut self.vault_yield_position,
+            self.unix_timestamp,
+            &self.sy_state,
+            amount_py,
+        )
+    }
+}
+
+/// Run an operation against a copy of an external position, and keep the result only if it succeeds
+fn with_position<T>(
+    position: &mut YieldTokenPosition,
+    f: impl FnOnce(&mut YieldTokenPosition) -> Result<T>,
+) -> Result<T> {
+    let mut next = position.clone();
+    let r = f(&mut next)?;
+    *position = next;
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use precise_number::Number;
+    use sy_common::SyState;
+
+    const NOW: u32 = 1_700_000_000;
+
+    fn sy_state(exchange_rate: Number) -> SyState {
+        SyState {
+            exchange_rate,
+            emission_indexes: vec![],
+        }
+    }
+
+    fn simulator() -> Simulator {
+        let mut sim = crate::market::tests::simulator();
+        sim.vault.interest_bps_fee = 500;
+        sim.vault.status |= STATUS_CAN_DEPOSIT_YT | STATUS_CAN_COLLECT_INTEREST;
+        sim.vault.claim_limits.max_claim_amount_per_window = u64::MAX / 2;
+        sim
+    }
+
+    fn data<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = vec![];
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn assert_same_vault(
+        sim: &Simulator,
+        vault: &Vault,
+        vault_yield_position: &YieldTokenPosition,
+    ) {
+        assert_eq!(data(&sim.vault), data(vault));
+        assert_eq!(data(&sim.vault_yield_position), data(vault_yield_position));
+    }
+
+    #[test]
+    fn strip_matches_vault_state() {
+        for amount_sy in [1_000_000, 5_000_000_000] {
+            let mut sim = simulator();
+            let mut vault = sim.vault.clone();
+            let mut vault_yield_position = sim.vault_yield_position.clone();
+
+            let expected = handle_strip(
+                &mut vault,
+                &mut vault_yield_position,
+                NOW,
+                &sim.sy_state,
+                amount_sy,
+            )
+            .unwrap();
+
+            assert_eq!(sim.strip(amount_sy).unwrap(), expected);
+            assert_same_vault(&sim, &vault, &vault_yield_position);
+        }
+    }
+
+    #[test]
+    fn merge_matches_vault_state() {
+        for amount_py in [1_000_000, 5_000_000_000] {
+            let mut sim = simulator();
+            sim.strip(10_000_000_000).unwrap();
+            sim.set_sy_state(sy_state(Number::from_ratio(12, 10)));
+            sim.set_unix_timestamp(NOW + 86_400);
+
+            let mut vault = sim.vault.clone();
+            let mut vault_yield_position = sim.vault_yield_position.clone();
+            let expected = handle_merge(
+                &mut vault,
+                &mut vault_yield_position,
+                NOW + 86_400,
+                &sim.sy_state,
+                amount_py,
+            )
+            .unwrap();
+
+            assert_eq!(sim.merge(amount_py).unwrap(), expected);
+            assert_same_vault(&sim, &vault, &vault_yield_position);
+        }
+    }
+
+    #[test]
+    fn collect_interest_matches_vault_state() {
+        let mut sim = simulator();
+        let mut user = YieldTokenPosition::default();
+        let yt = sim.strip(10_000_000_000).unwrap();
+        sim.deposit_yt(&mut user, yt).unwrap();
+
+        sim.set_sy_state(sy_state(Number::from_ratio(12, 10)));
+        sim.set_unix_timestamp(NOW + 86_400);
+        sim.stage_yield(&mut user).unwrap();
+        assert!(user.interest.staged > 0);
+
+        // part of the staged interest, then all that is left
+        for amount in [Some(user.interest.staged / 3), None] {
+            let amount = || amount.map_or(Amount::All, Amount::Some);
+
+            let mut vault = sim.vault.clone();
+            let mut expected_user = user.clone();
+
+            // the steps of the collect_interest handler, without the SY transfers
+            let amount_staged = amount().to_u64(expected_user.interest.staged).unwrap();
+            vault
+                .claim_limits
+                .verify_claim_limits(vault.staged_interest_sy(amount_staged), NOW + 86_400)
+                .unwrap();
+            expected_user.earn_all_with_tracking(&mut vault).unwrap();
+            let (amount_to_user, amount_to_treasury) =
+                handle_collect_interest(&mut vault, &mut expected_user, amount_staged).unwrap();
+
+            let r = sim.collect_interest(&mut user, amount()).unwrap();
+
+            assert_eq!(r.amount_to_user, amount_to_user);
+            assert_eq!(r.amount_to_treasury, amount_to_treasury);
+            assert!(amount_to_treasury > 0);
+            assert_eq!(data(&user), data(&expected_user));
+            assert_same_vault(&sim, &vault, &sim.vault_yield_position);
+        }
+
+        assert_eq!(user.interest.staged, 0);
+    }
+
+    #[test]
+    fn disabled_strip_fails_like_the_program() {
+        let mut sim = simulator();
+        sim.vault.status &= !STATUS_CAN_STRIP;
+        let vault = sim.vault.clone();
+
+        assert_eq!(
+            sim.strip(1_000_000).unwrap_err(),
+            validate_strip(&vault, NOW, 1_000_000).unwrap_err()
+        );
+        assert_same_vault(&sim, &vault, &YieldTokenPosition::default());
+    }
+}
@@ -0,0 +1,267 @@
+//! Rate risk of PT, YT and LP tokens
+//!
+//! Sensitivities are taken against the implied APY, by repricing each token along the same curve the market trades on.
+//! PT and YT are priced from the implied rate directly.
+//! LP is priced after arbitrage has traded the pool to the bumped rate, using the current rate scalar and anchor,
+//! so its numbers include the rebalancing of the pool between PT and asset.
+//! Fees are ignored.
+
+use crate::math::{
+    exchange_rate, exchange_rate_from_ln_implied_rate, find_rate_anchor, logit,
+    normalized_sec_remaining, rate_scalar,
+};
+
+/// Size of the implied APY bump, 1bp
+pub const BUMP: f64 = 0.0001;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RateRisk {
+    /// Price of one token in the underlying asset
+    pub price: f64,
+
+    /// Effective Macaulay duration in years, which is the time to expiry for PT
+    pub macaulay_duration: f64,
+
+    /// Relative price change per unit change of the implied APY
+    pub modified_duration: f64,
+
+    /// Fall in price (in the underlying asset) when the implied APY rises by 1bp
+    pub dv01: f64,
+
+    pub convexity: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MarketRateRisk {
+    /// Implied APY the market is trading at
+    pub implied_apy: f64,
+
+    pub years_to_expiry: f64,
+
+    pub pt: RateRisk,
+
+    pub yt: RateRisk,
+
+    /// Risk of a single LP token
+    pub lp: RateRisk,
+}
+
+/// Calculate the rate risk of a market's tokens
+///
+/// # Arguments
+/// - `pt_balance` - PT liquidity in the market
+/// - `asset_balance` - SY liquidity in the market, in the underlying asset
+/// - `lp_supply` - Supply of LP tokens
+/// - `rate_scalar_root` - Initial rate scalar of the market
+/// - `ln_implied_rate` - Last seen ln implied rate of the market
+/// - `sec_remaining` - Seconds until expiry
+pub fn market_rate_risk(
+    pt_balance: u64,
+    asset_balance: u64,
+    lp_supply: u64,
+    rate_scalar_root: f64,
+    ln_implied_rate: f64,
+    sec_remaining: u64,
+) -> MarketRateRisk {
+    let implied_apy = ln_implied_rate.exp() - 1.0;
+    let years_to_expiry = normalized_sec_remaining::<f64>(sec_remaining);
+
+    // at expiry PT is worth exactly 1 asset, and there is no rate left to be exposed to
+    if sec_remaining == 0 {
+        let lp_price = if lp_supply == 0 {
+            0.0
+        } else {
+            (asset_balance as f64 + pt_balance as f64) / lp_supply as f64
+        };
+
+        return MarketRateRisk {
+            implied_apy,
+            years_to_expiry,
+            pt: RateRisk {
+                price: 1.0,
+                ..Default::default()
+            },
+            yt: RateRisk::default(),
+            lp: RateRisk {
+                price: lp_price,
+                ..Default::default()
+            },
+        };
+    }
+
+    let curve = FrozenCurve::new(
+        pt_balance,
+        asset_balance,
+        rate_scalar_root,
+        ln_implied_rate,
+        sec_remaining,
+    );
+
+    let pt_price = |apy: f64| curve.pt_price(apy);
+    let yt_price = |apy: f64| 1.0 - curve.pt_price(apy);
+    let lp_price = |apy: f64| {
+        if lp_supply == 0 {
+            return 0.0;
+        }
+        let (pt, asset) = curve.pool_at_rate((1.0 + apy).ln());
+        (asset + pt * curve.pt_price(apy)) / lp_supply as f64
+    };
+
+    MarketRateRisk {
+        implied_apy,
+        years_to_expiry,
+        pt: rate_risk(pt_price, implied_apy),
+        yt: rate_risk(yt_price, implied_apy),
+        lp: rate_risk(lp_price, implied_apy),
+    }
+}
+
+/// Central differences of a pricing function around `apy`
+fn rate_risk(price: impl Fn(f64) -> f64, apy: f64) -> RateRisk {
+    let p = price(apy);
+    let p_up = price(apy + BUMP);
+    let p_down = price(apy - BUMP);
+
+    if p == 0.0 {
+        return RateRisk::default();
+    }
+
+    let modified_duration = (p_down - p_up) / (2.0 * BUMP * p);
+
+    RateRisk {
+        price: p,
+        macaulay_duration: modified_duration * (1.0 + apy),
+        modified_duration,
+        dv01: (p_down - p_up) / 2.0,
+        convexity: (p_up + p_down - 2.0 * p) / (p * BUMP * BUMP),
+    }
+}
+
+/// The market curve, frozen at the current time
+struct FrozenCurve {
+    pt_balance: f64,
+    asset_balance: f64,
+    rate_scalar: f64,
+    rate_anchor: f64,
+    sec_remaining: u64,
+}
+
+impl FrozenCurve {
+    fn new(
+        pt_balance: u64,
+        asset_balance: u64,
+        rate_scalar_root: f64,
+        ln_implied_rate: f64,
+        sec_remaining: u64,
+    ) -> Self {
+        let rate_scalar = rate_scalar::<f64>(rate_scalar_root, sec_remaining);
+        let rate_anchor = find_rate_anchor::<f64>(
+            pt_balance,
+            asset_balance,
+            rate_scalar,
+            ln_implied_rate,
+            sec_remaining,
+        );
+
+        Self {
+            pt_balance: pt_balance as f64,
+            asset_balance: asset_balance as f64,
+            rate_scalar,
+            rate_anchor,
+            sec_remaining,
+        }
+    }
+
+    /// Price of PT in asset at an implied APY
+    fn pt_price(&self, apy: f64) -> f64 {
+        1.0 / exchange_rate_from_ln_implied_rate::<f64>((1.0 + apy).ln(), self.sec_remaining)
+    }
+
+    /// Exchange rate on the curve for a PT proportion
+    fn exchange_rate(&self, proportion_pt: f64) -> f64 {
+        exchange_rate::<f64>(logit(proportion_pt), self.rate_scalar, self.rate_anchor)
+    }
+
+    /// Ln implied rate of the pool after a trader buys `pt_out` PT from it (negative to sell), before fees
+    /// Mirrors `math::trade`, which executes at the exchange rate of the post-trade proportion of the pre-trade liquidity
+    fn ln_implied_rate_after_trade(&self, pt_out: f64) -> f64 {
+        let total = self.pt_balance + self.asset_balance;
+        let trade_rate = self.exchange_rate((self.pt_balance - pt_out) / total);
+
+        let pt = self.pt_balance - pt_out;
+        let asset = self.asset_balance + pt_out / trade_rate;
+
+        self.exchange_rate(pt / (pt + asset)).ln()
+            / normalized_sec_remaining::<f64>(self.sec_remaining)
+    }
+
+    /// PT and asset balances of the pool once it has been traded to `ln_implied_rate`
+    fn pool_at_rate(&self, ln_implied_rate: f64) -> (f64, f64) {
+        // buying PT lowers the implied rate, so bisect over the PT bought
+        // rates off the end of the curve are NaN, which counts as too low
+        let mut lo = -self.asset_balance;
+        let mut hi = self.pt_balance;
+        for _ in 0..200 {
+            let mid = (lo + hi) / 2.0;
+            if self.ln_implied_rate_after_trade(mid) > ln_implied_rate {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        let pt_out = (lo + hi) / 2.0;
+        let trade_rate =
+            self.exchange_rate((self.pt_balance - pt_out) / (self.pt_balance + self.asset_balance));
+        (
+            self.pt_balance - pt_out,
+            self.asset_balance + pt_out / trade_rate,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEAR: u64 = 365 * 86400;
+
+    fn risk() -> MarketRateRisk {
+        market_rate_risk(
+            1_000_000_000_000,
+            2_000_000_000_000,
+            1_500_000_000_000,
+            20.0,
+            0.08,
+            YEAR / 2,
+        )
+    }
+
+    #[test]
+    fn pt_macaulay_duration_is_time_to_expiry() {
+        let r = risk();
+        assert!((r.pt.macaulay_duration - 0.5).abs() < 1e-6);
+        assert!((r.pt.modified_duration - 0.5 / (1.0 + r.implied_apy)).abs() < 1e-6);
+        assert!((r.pt.price - (-0.08f64 * 0.5).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn yt_offsets_pt() {
+        let r = risk();
+        assert!((r.pt.price + r.yt.price - 1.0).abs() < 1e-12);
+        assert!((r.pt.dv01 + r.yt.dv01).abs() < 1e-12);
+        assert!(r.yt.modified_duration < 0.0);
+    }
+
+    #[test]
+    fn lp_prices_the_current_pool() {
+        let r = risk();
+        let pt_price = (-0.08f64 * 0.5).exp();
+        let expected = (2_000_000_000_000.0 + 1_000_000_000_000.0 * pt_price) / 1_500_000_000_000.0;
+        assert!((r.lp.price - expected).abs() < 1e-9);
+
+        // the pool holds some PT, so LP has less rate exposure than PT
+        assert!(r.lp.modified_duration > 0.0);
+        assert!(r.lp.modified_duration < r.pt.modified_duration);
+    }
+}
@@ -0,0 +1,442 @@
+//! Calibration of the curve parameters passed to `init_market_two`
+//!
+//! The tradable band is the range of PT proportions `[tail_width, 1 - tail_width]`.
+//! The rate scalar root is chosen so that, at launch, the implied APY at the edges of the band reaches the expected rate range,
+//! starting from the implied APY of the seed liquidity.
+//! The rate anchor is then the one that prices the seed liquidity at the initial APY.
+
+use std::fmt;
+
+use crate::math::{
+    exchange_rate, fee_rate, find_rate_anchor, ln_implied_rate, logit, normalized_sec_remaining,
+    proportion_pt, rate_scalar, trade,
+};
+
+const DEFAULT_TAIL_WIDTH: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// The market has already expired
+    Expired,
+    /// Seed liquidity must include both PT and SY
+    EmptySeedLiquidity,
+    /// The tail width must be strictly between 0 and 1/2
+    InvalidTailWidth,
+    /// The rate range must satisfy 0 < min < initial < max
+    InvalidRateRange,
+    /// The fee APY cannot be negative
+    InvalidFeeApy,
+    /// The seed liquidity's PT proportion is outside of the tradable band
+    SeedOutsideBand,
+    /// Part of the band has an exchange rate at or below 1, where `trade` would fail
+    ExchangeRateBelowOne,
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            CalibrationError::Expired => "market has already expired",
+            CalibrationError::EmptySeedLiquidity => "seed liquidity must include both PT and SY",
+            CalibrationError::InvalidTailWidth => "tail width must be between 0 and 1/2",
+            CalibrationError::InvalidRateRange => "rate range must satisfy 0 < min < initial < max",
+            CalibrationError::InvalidFeeApy => "fee APY cannot be negative",
+            CalibrationError::SeedOutsideBand => {
+                "seed PT proportion is outside of the tradable band"
+            }
+            CalibrationError::ExchangeRateBelowOne => {
+                "exchange rate falls to 1 or below within the tradable band"
+            }
+        };
+        write!(f, "{msg}")
+    }
+}
+
+impl std::error::Error for CalibrationError {}
+
+/// Arguments for `init_market_two`, except for the accounts and treasury settings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarketInitArgs {
+    pub ln_fee_rate_root: f64,
+    pub rate_scalar_root: f64,
+    pub init_rate_anchor: f64,
+    pub pt_init: u64,
+    pub sy_init: u64,
+}
+
+/// Edges of the tradable band at launch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateBand {
+    pub min_proportion_pt: f64,
+    pub max_proportion_pt: f64,
+
+    /// Implied APY with the least PT in the pool
+    pub min_apy: f64,
+
+    /// Implied APY with the most PT in the pool
+    pub max_apy: f64,
+}
+
+/// Execution of a single trade against the seed liquidity, including the fee
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TradeImpact {
+    /// Asset paid when buying PT, or received when selling PT
+    pub asset: f64,
+
+    /// Relative difference between the execution price and the spot PT price, positive when the trader gets a worse price
+    pub price_impact: f64,
+
+    /// Implied APY of the pool after the trade
+    pub implied_apy_after: f64,
+
+    /// Whether the pool is still inside the tradable band after the trade
+    pub in_band: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceImpact {
+    pub size_pt: u64,
+
+    /// None if the market cannot sell this much PT
+    pub buy_pt: Option<TradeImpact>,
+
+    /// None if the market cannot buy this much PT
+    pub sell_pt: Option<TradeImpact>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    pub init: MarketInitArgs,
+
+    pub initial_apy: f64,
+
+    /// PT proportion of the seed liquidity
+    pub initial_proportion_pt: f64,
+
+    /// Spot price of PT in asset at launch
+    pub pt_price: f64,
+
+    /// Fee rate at launch
+    pub fee_rate: f64,
+
+    pub band: RateBand,
+
+    pub price_impacts: Vec<PriceImpact>,
+}
+
+/// Builder for the curve parameters of a new market
+#[derive(Clone, Debug)]
+pub struct MarketCalibration {
+    initial_apy: f64,
+    min_apy: f64,
+    max_apy: f64,
+    tail_width: f64,
+    fee_apy: f64,
+    sec_remaining: u64,
+    pt_init: u64,
+    sy_init: u64,
+    sy_exchange_rate: f64,
+    impact_sizes: Vec<u64>,
+}
+
+impl MarketCalibration {
+    /// Start a calibration for a market trading at `initial_apy`, with `sec_remaining` until expiry
+    ///
+    /// The rate range defaults to half and double the initial APY, and the tail width to 10%
+    pub fn new(initial_apy: f64, sec_remaining: u64) -> Self {
+        Self {
+            initial_apy,
+            min_apy: initial_apy / 2.0,
+            max_apy: initial_apy * 2.0,
+            tail_width: DEFAULT_TAIL_WIDTH,
+            fee_apy: 0.0,
+            sec_remaining,
+            pt_init: 0,
+            sy_init: 0,
+            sy_exchange_rate: 1.0,
+            impact_sizes: vec![],
+        }
+    }
+
+    /// Range of APYs the market is expected to trade in
+    pub fn rate_range(mut self, min_apy: f64, max_apy: f64) -> Self {
+        self.min_apy = min_apy;
+        self.max_apy = max_apy;
+        self
+    }
+
+    /// Proportion of PT at either end of the curve that is outside of the tradable band
+    pub fn tail_width(mut self, tail_width: f64) -> Self {
+        self.tail_width = tail_width;
+        self
+    }
+
+    /// Fee charged on trades, as an annualized rate
+    pub fn fee_apy(mut self, fee_apy: f64) -> Self {
+        self.fee_apy = fee_apy;
+        self
+    }
+
+    /// Liquidity deposited when the market is created
+    pub fn seed_liquidity(mut self, pt_init: u64, sy_init: u64, sy_exchange_rate: f64) -> Self {
+        self.pt_init = pt_init;
+        self.sy_init = sy_init;
+        self.sy_exchange_rate = sy_exchange_rate;
+        self
+    }
+
+    /// PT trade sizes to report the price impact of
+    pub fn impact_sizes(mut self, sizes: &[u64]) -> Self {
+        self.impact_sizes = sizes.to_vec();
+        self
+    }
+
+    pub fn build(&self) -> Result<Calibration, CalibrationError> {
+        if self.sec_remaining == 0 {
+            return Err(CalibrationError::Expired);
+        }
+        if !(self.tail_width > 0.0 && self.tail_width < 0.5) {
+            return Err(CalibrationError::InvalidTailWidth);
+        }
+        if !(0.0 < self.min_apy
+            && self.min_apy < self.initial_apy
+            && self.initial_apy < self.max_apy)
+        {
+            return Err(CalibrationError::InvalidRateRange);
+        }
+        if self.fee_apy.is_nan() || self.fee_apy < 0.0 {
+            return Err(CalibrationError::InvalidFeeApy);
+        }
+
+        // the program floors the asset value of the SY
+        let asset_init = (self.sy_init as f64 * self.sy_exchange_rate).floor() as u64;
+        if self.pt_init == 0 || asset_init == 0 {
+            return Err(CalibrationError::EmptySeedLiquidity);
+        }
+
+        let t = normalized_sec_remaining::<f64>(self.sec_remaining);
+        let p0 = proportion_pt::<f64>(self.pt_init, asset_init);
+        let l0 = logit(p0);
+
+        // logit of the band's edges is -s and s
+        let s = logit(1.0 - self.tail_width);
+        if l0.abs() >= s {
+            return Err(CalibrationError::SeedOutsideBand);
+        }
+
+        // at launch the exchange rate is (1 + apy)^t, and moves by t * (l - l0) / rate_scalar_root from the seed proportion
+        // the root is the largest that still reaches both ends of the rate range
+        let er_init = (1.0 + self.initial_apy).powf(t);
+        let root_high = t * (s - l0) / ((1.0 + self.max_apy).powf(t) - er_init);
+        let root_low = t * (s + l0) / (er_init - (1.0 + self.min_apy).powf(t));
+        let rate_scalar_root = root_high.min(root_low);
+
+        let ln_initial_rate = (1.0 + self.initial_apy).ln();
+        let scalar = rate_scalar::<f64>(rate_scalar_root, self.sec_remaining);
+        let init_rate_anchor = find_rate_anchor::<f64>(
+            self.pt_init,
+            asset_init,
+            scalar,
+            ln_initial_rate,
+            self.sec_remaining,
+        );
+
+        // the exchange rate increases with the PT proportion, so the lower edge is the lowest in the band
+        // every trade executing inside the band therefore passes the `er > 1` check in `trade`
+        let er_min = exchange_rate::<f64>(-s, scalar, init_rate_anchor);
+        let er_max = exchange_rate::<f64>(s, scalar, init_rate_anchor);
+        if er_min.is_nan() || er_min <= 1.0 {
+            return Err(CalibrationError::ExchangeRateBelowOne);
+        }
+
+        let band = RateBand {
+            min_proportion_pt: self.tail_width,
+            max_proportion_pt: 1.0 - self.tail_width,
+            min_apy: er_min.powf(1.0 / t) - 1.0,
+            max_apy: er_max.powf(1.0 / t) - 1.0,
+        };
+
+        let ln_fee_rate_root = (1.0 + self.fee_apy).ln();
+        let curve = LaunchCurve {
+            pt: self.pt_init,
+            asset: asset_init,
+            rate_scalar: scalar,
+            rate_anchor: init_rate_anchor,
+            fee_rate: fee_rate::<f64>(ln_fee_rate_root, self.sec_remaining),
+            sec_remaining: self.sec_remaining,
+            pt_price: 1.0 / er_init,
+            band,
+        };
+
+        let price_impacts = self
+            .impact_sizes
+            .iter()
+            .map(|&size_pt| PriceImpact {
+                size_pt,
+                buy_pt: curve.impact(size_pt as f64),
+                sell_pt: curve.impact(-(size_pt as f64)),
+            })
+            .collect();
+
+        Ok(Calibration {
+            init: MarketInitArgs {
+                ln_fee_rate_root,
+                rate_scalar_root,
+                init_rate_anchor,
+                pt_init: self.pt_init,
+                sy_init: self.sy_init,
+            },
+            initial_apy: self.initial_apy,
+            initial_proportion_pt: p0,
+            pt_price: curve.pt_price,
+            fee_rate: curve.fee_rate,
+            band,
+            price_impacts,
+        })
+    }
+}
+
+/// The curve as it is at launch
+struct LaunchCurve {
+    pt: u64,
+    asset: u64,
+    rate_scalar: f64,
+    rate_anchor: f64,
+    fee_rate: f64,
+    sec_remaining: u64,
+    pt_price: f64,
+    band: RateBand,
+}
+
+impl LaunchCurve {
+    /// Impact of a trade of `net_trader_pt`, which is positive when buying PT
+    fn impact(&self, net_trader_pt: f64) -> Option<TradeImpact> {
+        if net_trader_pt == 0.0 {
+            return None;
+        }
+
+        // trades the market cannot fill, or that push the exchange rate to 1 or below, are skipped
+        let r = trade::<f64>(
+            self.pt,
+            self.asset,
+            self.rate_scalar,
+            self.rate_anchor,
+            self.fee_rate,
+            net_trader_pt,
+            false,
+        )
+        .ok()?;
+
+        // the market receives what the trader pays, fees included
+        let pt_after = (self.pt as f64 - net_trader_pt) as u64;
+        let asset_after = (self.asset as f64 - r.net_trader_asset) as u64;
+        if asset_after == 0 {
+            return None;
+        }
+
+        let price = r.net_trader_asset.abs() / net_trader_pt.abs();
+        let price_impact = if net_trader_pt > 0.0 {
+            price / self.pt_price - 1.0
+        } else {
+            1.0 - price / self.pt_price
+        };
+
+        let ln_rate_after = ln_implied_rate::<f64>(
+            pt_after,
+            asset_after,
+            self.rate_scalar,
+            self.rate_anchor,
+            self.sec_remaining,
+        );
+        let p_after = proportion_pt::<f64>(pt_after, asset_after);
+
+        Some(TradeImpact {
+            asset: r.net_trader_asset.abs(),
+            price_impact,
+            implied_apy_after: ln_rate_after.exp() - 1.0,
+            in_band: p_after >= self.band.min_proportion_pt
+                && p_after <= self.band.max_proportion_pt,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEAR: u64 = 365 * 86400;
+
+    fn calibration() -> MarketCalibration {
+        MarketCalibration::new(0.08, YEAR / 2)
+            .rate_range(0.04, 0.12)
+            .fee_apy(0.002)
+            .seed_liquidity(1_000_000_000, 1_000_000_000, 1.05)
+            .impact_sizes(&[1_000_000, 100_000_000, 2_000_000_000])
+    }
+
+    #[test]
+    fn seed_liquidity_trades_at_initial_apy() {
+        let c = calibration().build().unwrap();
+
+        let asset = (1_000_000_000f64 * 1.05).floor() as u64;
+        let scalar = rate_scalar::<f64>(c.init.rate_scalar_root, YEAR / 2);
+        let ln_rate = ln_implied_rate::<f64>(
+            c.init.pt_init,
+            asset,
+            scalar,
+            c.init.init_rate_anchor,
+            YEAR / 2,
+        );
+        assert!((ln_rate.exp() - 1.0 - 0.08).abs() < 1e-9);
+    }
+
+    #[test]
+    fn band_covers_rate_range() {
+        let c = calibration().build().unwrap();
+
+        // one side is tight and the other wider
+        assert!(c.band.min_apy <= 0.04 + 1e-9);
+        assert!(c.band.max_apy >= 0.12 - 1e-9);
+        assert!((c.band.min_apy - 0.04).abs() < 1e-9 || (c.band.max_apy - 0.12).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reports_price_impact() {
+        let c = calibration().build().unwrap();
+
+        let small = c.price_impacts[0];
+        let medium = c.price_impacts[1];
+        let buy_small = small.buy_pt.unwrap();
+        let buy_medium = medium.buy_pt.unwrap();
+        assert!(buy_small.price_impact > 0.0);
+        assert!(buy_medium.price_impact > buy_small.price_impact);
+        assert!(buy_medium.implied_apy_after < 0.08);
+        assert!(medium.sell_pt.unwrap().implied_apy_after > 0.08);
+
+        // larger than the market's PT balance
+        assert!(c.price_impacts[2].buy_pt.is_none());
+    }
+
+    #[test]
+    fn rejects_infeasible_bounds() {
+        let e = |c: MarketCalibration| c.build().unwrap_err();
+
+        assert_eq!(
+            e(calibration().rate_range(0.09, 0.12)),
+            CalibrationError::InvalidRateRange
+        );
+        assert_eq!(
+            e(calibration().tail_width(0.5)),
+            CalibrationError::InvalidTailWidth
+        );
+        assert_eq!(
+            e(calibration().seed_liquidity(9_500_000_000, 100_000_000, 1.0)),
+            CalibrationError::SeedOutsideBand
+        );
+
+        // the high side is so wide that the low edge of the band falls below a zero rate
+        assert_eq!(
+            e(calibration().rate_range(0.079, 5.0)),
+            CalibrationError::ExchangeRateBelowOne
+        );
+    }
+}
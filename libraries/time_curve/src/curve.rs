@@ -0,0 +1,152 @@
+//! Pricing invariants a market can trade on
+//!
+//! A curve is frozen at a point in time, and anchored so that it prices the pool at the last seen implied rate.
+//! The implied rate then only moves when the pool's balances do.
+//! Adding and removing liquidity works on the pool's balances alone, so those do not need a built curve.
+
+use crate::{
+    math::{
+        self, exchange_rate, find_rate_anchor, logit, normalized_sec_remaining, proportion_pt,
+        rate_scalar, AddLiquidityResult, CurveError, RemoveLiquidityResult, TradeResult,
+    },
+    num::Num,
+};
+
+pub trait Curve<N: Num> {
+    /// Seconds until expiry the curve was built for
+    fn sec_remaining(&self) -> u64;
+
+    /// Exchange rate of asset to PT for the pool's balances, which is the inverse of the spot price of PT in asset
+    fn exchange_rate(&self, pt: u64, asset: u64) -> N;
+
+    /// Trade `net_trader_pt` against the pool, where a positive amount is the trader buying PT
+    fn trade(
+        &self,
+        market_pt: u64,
+        market_asset: u64,
+        fee_rate: N,
+        net_trader_pt: N,
+        is_current_flash_swap: bool,
+    ) -> Result<TradeResult<N>, CurveError>;
+
+    /// Natural log of the implied interest rate for the pool's balances
+    fn ln_implied_rate(&self, pt: u64, asset: u64) -> N {
+        self.exchange_rate(pt, asset).ln() / normalized_sec_remaining::<N>(self.sec_remaining())
+    }
+
+    /// Spot price of PT in asset for the pool's balances
+    fn spot_price(&self, pt: u64, asset: u64) -> N {
+        N::one() / self.exchange_rate(pt, asset)
+    }
+
+    /// PT balance at which the pool would be at `exchange_rate` if its asset balance stayed at `asset`
+    /// Solvers start from this estimate, so a curve without a closed form for it returns None
+    fn pt_for_exchange_rate(&self, _asset: u64, _exchange_rate: N) -> Option<N> {
+        None
+    }
+
+    /// Calculate the amount of LP tokens received, and SY & PT put in
+    /// Liquidity is added pro rata by default, which leaves the implied rate unchanged
+    fn add_liquidity(
+        intent_sy: u64,
+        intent_pt: u64,
+        market_total_lp: u64,
+        market_total_sy: u64,
+        market_total_pt: u64,
+    ) -> AddLiquidityResult {
+        math::add_liquidity::<N>(
+            intent_sy,
+            intent_pt,
+            market_total_lp,
+            market_total_sy,
+            market_total_pt,
+        )
+    }
+
+    /// Calculate the amount of SY and PT received for LP tokens in
+    /// Liquidity is removed pro rata by default, which leaves the implied rate unchanged
+    fn rm_liquidity(
+        lp_in: u64,
+        market_total_lp: u64,
+        market_total_sy: u64,
+        market_total_pt: u64,
+    ) -> RemoveLiquidityResult {
+        math::rm_liquidity::<N>(lp_in, market_total_lp, market_total_sy, market_total_pt)
+    }
+}
+
+/// The logit curve, where the exchange rate is logit(proportion_pt) / rate_scalar + rate_anchor
+///
+/// The rate scalar grows as expiry approaches, which flattens the curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogitCurve<N: Num> {
+    pub rate_scalar: N,
+    pub rate_anchor: N,
+    pub sec_remaining: u64,
+}
+
+impl<N: Num> LogitCurve<N> {
+    /// Build the curve at `sec_remaining`, anchored so that the pool is at `last_ln_implied_rate`
+    pub fn new(
+        rate_scalar_root: N,
+        pt: u64,
+        asset: u64,
+        last_ln_implied_rate: N,
+        sec_remaining: u64,
+    ) -> Self {
+        let rate_scalar = rate_scalar::<N>(rate_scalar_root, sec_remaining);
+        let rate_anchor =
+            find_rate_anchor::<N>(pt, asset, rate_scalar, last_ln_implied_rate, sec_remaining);
+
+        Self {
+            rate_scalar,
+            rate_anchor,
+            sec_remaining,
+        }
+    }
+}
+
+impl<N: Num> Curve<N> for LogitCurve<N> {
+    fn sec_remaining(&self) -> u64 {
+        self.sec_remaining
+    }
+
+    fn exchange_rate(&self, pt: u64, asset: u64) -> N {
+        exchange_rate(
+            logit(proportion_pt::<N>(pt, asset)),
+            self.rate_scalar,
+            self.rate_anchor,
+        )
+    }
+
+    /// Inverts the curve, since pt / asset = exp(logit(proportion_pt))
+    fn pt_for_exchange_rate(&self, asset: u64, exchange_rate: N) -> Option<N> {
+        let l = (exchange_rate - self.rate_anchor) * self.rate_scalar;
+
+        // beyond this the balance does not fit in u64
+        if l.abs() > N::from_u64(40) {
+            return None;
+        }
+
+        Some(N::from_u64(asset) * l.exp())
+    }
+
+    fn trade(
+        &self,
+        market_pt: u64,
+        market_asset: u64,
+        fee_rate: N,
+        net_trader_pt: N,
+        is_current_flash_swap: bool,
+    ) -> Result<TradeResult<N>, CurveError> {
+        math::trade(
+            market_pt,
+            market_asset,
+            self.rate_scalar,
+            self.rate_anchor,
+            fee_rate,
+            net_trader_pt,
+            is_current_flash_swap,
+        )
+    }
+}
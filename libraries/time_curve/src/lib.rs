@@ -1,2 +1,5 @@
+pub mod analytics;
+pub mod calibration;
+pub mod curve;
 pub mod math;
 pub mod num;
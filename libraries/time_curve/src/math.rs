@@ -1,4 +1,5 @@
-use crate::num::Num;
+use crate::{curve::Curve, num::Num};
+use std::cmp::Ordering;
 
 const DAY_SEC: u64 = 86400;
 const YEAR_SEC: u64 = DAY_SEC * 365;
@@ -256,6 +257,17 @@ pub struct TradeResult<N: Num> {
     pub asset_fee: N,
 }
 
+/// Reasons a trade cannot be executed against the curve
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveError {
+    /// The market does not have enough PT to sell
+    InsufficientPtLiquidity,
+    /// The trade would push the exchange rate to 1 or below, where asset is worth less than PT
+    ExchangeRateBelowOne,
+    /// The fee rate is below 1
+    InvalidFeeRate,
+}
+
 pub fn trade<N: Num>(
     market_pt: u64,
     market_asset: u64,
@@ -264,19 +276,29 @@ pub fn trade<N: Num>(
     fee_rate: N,
     net_trader_pt: N,
     is_current_flash_swap: bool,
-) -> TradeResult<N> {
-    // assert that the user is selling PT into the market
+) -> Result<TradeResult<N>, CurveError> {
+    // the user is selling PT into the market
     // or the market has more PT than the user is buying
-    assert!(net_trader_pt < N::zero() || market_pt > net_trader_pt.to_u64());
-
-    let market_pt = N::from_u64(market_pt);
-    let new_pt = market_pt - net_trader_pt;
+    if net_trader_pt >= N::zero() && market_pt <= net_trader_pt.to_u64() {
+        return Err(CurveError::InsufficientPtLiquidity);
+    }
 
-    let p = new_pt / (market_pt + N::from_u64(market_asset));
-    let l_p = logit(p);
-    let er = exchange_rate(l_p, rate_scalar, rate_anchor);
+    if fee_rate < N::one() {
+        return Err(CurveError::InvalidFeeRate);
+    }
 
-    assert!(er > N::one(), "Asset cannot be worth less than PT");
+    let er = post_trade_exchange_rate(
+        market_pt,
+        market_asset,
+        rate_scalar,
+        rate_anchor,
+        net_trader_pt,
+    );
+
+    // Asset cannot be worth less than PT, which also rejects a NaN rate off the end of the curve
+    if er.partial_cmp(&N::one()) != Some(Ordering::Greater) {
+        return Err(CurveError::ExchangeRateBelowOne);
+    }
 
     // negate the trader PT to get the net change in asset for the trader
     let pre_fee_net_trader_asset = -net_trader_pt / er;
@@ -296,8 +318,401 @@ pub fn trade<N: Num>(
     // if net_trader_asset is positive, the user is selling PT and buying asset and the "fee" value is positive in order to decrease the magnitude of net_trader_asset
     let net_trader_asset = pre_fee_net_trader_asset - fee;
 
-    TradeResult {
+    Ok(TradeResult {
         net_trader_asset,
         asset_fee: fee,
+    })
+}
+
+/// The exchange rate at which a trade of `net_trader_pt` executes
+/// Note that the proportion is taken against the pre-trade liquidity, matching `trade`
+pub fn post_trade_exchange_rate<N: Num>(
+    market_pt: u64,
+    market_asset: u64,
+    rate_scalar: N,
+    rate_anchor: N,
+    net_trader_pt: N,
+) -> N {
+    let market_pt = N::from_u64(market_pt);
+    let new_pt = market_pt - net_trader_pt;
+
+    let p = new_pt / (market_pt + N::from_u64(market_asset));
+    let l_p = logit(p);
+    exchange_rate(l_p, rate_scalar, rate_anchor)
+}
+
+/// Calculate the amount of asset a trader must spend to receive `pt_out`, including the fee
+/// This is the forward direction of `trade` for a PT purchase, returned as a positive magnitude
+pub fn asset_in_for_pt_out<N: Num>(
+    curve: &impl Curve<N>,
+    market_pt: u64,
+    market_asset: u64,
+    fee_rate: N,
+    pt_out: u64,
+    is_current_flash_swap: bool,
+) -> Result<N, CurveError> {
+    let r = curve.trade(
+        market_pt,
+        market_asset,
+        fee_rate,
+        N::from_u64(pt_out),
+        is_current_flash_swap,
+    )?;
+
+    Ok(-r.net_trader_asset)
+}
+
+/// Find the largest whole amount of PT that can be bought by spending at most `asset_in`
+///
+/// The search starts from the spot estimate `asset_in * exchange_rate`, which is an upper bound since the price of PT
+/// only rises as more is bought. The cost of buying PT strictly increases with the amount bought,
+/// so `largest_within` then closes in on the answer in at most MAX_SOLVER_PROBES trade evaluations.
+/// Amounts that would push the exchange rate to 1 or below are treated as unaffordable.
+pub fn pt_out_for_asset_in<N: Num>(
+    curve: &impl Curve<N>,
+    market_pt: u64,
+    market_asset: u64,
+    fee_rate: N,
+    asset_in: N,
+    is_current_flash_swap: bool,
+) -> u64 {
+    let spot_pt_out = (asset_in * curve.exchange_rate(market_pt, market_asset))
+        .min(N::from_u64(market_pt))
+        .to_u64();
+
+    // the market can never sell its entire PT balance
+    largest_within(asset_in, market_pt, Some(spot_pt_out), |pt_out| {
+        asset_in_for_pt_out(
+            curve,
+            market_pt,
+            market_asset,
+            fee_rate,
+            pt_out,
+            is_current_flash_swap,
+        )
+        .ok()
+        .map(|cost| cost - asset_in)
+    })
+}
+
+/// Most evaluations a solver makes before settling for the best amount found so far
+pub const MAX_SOLVER_PROBES: u32 = 32;
+
+/// Find the largest amount in `[0, hi)` whose `excess` is at most zero, for an excess that increases with the amount
+///
+/// `budget` is how far below zero the excess is at 0, and `hi` is treated as over the limit without being evaluated.
+/// An amount `excess` returns None for is over the limit, such as a trade the curve rejects.
+/// Each probe interpolates between the bracket's ends (regula falsi, with the Illinois fix so both ends move),
+/// starting from `estimate` if it is inside the bracket, and bisects when an end has no excess to interpolate from.
+/// After MAX_SOLVER_PROBES probes this returns the largest amount found within the limit, which can only understate the answer.
+pub fn largest_within<N: Num>(
+    budget: N,
+    hi: u64,
+    estimate: Option<u64>,
+    mut excess: impl FnMut(u64) -> Option<N>,
+) -> u64 {
+    let (mut lo, mut lo_excess) = (0, -budget);
+    let (mut hi, mut hi_excess) = (hi, None);
+    let mut next = estimate;
+    // +1 if the last probe moved lo, -1 if it moved hi
+    let mut last_side = 0;
+
+    for _ in 0..MAX_SOLVER_PROBES {
+        if hi <= lo + 1 {
+            break;
+        }
+
+        let probe = next
+            .filter(|&x| lo < x && x < hi)
+            .unwrap_or(lo + (hi - lo) / 2);
+
+        match excess(probe) {
+            Some(e) if e <= N::zero() => {
+                // the same end kept twice, so halve the other end's weight to pull the next probe towards it
+                if last_side == 1 {
+                    hi_excess = hi_excess.map(|h| h / N::from_u64(2));
+                }
+                (lo, lo_excess, last_side) = (probe, e, 1);
+            }
+            e => {
+                if last_side == -1 {
+                    lo_excess = lo_excess / N::from_u64(2);
+                }
+                (hi, hi_excess, last_side) = (probe, e, -1);
+            }
+        }
+
+        next = match hi_excess {
+            Some(h) => {
+                // the answer is the floor of where the excess crosses zero, so the amount just past lo is worth probing
+                let step = N::from_u64(hi - lo) * -lo_excess / (h - lo_excess);
+                Some(lo + step.to_u64().max(1))
+            }
+            // extend the line from 0 through lo, which overshoots for an excess that grows faster than the amount
+            // one past it, so the probe lands beyond the answer and gives hi an excess
+            None if lo > 0 && lo_excess > -budget => {
+                let amount = N::from_u64(lo) * budget / (lo_excess + budget);
+                Some(amount.to_u64().saturating_add(1))
+            }
+            None => None,
+        };
+    }
+
+    lo
+}
+
+/// Exchange rate of the pool once a trade of `net_trader_pt` has settled
+/// The fee stays in the pool, so the rate is taken from the post-fee asset balance
+pub fn exchange_rate_after_trade<N: Num>(
+    curve: &impl Curve<N>,
+    market_pt: u64,
+    market_asset: u64,
+    fee_rate: N,
+    net_trader_pt: i64,
+    is_current_flash_swap: bool,
+) -> Result<N, CurveError> {
+    let r = curve.trade(
+        market_pt,
+        market_asset,
+        fee_rate,
+        N::from_i64(net_trader_pt),
+        is_current_flash_swap,
+    )?;
+
+    let pt = market_pt
+        .checked_add_signed(-net_trader_pt)
+        .ok_or(CurveError::InsufficientPtLiquidity)?;
+    let asset = N::from_u64(market_asset) - r.net_trader_asset;
+
+    Ok(curve.exchange_rate(pt, asset.to_u64()))
+}
+
+/// Find the net PT a trader must buy (negative to sell) to move the pool's exchange rate to `target_exchange_rate`
+///
+/// A target ln implied rate converts with `exchange_rate_from_ln_implied_rate`, since the implied rate rises with the exchange rate.
+/// Buying PT lowers the exchange rate and selling PT raises it, so this searches the PT amount
+/// on the side of the current rate that the target is on, starting from the curve's spot estimate,
+/// in at most MAX_SOLVER_PROBES trade evaluations.
+/// Returns the largest whole amount that does not carry the rate past the target.
+/// If the curve cannot reach the target, this is the largest trade the curve allows in that direction,
+/// which only bisection can find, so it may come up a little short.
+pub fn net_trader_pt_for_exchange_rate<N: Num>(
+    curve: &impl Curve<N>,
+    market_pt: u64,
+    market_asset: u64,
+    fee_rate: N,
+    target_exchange_rate: N,
+    is_current_flash_swap: bool,
+) -> i64 {
+    let current = curve.exchange_rate(market_pt, market_asset);
+
+    if target_exchange_rate == current {
+        return 0;
+    }
+
+    let is_buy = target_exchange_rate < current;
+
+    // the pool leaves the target behind once the excess is above zero
+    // a trade the curve rejects has gone too far
+    let excess = |pt: u64| {
+        let net_trader_pt = if is_buy { pt as i64 } else { -(pt as i64) };
+        exchange_rate_after_trade(
+            curve,
+            market_pt,
+            market_asset,
+            fee_rate,
+            net_trader_pt,
+            is_current_flash_swap,
+        )
+        .ok()
+        .map(|rate| {
+            if is_buy {
+                target_exchange_rate - rate
+            } else {
+                rate - target_exchange_rate
+            }
+        })
+    };
+
+    // the market can never sell its entire PT balance,
+    // and the PT proportion a sale executes at must stay below 1, so at most as much PT as there is asset can be sold
+    let hi = if is_buy { market_pt } else { market_asset }.min(i64::MAX as u64);
+
+    // the spot estimate leaves out the asset the trade moves and the fee
+    let estimate = curve
+        .pt_for_exchange_rate(market_asset, target_exchange_rate)
+        .map(|pt| {
+            (N::from_u64(market_pt) - pt)
+                .abs()
+                .min(N::from_u64(hi))
+                .to_u64()
+        });
+
+    let pt = largest_within((target_exchange_rate - current).abs(), hi, estimate, excess);
+
+    if is_buy {
+        pt as i64
+    } else {
+        -(pt as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::LogitCurve;
+    use std::cell::Cell;
+
+    /// Logit curve that counts its trade evaluations
+    struct CountingCurve {
+        curve: LogitCurve<f64>,
+        trades: Cell<u32>,
+    }
+
+    impl Curve<f64> for CountingCurve {
+        fn sec_remaining(&self) -> u64 {
+            self.curve.sec_remaining
+        }
+
+        fn exchange_rate(&self, pt: u64, asset: u64) -> f64 {
+            self.curve.exchange_rate(pt, asset)
+        }
+
+        fn pt_for_exchange_rate(&self, asset: u64, exchange_rate: f64) -> Option<f64> {
+            self.curve.pt_for_exchange_rate(asset, exchange_rate)
+        }
+
+        fn trade(
+            &self,
+            market_pt: u64,
+            market_asset: u64,
+            fee_rate: f64,
+            net_trader_pt: f64,
+            is_current_flash_swap: bool,
+        ) -> Result<TradeResult<f64>, CurveError> {
+            self.trades.set(self.trades.get() + 1);
+            self.curve.trade(
+                market_pt,
+                market_asset,
+                fee_rate,
+                net_trader_pt,
+                is_current_flash_swap,
+            )
+        }
+    }
+
+    #[test]
+    fn pt_out_for_asset_in_finds_the_largest_affordable_amount() {
+        let sec_remaining = 90 * 86_400;
+        let fee_rate = fee_rate::<f64>(0.002, sec_remaining);
+
+        for (pt, asset) in [
+            (1_000_000_000_000, 2_000_000_000_000),
+            (5_000_000, 1_000_000_000_000),
+            (1_000_000_000_000, 50_000_000),
+        ] {
+            let curve = CountingCurve {
+                curve: LogitCurve::new(15.0, pt, asset, 0.1, sec_remaining),
+                trades: Cell::new(0),
+            };
+
+            for asset_in in [1, 1_000, 1_000_000, asset / 100, asset / 2, asset * 10] {
+                for is_current_flash_swap in [false, true] {
+                    let affordable = |pt_out: u64| {
+                        asset_in_for_pt_out(
+                            &curve.curve,
+                            pt,
+                            asset,
+                            fee_rate,
+                            pt_out,
+                            is_current_flash_swap,
+                        )
+                        .is_ok_and(|cost| cost <= asset_in as f64)
+                    };
+
+                    curve.trades.set(0);
+                    let pt_out = pt_out_for_asset_in(
+                        &curve,
+                        pt,
+                        asset,
+                        fee_rate,
+                        asset_in as f64,
+                        is_current_flash_swap,
+                    );
+
+                    let probes = curve.trades.get();
+
+                    // reference answer by bisecting the whole PT balance
+                    let (mut lo, mut hi) = (0, pt);
+                    while hi - lo > 1 {
+                        let mid = lo + (hi - lo) / 2;
+                        if affordable(mid) {
+                            lo = mid;
+                        } else {
+                            hi = mid;
+                        }
+                    }
+
+                    let is_budget_bound = asset_in_for_pt_out(
+                        &curve.curve,
+                        pt,
+                        asset,
+                        fee_rate,
+                        lo + 1,
+                        is_current_flash_swap,
+                    )
+                    .is_ok();
+
+                    if is_budget_bound {
+                        assert_eq!(pt_out, lo);
+                        assert!(probes <= 6, "{probes} probes");
+                    } else {
+                        // only bisection finds where the curve stops, so the answer may be a little short
+                        assert!(pt_out <= lo && lo - pt_out <= lo / 1_000_000);
+                        assert!(probes <= MAX_SOLVER_PROBES);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trades_to_target_exchange_rate() {
+        let (pt, asset) = (1_000_000_000_000, 2_000_000_000_000);
+        let sec_remaining = 180 * 86_400;
+        let curve = CountingCurve {
+            curve: LogitCurve::<f64>::new(20.0, pt, asset, 0.08, sec_remaining),
+            trades: Cell::new(0),
+        };
+        let fee_rate = fee_rate::<f64>(0.001, sec_remaining);
+
+        let rate_after = |net_trader_pt: i64| {
+            exchange_rate_after_trade(&curve.curve, pt, asset, fee_rate, net_trader_pt, false)
+                .unwrap()
+        };
+
+        for target_ln_implied_rate in [0.05, 0.079, 0.081, 0.12] {
+            let target =
+                exchange_rate_from_ln_implied_rate::<f64>(target_ln_implied_rate, sec_remaining);
+
+            curve.trades.set(0);
+            let net_trader_pt =
+                net_trader_pt_for_exchange_rate(&curve, pt, asset, fee_rate, target, false);
+            assert!(curve.trades.get() <= 10, "{} probes", curve.trades.get());
+
+            // lowering the rate buys PT, raising it sells PT
+            assert_eq!(net_trader_pt > 0, target_ln_implied_rate < 0.08);
+
+            // one more PT carries the rate past the target
+            let step = net_trader_pt.signum();
+            let (before, after) = (rate_after(net_trader_pt), rate_after(net_trader_pt + step));
+            assert!((before - target) * (after - target) <= 0.0);
+            assert!(((before - target) / target).abs() < 1e-9);
+        }
+
+        let current = exchange_rate_from_ln_implied_rate::<f64>(0.08, sec_remaining);
+        assert!(
+            net_trader_pt_for_exchange_rate(&curve, pt, asset, fee_rate, current, false).abs() <= 1
+        );
     }
 }
@@ -60,4 +60,82 @@ pub enum ExponentCoreError {
     MinPtOutNotMet,
     #[msg("Min LP out not met")]
     MinLpOutNotMet,
+    #[msg("Min YT out not met")]
+    MinYtOutNotMet,
+    #[msg("TWAP window must be greater than zero")]
+    InvalidTwapWindow,
+    #[msg("Rate oracle does not cover the TWAP window")]
+    TwapWindowNotCovered,
+    #[msg("Market is expired")]
+    MarketExpired,
+    #[msg("Insufficient PT liquidity in the market")]
+    InsufficientPtLiquidity,
+    #[msg("Insufficient SY liquidity in the market")]
+    InsufficientSyLiquidity,
+    #[msg("Max PY supply exceeded")]
+    MaxPySupplyExceeded,
+    #[msg("Trade moves the curve out of range")]
+    CurveOutOfRange,
+    #[msg("Invalid fee rate")]
+    InvalidFeeRate,
+    #[msg("Slippage tolerance exceeded")]
+    SlippageExceeded,
+    #[msg("Insufficient YT balance")]
+    InsufficientYtBalance,
+    #[msg("Insufficient LP balance")]
+    InsufficientLpBalance,
+    #[msg("SY in must be less than the SY stripped to buy YT")]
+    SyInExceedsStrip,
+    #[msg("Vault is not expired")]
+    VaultIsNotExpired,
+    #[msg("Target market does not trade the vault's SY")]
+    RollTargetSyMismatch,
+    #[msg("Implied rate is out of bounds")]
+    ImpliedRateOutOfBounds,
+    #[msg("Keeper tip is above the maximum")]
+    KeeperTipTooHigh,
+    #[msg("Nothing to compound")]
+    NothingToCompound,
+    #[msg("Signer is not the owner or operator of the position")]
+    NotPositionOperator,
+    #[msg("Claim must pay into an account owned by the beneficiary")]
+    InvalidClaimDestination,
+    #[msg("Account is not a yield or LP position")]
+    InvalidPosition,
+    #[msg("Position still holds a balance or staged earnings")]
+    PositionNotEmpty,
+    #[msg("Vault is not in emergency mode")]
+    VaultNotInEmergencyMode,
+    #[msg("Escrow cannot cover any of the PT and YT claims")]
+    EmergencyNotResolvable,
+    #[msg("Emergency withdrawals are disabled")]
+    EmergencyWithdrawDisabled,
+    #[msg("Account required for this kind of emergency withdrawal is missing")]
+    EmergencyWithdrawAccountMissing,
+    #[msg("Market is not linked to the vault")]
+    MarketNotLinkedToVault,
+    #[msg("Market already holds the fields added since launch")]
+    MarketAlreadyMigrated,
+    #[msg("Vault already holds the fields added since launch")]
+    VaultAlreadyMigrated,
+    #[msg("Vault cannot expire in the second it starts")]
+    VaultCannotExpireAtStart,
+    #[msg("Every market linked to the vault must be passed")]
+    LinkedMarketMissing,
+    #[msg("Emergency has already been settled for this vault")]
+    EmergencyAlreadySettled,
+    #[msg("Market must be migrated with migrate_market first")]
+    MarketNotMigrated,
+}
+
+impl From<exponent_time_curve::math::CurveError> for ExponentCoreError {
+    fn from(e: exponent_time_curve::math::CurveError) -> Self {
+        use exponent_time_curve::math::CurveError;
+
+        match e {
+            CurveError::InsufficientPtLiquidity => ExponentCoreError::InsufficientPtLiquidity,
+            CurveError::ExchangeRateBelowOne => ExponentCoreError::CurveOutOfRange,
+            CurveError::InvalidFeeRate => ExponentCoreError::InvalidFeeRate,
+        }
+    }
 }
@@ -9,7 +9,7 @@ use crate::{error::ExponentCoreError, MarketTwo};
 pub struct AddFarm<'info> {
     #[account(
         mut,
-        realloc = MarketTwo::size_of(&market.cpi_accounts, market.emissions.trackers.len(), market.lp_farm.farm_emissions.len() + 1),
+        realloc = MarketTwo::size_of(&market.cpi_accounts, market.emissions.trackers.len(), market.lp_farm.farm_emissions.len() + 1, market.rate_oracle.capacity.into()),
         realloc::payer = fee_payer,
         realloc::zero = false,
     )]
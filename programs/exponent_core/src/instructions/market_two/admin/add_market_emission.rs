@@ -10,7 +10,7 @@ use crate::{cpi_common::CpiAccounts, MarketTwo};
 pub struct AddMarketEmission<'info> {
     #[account(
         mut,
-        realloc = MarketTwo::size_of(&cpi_accounts, market.emissions.trackers.len() + 1, market.lp_farm.farm_emissions.len()),
+        realloc = MarketTwo::size_of(&cpi_accounts, market.emissions.trackers.len() + 1, market.lp_farm.farm_emissions.len(), market.rate_oracle.capacity.into()),
         realloc::payer = fee_payer,
         realloc::zero = false,
     )]
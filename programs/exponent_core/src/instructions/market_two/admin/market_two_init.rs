@@ -2,7 +2,7 @@ use crate::{
     cpi_common::CpiAccounts,
     seeds::MARKET_SEED,
     utils::{cpi_init_sy_personal_account, do_deposit_sy},
-    MarketTwo, Vault, ID,
+    MarketTwo, RateOracle, Vault, ID,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
@@ -43,12 +43,14 @@ pub struct MarketTwoInit<'info> {
             &[seed_id],
         ],
         bump,
-        space = MarketTwo::size_of(&cpi_accounts, 0, 0)
+        space = MarketTwo::size_of(&cpi_accounts, 0, 0, RateOracle::DEFAULT_CAPACITY.into())
     )]
     pub market: Account<'info, MarketTwo>,
 
     /// Links the mint_sy & mint_pt & sy_program together
+    /// Records the market, so the vault can wind down all of its markets
     #[account(
+        mut,
         has_one = mint_sy,
         has_one = mint_pt,
         has_one = sy_program
@@ -361,6 +363,7 @@ pub fn handler<'info>(
         seed_id,
     );
     ctx.accounts.market.set_inner(market);
+    ctx.accounts.vault.link_market(seed_id);
 
     ctx.accounts.create_lp_mint()?;
 
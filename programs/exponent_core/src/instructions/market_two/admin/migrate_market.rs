@@ -0,0 +1,75 @@
+use crate::{error::ExponentCoreError, state::MarketTwo};
+use anchor_lang::prelude::*;
+use exponent_admin::Admin;
+
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    /// CHECK: deserialized as a market created before the appended fields
+    #[account(mut, owner = crate::ID)]
+    pub market: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub admin_state: Account<'info, Admin>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl MigrateMarket<'_> {
+    pub fn validate(&self) -> Result<()> {
+        self.admin_state
+            .principles
+            .cold_admin
+            .is_admin(&self.signer.key())?;
+
+        Ok(())
+    }
+}
+
+/// Extend a market created before the fields appended to MarketTwo, so the program can load it
+/// Deploying the upgrade requires running this once for every existing market, before the market is used again
+/// The account grows by MarketTwo::MIGRATION_SIZE, and the appended fields take their defaults:
+/// the logit curve, no dynamic fee, the PT fee for YT trades, and a rate oracle with no capacity
+/// The rate oracle is given capacity afterwards with realloc_market
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<MigrateMarket>) -> Result<()> {
+    let market = ctx.accounts.market.to_account_info();
+
+    let market_two = MarketTwo::from_unmigrated(&market.try_borrow_data()?)?;
+
+    let new_size = MarketTwo::size_of(
+        &market_two.cpi_accounts,
+        market_two.emissions.trackers.len(),
+        market_two.lp_farm.farm_emissions.len(),
+        0,
+    );
+
+    // markets that already hold the appended fields have room for them
+    require!(
+        market.data_len() < new_size,
+        ExponentCoreError::MarketAlreadyMigrated
+    );
+
+    let lamports_required = Rent::get()?.minimum_balance(new_size);
+    let lamports_to_transfer = lamports_required.saturating_sub(market.lamports());
+
+    if lamports_to_transfer > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.signer.to_account_info(),
+                    to: market.clone(),
+                },
+            ),
+            lamports_to_transfer,
+        )?;
+    }
+
+    market.realloc(new_size, true)?;
+
+    let mut data = market.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    market_two.try_serialize(&mut writer)
+}
@@ -15,3 +15,6 @@ pub use modify_farm::*;
 
 pub mod realloc_market;
 pub use realloc_market::*;
+
+pub mod migrate_market;
+pub use migrate_market::*;
@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
 use exponent_admin::Admin;
 
-use crate::{cpi_common::CpiAccounts, LiquidityNetBalanceLimits, MarketTwo};
+use crate::{
+    cpi_common::CpiAccounts, error::ExponentCoreError, DynamicFee, LiquidityNetBalanceLimits,
+    MarketTwo, YtFee,
+};
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub enum MarketAdminAction {
@@ -10,6 +13,15 @@ pub enum MarketAdminAction {
     ChangeTreasuryTradeSyBpsFee(u16),
     ChangeLnFeeRateRoot(f64),
     ChangeRateScalarRoot(f64),
+    ChangeYtTreasuryTradeSyBpsFee(u16),
+    ChangeYtLnFeeRateRoot(f64),
+    /// Charge YT trades the PT fee again
+    RemoveYtFee,
+    ChangeDynamicFee {
+        sensitivity: f64,
+        max_ln_fee_rate_root: f64,
+        window_seconds: u32,
+    },
     ChangeCpiAccounts {
         cpi_accounts: CpiAccounts,
     },
@@ -55,6 +67,14 @@ fn validate_status_update(
     admin_state.principles.cold_admin.is_admin(signer)
 }
 
+/// The market's YT fee, which starts as a copy of the PT fee if it has not been set yet
+fn yt_fee_or_pt_fee(market: &mut MarketTwo) -> &mut YtFee {
+    market.yt_fee.get_or_insert(YtFee {
+        ln_fee_rate_root: market.financials.ln_fee_rate_root,
+        treasury_sy_bps: market.fee_treasury_sy_bps,
+    })
+}
+
 #[access_control(ctx.accounts.validate())]
 pub fn handler(ctx: Context<ModifyMarketSetting>, action: MarketAdminAction) -> Result<()> {
     let market = &mut ctx.accounts.market;
@@ -111,6 +131,74 @@ pub fn handler(ctx: Context<ModifyMarketSetting>, action: MarketAdminAction) ->
 
             market.financials.rate_scalar_root = new_rate_scalar_root;
         }
+        MarketAdminAction::ChangeYtTreasuryTradeSyBpsFee(new_treasury_trade_sy_bps_fee) => {
+            ctx.accounts
+                .admin_state
+                .principles
+                .cold_admin
+                .is_admin(ctx.accounts.signer.key)?;
+
+            require!(
+                new_treasury_trade_sy_bps_fee <= 10000,
+                ExponentCoreError::InvalidFeeRate
+            );
+
+            yt_fee_or_pt_fee(market).treasury_sy_bps = new_treasury_trade_sy_bps_fee;
+        }
+        MarketAdminAction::ChangeYtLnFeeRateRoot(new_ln_fee_rate_root) => {
+            ctx.accounts
+                .admin_state
+                .principles
+                .hot_admin
+                .is_admin(ctx.accounts.signer.key)?;
+
+            require!(
+                new_ln_fee_rate_root.is_finite() && new_ln_fee_rate_root >= 0.0,
+                ExponentCoreError::InvalidFeeRate
+            );
+
+            yt_fee_or_pt_fee(market).ln_fee_rate_root = new_ln_fee_rate_root;
+        }
+        MarketAdminAction::RemoveYtFee => {
+            ctx.accounts
+                .admin_state
+                .principles
+                .cold_admin
+                .is_admin(ctx.accounts.signer.key)?;
+
+            market.yt_fee = None;
+        }
+        MarketAdminAction::ChangeDynamicFee {
+            sensitivity,
+            max_ln_fee_rate_root,
+            window_seconds,
+        } => {
+            ctx.accounts
+                .admin_state
+                .principles
+                .hot_admin
+                .is_admin(ctx.accounts.signer.key)?;
+
+            let dynamic_fee = DynamicFee {
+                sensitivity,
+                max_ln_fee_rate_root,
+                window_seconds,
+            };
+
+            require!(
+                sensitivity.is_finite()
+                    && sensitivity >= 0.0
+                    && max_ln_fee_rate_root.is_finite()
+                    && max_ln_fee_rate_root >= 0.0,
+                ExponentCoreError::InvalidFeeRate
+            );
+            require!(
+                !dynamic_fee.is_enabled() || window_seconds > 0,
+                ExponentCoreError::InvalidTwapWindow
+            );
+
+            market.dynamic_fee = dynamic_fee;
+        }
         MarketAdminAction::ChangeCpiAccounts { cpi_accounts } => {
             ctx.accounts
                 .admin_state
@@ -123,6 +211,7 @@ pub fn handler(ctx: Context<ModifyMarketSetting>, action: MarketAdminAction) ->
                 &cpi_accounts,
                 market.emissions.trackers.len(),
                 market.lp_farm.farm_emissions.len(),
+                market.rate_oracle.capacity.into(),
             );
 
             if new_size > old_size {
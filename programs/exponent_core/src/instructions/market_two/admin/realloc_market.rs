@@ -1,3 +1,4 @@
+use crate::{error::ExponentCoreError, state::MarketTwo};
 use anchor_lang::prelude::*;
 use exponent_admin::Admin;
 
@@ -29,10 +30,18 @@ impl ReallocMarket<'_> {
 }
 
 /// This instruction is used to reallocate the market account to a new size with an additional byte.
+/// Any space the market does not use goes to the rate oracle.
+/// Markets created before the rate oracle are migrated with migrate_market first.
 #[access_control(ctx.accounts.validate())]
 pub fn handler(ctx: Context<ReallocMarket>, additional_bytes: u64) -> Result<()> {
     let market = &mut ctx.accounts.market;
 
+    // once grown, an unmigrated market would read its appended fields from the new zeroed bytes
+    require!(
+        MarketTwo::try_deserialize(&mut &market.try_borrow_data()?[..]).is_ok(),
+        ExponentCoreError::MarketNotMigrated
+    );
+
     let current_size = market.to_account_info().data_len();
     let new_size = current_size + additional_bytes as usize;
 
@@ -56,5 +65,17 @@ pub fn handler(ctx: Context<ReallocMarket>, additional_bytes: u64) -> Result<()>
 
     market.realloc(new_size, false)?;
 
-    Ok(())
+    grow_rate_oracle(&market.to_account_info())
+}
+
+/// Let the rate oracle grow into whatever space the market does not use
+fn grow_rate_oracle(market: &AccountInfo) -> Result<()> {
+    let mut data = market.try_borrow_mut_data()?;
+
+    let mut market_two = MarketTwo::try_deserialize(&mut &data[..])?;
+
+    market_two.grow_rate_oracle(data.len());
+
+    let mut writer: &mut [u8] = &mut data;
+    market_two.try_serialize(&mut writer)
 }
@@ -164,15 +164,20 @@ impl<'i> BuyYt<'i> {
     }
 
     pub fn validate(&self) -> Result<()> {
-        require!(
-            self.market.check_status_flags(STATUS_CAN_BUY_YT),
-            ExponentCoreError::BuyingYtDisabled
-        );
-
-        Ok(())
+        validate_buy_yt(&self.market)
     }
 }
 
+/// Check that the market allows buying YT
+pub fn validate_buy_yt(market: &MarketTwo) -> Result<()> {
+    require!(
+        market.check_status_flags(STATUS_CAN_BUY_YT),
+        ExponentCoreError::BuyingYtDisabled
+    );
+
+    Ok(())
+}
+
 /// To buy YT with SY, the following steps are taken:
 /// - Set the max amount of SY you will spend
 /// - Borrow an amount of SY from the pool
@@ -191,35 +196,21 @@ pub fn handler<'i>(
         &ctx.accounts.market.cpi_accounts,
         ctx.remaining_accounts,
         ctx.accounts.sy_program.key(),
-    )
-    .map(|x| x.exchange_rate)
-    .expect("failed to get sy state");
-
-    // calculate how much SY must be stripped to get the target YT
-    // Use ceiling division
-    let sy_to_strip = py_to_sy_ceil(sy_exchange_rate, yt_out);
-
-    assert!(
-        sy_to_strip > sy_in,
-        "sy_to_strip must be greater than max_sy_in"
-    );
+    )?
+    .exchange_rate;
 
-    let sy_to_borrow = sy_to_strip - sy_in;
-
-    // Check borrow amount is less than target strip amount
-    assert!(
-        sy_to_borrow < sy_to_strip,
-        "sy_to_borrow must be less than sy_to_strip"
-    );
-
-    // The total amount of SY spent by the trader is the amount of SY stripped minus the amount of SY borrowed
-    let net_sy_spend = sy_to_strip - sy_to_borrow;
+    execute_buy_yt(ctx, sy_exchange_rate, sy_in, yt_out)
+}
 
-    // Check slippage
-    assert!(
-        net_sy_spend <= sy_in,
-        "net_sy_spend must be less than or equal to max_sy_in"
-    );
+/// Borrow, strip, and sell the PT back into the market to deliver `yt_out` to the trader
+/// Shared by the buy_yt instruction variants once the YT amount is known
+pub(crate) fn execute_buy_yt<'i>(
+    ctx: Context<'_, '_, '_, 'i, BuyYt<'i>>,
+    sy_exchange_rate: Number,
+    sy_in: u64,
+    yt_out: u64,
+) -> Result<BuyYtEvent> {
+    let (sy_to_strip, sy_to_borrow) = handle_buy_yt_borrow(sy_exchange_rate, sy_in, yt_out)?;
 
     // =========== Perform the borrow ===========
 
@@ -233,13 +224,10 @@ pub fn handler<'i>(
         ctx.remaining_accounts,
         ctx.accounts.sy_program.key(),
         &[&ctx.accounts.market.signer_seeds()],
-    )
-    .expect("failed to withdraw sy from CPI");
+    )?;
 
     // borrow the SY to the trader's token account
-    ctx.accounts
-        .do_borrow_sy(sy_to_borrow)
-        .expect("borrow SY failed");
+    ctx.accounts.do_borrow_sy(sy_to_borrow)?;
 
     // ========== Done with the borrow ===========
     // strip sy_to_strip
@@ -247,9 +235,8 @@ pub fn handler<'i>(
         ctx.accounts.strip_sy_accounts(),
         ctx.remaining_accounts,
         sy_to_strip,
-    )
-    .map(|x| x.amount_py_out)
-    .expect("Strip failed");
+    )?
+    .amount_py_out;
 
     ctx.accounts.market.is_current_flash_swap = true;
     ctx.accounts.market.exit(&crate::ID)?;
@@ -258,21 +245,22 @@ pub fn handler<'i>(
     // constrain it so that the trader receives at least the borrowed amount of SY
     // although, the trader should receive the exact amount of SY they expect, since we ran a simulated sale of PT above
     let market_pre_sy_balance = ctx.accounts.market.financials.sy_balance;
-    let pt_sell: i64 = pt_out.try_into().unwrap();
+    let pt_sell: i64 = pt_out
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
+    let sy_min_out: i64 = sy_to_borrow
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
     let res = do_cpi_trade_pt(
         ctx.accounts.trade_pt_accounts(),
         ctx.remaining_accounts,
-        pt_sell * -1i64,
-        sy_to_borrow.try_into().unwrap(),
-    )
-    .expect("sell PT failed");
+        -pt_sell,
+        sy_min_out,
+    )?;
     let received_sy = res.net_trader_sy as u64;
 
-    assert!(
-        received_sy >= sy_to_borrow,
-        "received SY must be greater than or equal to borrowed SY"
-    );
-    let sy_leftover = received_sy.checked_sub(sy_to_borrow).unwrap();
+    // Gift the leftover Dust to the Market
+    let sy_to_repay = handle_buy_yt_repay(received_sy, sy_to_borrow)?;
 
     // Reload the market state before mutating
     ctx.accounts.market.reload()?;
@@ -281,18 +269,17 @@ pub fn handler<'i>(
 
     let market_post_sy_balance = ctx.accounts.market.financials.sy_balance;
 
-    assert!(
-        market_post_sy_balance <= market_pre_sy_balance - received_sy,
-        "market SY post balance must be less than or equal to pre-trade SY balance, because of the treasury fee"
+    // market SY post balance must be less than or equal to pre-trade SY balance, because of the treasury fee
+    let market_sy_after_payout = market_pre_sy_balance
+        .checked_sub(received_sy)
+        .ok_or(ExponentCoreError::MathOverflow)?;
+    require!(
+        market_post_sy_balance <= market_sy_after_payout,
+        ExponentCoreError::NetBalanceChangeExceedsLimit
     );
 
-    // Gift the leftover Dust to the Market
-    let sy_to_repay = sy_to_borrow + sy_leftover;
-
     // repay the borrowed SY to the Market's escrow account
-    ctx.accounts
-        .do_repay_sy(sy_to_repay)
-        .expect("repay SY failed");
+    ctx.accounts.do_repay_sy(sy_to_repay)?;
 
     // Deposit the SY into the SY program from the Market's escrow account
     do_deposit_sy(
@@ -303,8 +290,7 @@ pub fn handler<'i>(
         ctx.remaining_accounts,
         ctx.accounts.sy_program.key(),
         &[&ctx.accounts.market.signer_seeds()],
-    )
-    .expect("CPI deposit SY failed");
+    )?;
 
     let event = BuyYtEvent {
         trader: ctx.accounts.trader.key(),
@@ -329,6 +315,46 @@ pub fn handler<'i>(
     Ok(event)
 }
 
+/// Calculate the SY to strip for `yt_out`, and the part of it borrowed from the market
+/// Returns the amounts of SY to strip and to borrow
+pub fn handle_buy_yt_borrow(
+    sy_exchange_rate: Number,
+    sy_in: u64,
+    yt_out: u64,
+) -> Result<(u64, u64)> {
+    // calculate how much SY must be stripped to get the target YT
+    // Use ceiling division
+    let sy_to_strip = py_to_sy_ceil(sy_exchange_rate, yt_out);
+
+    require!(sy_to_strip > sy_in, ExponentCoreError::SyInExceedsStrip);
+
+    let sy_to_borrow = sy_to_strip - sy_in;
+
+    // Check borrow amount is less than target strip amount
+    require!(
+        sy_to_borrow < sy_to_strip,
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    // The total amount of SY spent by the trader is the amount of SY stripped minus the amount of SY borrowed
+    let net_sy_spend = sy_to_strip - sy_to_borrow;
+
+    // Check slippage
+    require!(net_sy_spend <= sy_in, ExponentCoreError::SlippageExceeded);
+
+    Ok((sy_to_strip, sy_to_borrow))
+}
+
+/// Check the sale of the stripped PT covers the borrowed SY
+/// Returns the SY repaid to the market, which includes any leftover dust
+pub fn handle_buy_yt_repay(received_sy: u64, sy_to_borrow: u64) -> Result<u64> {
+    let sy_leftover = received_sy
+        .checked_sub(sy_to_borrow)
+        .ok_or(ExponentCoreError::SlippageExceeded)?;
+
+    Ok(sy_to_borrow + sy_leftover)
+}
+
 #[event]
 pub struct BuyYtEvent {
     pub trader: Pubkey,
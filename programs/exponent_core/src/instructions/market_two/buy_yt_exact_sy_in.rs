@@ -0,0 +1,70 @@
+use crate::{error::ExponentCoreError, state::MarketTwo, utils::do_get_sy_state};
+use anchor_lang::prelude::*;
+use precise_number::Number;
+
+use super::buy_yt::{execute_buy_yt, BuyYt, BuyYtEvent};
+
+/// Buy YT by spending at most an exact amount of SY
+///
+/// The amount of YT is solved on-chain as the largest amount whose borrow, strip and PT sale
+/// fits within `sy_in`, using the same fee and rounding rules as buy_yt.
+/// The solve is seeded at the spot YT price and capped at MAX_SOLVER_PROBES quotes of the PT sale.
+/// The trader only spends the solved cost, so any SY left over from rounding stays with the trader.
+#[access_control(ctx.accounts.validate())]
+pub fn handler<'i>(
+    ctx: Context<'_, '_, '_, 'i, BuyYt<'i>>,
+    sy_in: u64,
+    min_yt_out: u64,
+) -> Result<BuyYtEvent> {
+    let now = Clock::get()?.unix_timestamp as u64;
+    require!(
+        ctx.accounts.market.is_active(now),
+        ExponentCoreError::MarketExpired
+    );
+
+    // get exchange rate for SY
+    let sy_exchange_rate = do_get_sy_state(
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.market.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?
+    .exchange_rate;
+
+    let (sy_spend, yt_out) = handle_buy_yt_exact_sy_in(
+        &ctx.accounts.market,
+        sy_exchange_rate,
+        now,
+        sy_in,
+        min_yt_out,
+    )?;
+
+    execute_buy_yt(ctx, sy_exchange_rate, sy_spend, yt_out)
+}
+
+/// Solve the YT bought by spending at most `sy_in`
+/// Returns the SY the trader spends and the YT they receive
+pub fn handle_buy_yt_exact_sy_in(
+    market: &MarketTwo,
+    sy_exchange_rate: Number,
+    now: u64,
+    sy_in: u64,
+    min_yt_out: u64,
+) -> Result<(u64, u64)> {
+    let financials = &market.financials;
+    let fees = market.trade_fees(now);
+    let curve_kind = market.curve_kind;
+
+    let yt_out = financials.yt_out_for_sy_in(curve_kind, sy_exchange_rate, sy_in, now, fees);
+
+    require!(
+        yt_out > 0 && yt_out >= min_yt_out,
+        ExponentCoreError::MinYtOutNotMet
+    );
+
+    let sy_spend = financials
+        .sy_in_for_yt_out(curve_kind, sy_exchange_rate, yt_out, now, fees)
+        .ok_or(ExponentCoreError::InsufficientSyLiquidity)?;
+
+    Ok((sy_spend, yt_out))
+}
@@ -1,5 +1,6 @@
 use crate::{
     error::ExponentCoreError, util::token_transfer, LpPosition, MarketTwo, PersonalYieldTrackers,
+    PositionDelegate,
 };
 use amount_value::Amount;
 use anchor_lang::prelude::*;
@@ -8,6 +9,7 @@ use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, Transfer};
 #[event_cpi]
 #[derive(Accounts)]
 pub struct ClaimFarmEmissions<'info> {
+    /// Owner of the LP position, or its operator
     #[account(mut)]
     pub owner: Signer<'info>,
 
@@ -16,7 +18,6 @@ pub struct ClaimFarmEmissions<'info> {
     #[account(
         mut,
         has_one = market,
-        has_one = owner
     )]
     pub lp_position: Account<'info, LpPosition>,
 
@@ -44,6 +45,16 @@ impl<'i> ClaimFarmEmissions<'i> {
         }
     }
 
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        PositionDelegate::verify_claim(
+            self.lp_position.key(),
+            self.lp_position.owner,
+            self.owner.key(),
+            self.token_dst.owner,
+            remaining_accounts,
+        )
+    }
+
     fn transfer_ctx(&self) -> CpiContext<'_, '_, '_, 'i, Transfer<'i>> {
         CpiContext::new(
             self.token_program.to_account_info(),
@@ -52,6 +63,7 @@ impl<'i> ClaimFarmEmissions<'i> {
     }
 }
 
+#[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
 pub fn handler(
     ctx: Context<ClaimFarmEmissions>,
     amount: Amount,
@@ -72,7 +84,7 @@ pub fn handler(
         amount_to_send,
     )?;
 
-    ctx.accounts.lp_position.farms.trackers[index].dec_staged(amount_to_send);
+    ctx.accounts.lp_position.farms.trackers[index].dec_staged(amount_to_send)?;
 
     let event = ClaimFarmEmissionsEventV2 {
         owner: ctx.accounts.owner.key(),
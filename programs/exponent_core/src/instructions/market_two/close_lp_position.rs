@@ -0,0 +1,77 @@
+use crate::{
+    error::ExponentCoreError, seeds::POSITION_DELEGATE_SEED, util::close_if_initialized,
+    LpPosition, MarketTwo,
+};
+use anchor_lang::prelude::*;
+
+/// Close an empty LpPosition and return its rent to the owner
+///
+/// The position must hold no LP, and all of its emissions and farm rewards must be claimed.
+/// This is allowed at any time, including after the market has expired.
+/// The position's delegate is closed with it, so a position re-created at the same address starts without one.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseLpPosition<'info> {
+    /// Owner of the LpPosition
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub market: Box<Account<'info, MarketTwo>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = market,
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    /// CHECK: the position's delegate, closed if one was set
+    #[account(
+        mut,
+        seeds = [
+            POSITION_DELEGATE_SEED,
+            lp_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub position_delegate: UncheckedAccount<'info>,
+}
+
+impl<'i> CloseLpPosition<'i> {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.lp_position.is_empty(),
+            ExponentCoreError::PositionNotEmpty
+        );
+
+        Ok(())
+    }
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<CloseLpPosition>) -> Result<CloseLpPositionEvent> {
+    close_if_initialized(
+        &ctx.accounts.position_delegate,
+        &ctx.accounts.owner.to_account_info(),
+    )?;
+
+    let event = CloseLpPositionEvent {
+        owner: ctx.accounts.owner.key(),
+        market: ctx.accounts.market.key(),
+        lp_position: ctx.accounts.lp_position.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct CloseLpPositionEvent {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub lp_position: Pubkey,
+    pub timestamp: i64,
+}
@@ -135,20 +135,20 @@ impl<'i> DepositLiquidity<'i> {
     }
 
     fn validate(&self) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp as u64;
+        validate_deposit_liquidity(&self.market, Clock::get()?.unix_timestamp as u64)
+    }
+}
 
-        require!(
-            self.market.is_active(current_timestamp),
-            ExponentCoreError::VaultIsNotActive
-        );
+/// Check that the market allows depositing liquidity at `now`
+pub fn validate_deposit_liquidity(market: &MarketTwo, now: u64) -> Result<()> {
+    require!(market.is_active(now), ExponentCoreError::VaultIsNotActive);
 
-        require!(
-            self.market.check_status_flags(STATUS_CAN_DEPOSIT_LIQUIDITY),
-            ExponentCoreError::DepositingLiquidityDisabled
-        );
+    require!(
+        market.check_status_flags(STATUS_CAN_DEPOSIT_LIQUIDITY),
+        ExponentCoreError::DepositingLiquidityDisabled
+    );
 
-        Ok(())
-    }
+    Ok(())
 }
 
 #[access_control(ctx.accounts.validate())]
@@ -158,11 +158,13 @@ pub fn handler<'info>(
     sy_intent: u64,
     min_lp_out: u64,
 ) -> Result<DepositLiquidityEvent> {
+    let curve_kind = ctx.accounts.market.curve_kind;
     let r = ctx.accounts.market.financials.add_liquidity(
+        curve_kind,
         sy_intent,
         pt_intent,
         ctx.accounts.mint_lp.supply,
-    );
+    )?;
 
     if r.lp_out < min_lp_out {
         return Err(ExponentCoreError::MinLpOutNotMet.into());
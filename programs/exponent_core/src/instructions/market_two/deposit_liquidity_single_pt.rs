@@ -0,0 +1,284 @@
+use crate::{error::ExponentCoreError, instructions::self_cpi, state::*, utils::do_get_sy_state};
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_interface::*};
+use precise_number::Number;
+
+use super::deposit_liquidity::validate_deposit_liquidity;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositLiquiditySinglePt<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = token_pt_escrow,
+        has_one = token_sy_escrow,
+        has_one = mint_lp,
+        has_one = sy_program,
+        has_one = address_lookup_table,
+    )]
+    pub market: Box<Account<'info, MarketTwo>>,
+
+    /// PT liquidity account
+    #[account(mut)]
+    pub token_pt_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// SY token account owned by the market
+    #[account(mut)]
+    pub token_sy_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub token_lp_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token account for PT owned by the depositor
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub token_pt_depositor: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token account for SY owned by the depositor, which receives the SY from the PT sale
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub token_sy_depositor: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: constrained by market
+    pub address_lookup_table: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by market
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub token_fee_treasury_sy: UncheckedAccount<'info>,
+
+    /// Pass with lp_position to stake the LP tokens
+    /// CHECK: Checked by deposit_lp
+    #[account(mut)]
+    pub token_lp_escrow: Option<UncheckedAccount<'info>>,
+
+    /// Pass with token_lp_escrow to stake the LP tokens
+    /// CHECK: Checked by deposit_lp
+    #[account(mut)]
+    pub lp_position: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'i> DepositLiquiditySinglePt<'i> {
+    fn validate(&self) -> Result<()> {
+        validate_deposit_liquidity(&self.market, Clock::get()?.unix_timestamp as u64)
+    }
+
+    fn to_trade_pt_accounts(&self) -> self_cpi::TradePtAccounts<'i> {
+        self_cpi::TradePtAccounts {
+            trader: self.depositor.to_account_info(),
+            market: self.market.to_account_info(),
+            token_sy_trader: self.token_sy_depositor.to_account_info(),
+            token_pt_trader: self.token_pt_depositor.to_account_info(),
+            token_sy_escrow: self.token_sy_escrow.to_account_info(),
+            token_pt_escrow: self.token_pt_escrow.to_account_info(),
+            address_lookup_table: self.address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+            token_fee_treasury_sy: self.token_fee_treasury_sy.to_account_info(),
+        }
+    }
+
+    fn to_deposit_liquidity_accounts(&self) -> self_cpi::DepositLiquidityAccounts<'i> {
+        self_cpi::DepositLiquidityAccounts {
+            depositor: self.depositor.to_account_info(),
+            market: self.market.to_account_info(),
+            token_pt_escrow: self.token_pt_escrow.to_account_info(),
+            token_sy_escrow: self.token_sy_escrow.to_account_info(),
+            mint_lp: self.mint_lp.to_account_info(),
+            token_lp_dst: self.token_lp_dst.to_account_info(),
+            address_lookup_table: self.address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            token_pt_src: self.token_pt_depositor.to_account_info(),
+            token_sy_src: self.token_sy_depositor.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+
+    /// Accounts to stake the LP tokens, if both staking accounts were passed
+    fn to_deposit_lp_accounts(&self) -> Option<self_cpi::DepositLpAccounts<'i>> {
+        let token_lp_escrow = self.token_lp_escrow.as_ref()?;
+        let lp_position = self.lp_position.as_ref()?;
+
+        Some(self_cpi::DepositLpAccounts {
+            owner: self.depositor.to_account_info(),
+            address_lookup_table: self.address_lookup_table.to_account_info(),
+            lp_position: lp_position.to_account_info(),
+            market: self.market.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            token_lp_src: self.token_lp_dst.to_account_info(),
+            token_lp_escrow: token_lp_escrow.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            mint_lp: self.mint_lp.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        })
+    }
+}
+
+/// Calculate how much of `total_amount_pt` to sell, so that the PT kept and the SY received
+/// are in the same proportion as the market's liquidity
+/// This is the reverse of calc_strip_amount in wrapper_provide_liquidity
+pub fn calc_pt_to_sell(
+    total_amount_pt: u64,
+    exchange_rate: f64,
+    market_pt_liq: u64,
+    market_asset_liq: f64,
+) -> u64 {
+    let amt_pt = total_amount_pt as f64;
+
+    // we want to split amt_pt into two lots: A & B
+    // B will be sold for asset -- at the amount B / exchange_rate
+    // A remains
+    // The proportion A/(B / exchange_rate) should be the same as the proportion of PT/asset in the market
+    // Eq 1: A + B = amt_pt
+    // Eq 2: A / (B / exchange_rate) = market_pt_liq / market_asset_liq
+    // => A = B * market_pt_liq / (exchange_rate * market_asset_liq)
+    // => B = (amt_pt - B) * exchange_rate * market_asset_liq / market_pt_liq
+    // => B = (amt_pt * exchange_rate * market_asset_liq) / (market_pt_liq + market_asset_liq * exchange_rate)
+    let asset_in_pt = market_asset_liq * exchange_rate;
+    let to_sell = amt_pt * asset_in_pt / (market_pt_liq as f64 + asset_in_pt);
+
+    to_sell.floor() as u64
+}
+
+/// Calculate how much of `amount_pt` to sell at the market's current state
+/// Both the PT sold and the PT kept must be non-zero
+pub fn handle_pt_to_sell(
+    market: &MarketTwo,
+    sy_exchange_rate: Number,
+    now: u64,
+    amount_pt: u64,
+) -> Result<u64> {
+    let financials = &market.financials;
+    let sy_exchange_rate = sy_exchange_rate
+        .to_f64()
+        .ok_or(ExponentCoreError::MathOverflow)?;
+    let market_asset_liq = financials.sy_balance as f64 * sy_exchange_rate;
+
+    let pt_to_sell = calc_pt_to_sell(
+        amount_pt,
+        financials.exchange_rate(now),
+        financials.pt_balance,
+        market_asset_liq,
+    );
+
+    require!(
+        pt_to_sell > 0 && pt_to_sell < amount_pt,
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    Ok(pt_to_sell)
+}
+
+/// Provide liquidity with PT only
+///
+/// Sells part of the PT for SY against the curve, and deposits the rest of the PT with the SY received.
+/// Any PT or SY that does not fit the market's proportion after the sale stays with the depositor.
+/// The LP tokens are staked into the LP position when token_lp_escrow and lp_position are passed.
+#[access_control(ctx.accounts.validate())]
+pub fn handler<'i>(
+    ctx: Context<'_, '_, '_, 'i, DepositLiquiditySinglePt<'i>>,
+    amount_pt: u64,
+    min_lp_out: u64,
+) -> Result<DepositLiquiditySinglePtEvent> {
+    let now = Clock::get()?.unix_timestamp as u64;
+
+    let sy_state = do_get_sy_state(
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.market.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let pt_to_sell =
+        handle_pt_to_sell(&ctx.accounts.market, sy_state.exchange_rate, now, amount_pt)?;
+
+    let pt_sell: i64 = pt_to_sell
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
+
+    // sell the PT for SY, with the LP output bounding the price
+    let trade = self_cpi::do_cpi_trade_pt(
+        ctx.accounts.to_trade_pt_accounts(),
+        ctx.remaining_accounts,
+        -pt_sell,
+        0,
+    )?;
+
+    // Reload the market, because trading PT changes market state
+    ctx.accounts.market.reload()?;
+
+    let pt_intent = amount_pt - pt_to_sell;
+    let sy_intent = trade.net_trader_sy as u64;
+
+    let deposit = self_cpi::do_cpi_deposit_liquidity(
+        ctx.accounts.to_deposit_liquidity_accounts(),
+        ctx.remaining_accounts,
+        pt_intent,
+        sy_intent,
+        min_lp_out,
+    )?;
+
+    // Reload the market, because depositing liquidity changes market state
+    ctx.accounts.market.reload()?;
+    ctx.accounts.mint_lp.reload()?;
+
+    let is_staked = match ctx.accounts.to_deposit_lp_accounts() {
+        Some(accounts) => {
+            self_cpi::do_cpi_deposit_lp(accounts, ctx.remaining_accounts, deposit.lp_out)?;
+
+            // Reload the market, because depositing LP changes market state
+            ctx.accounts.market.reload()?;
+            true
+        }
+        None => false,
+    };
+
+    let event = DepositLiquiditySinglePtEvent {
+        depositor: ctx.accounts.depositor.key(),
+        market: ctx.accounts.market.key(),
+        amount_pt_in: amount_pt,
+        pt_sold: pt_to_sell,
+        sy_from_sale: sy_intent,
+        pt_in: deposit.pt_in,
+        sy_in: deposit.sy_in,
+        lp_out: deposit.lp_out,
+        is_staked,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct DepositLiquiditySinglePtEvent {
+    pub depositor: Pubkey,
+    pub market: Pubkey,
+    pub amount_pt_in: u64,
+    pub pt_sold: u64,
+    pub sy_from_sale: u64,
+    pub pt_in: u64,
+    pub sy_in: u64,
+    pub lp_out: u64,
+    pub is_staked: bool,
+    pub timestamp: i64,
+}
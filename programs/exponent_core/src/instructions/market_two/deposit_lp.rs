@@ -1,4 +1,7 @@
-use crate::{utils::do_get_position_state, LpPosition, MarketTwo, PersonalYieldTrackers};
+use crate::{
+    error::ExponentCoreError, utils::do_get_position_state, LpPosition, MarketTwo,
+    PersonalYieldTrackers,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     token::Token,
@@ -72,7 +75,11 @@ impl<'i> DepositLp<'i> {
         #[allow(deprecated)]
         token_2022::transfer(self.transfer_lp_in_context(), amount)?;
 
-        self.market.lp_escrow_amount = self.market.lp_escrow_amount.checked_add(amount).unwrap();
+        self.market.lp_escrow_amount = self
+            .market
+            .lp_escrow_amount
+            .checked_add(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
 
         Ok(())
     }
@@ -102,13 +109,13 @@ pub fn handler(ctx: Context<DepositLp>, amount: u64) -> Result<DepositLpEventV2>
     ctx.accounts.lp_position.stage_all(
         &ctx.accounts.market.emissions.get_last_seen_indices(),
         &ctx.accounts.market.lp_farm.get_last_seen_indices(),
-    );
+    )?;
 
     // Transfer LP tokens into account
     ctx.accounts.do_transfer_lp_in(amount)?;
 
     // Increase LP balance
-    ctx.accounts.lp_position.add_lp(amount);
+    ctx.accounts.lp_position.add_lp(amount)?;
 
     let event = DepositLpEventV2 {
         owner: ctx.accounts.owner.key(),
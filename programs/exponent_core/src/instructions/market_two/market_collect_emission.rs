@@ -1,4 +1,5 @@
 use crate::{
+    error::ExponentCoreError,
     state::*,
     util::{deserialize_lookup_table, token_transfer},
     utils::cpi_claim_emission,
@@ -12,6 +13,7 @@ use cpi_common::to_account_metas;
 #[derive(Accounts)]
 #[instruction(emission_index: u16)]
 pub struct MarketCollectEmission<'info> {
+    /// Owner of the LP position, or its operator
     #[account(mut)]
     pub owner: Signer<'info>,
 
@@ -24,7 +26,6 @@ pub struct MarketCollectEmission<'info> {
 
     #[account(
         mut,
-        has_one = owner,
         has_one = market,
     )]
     pub lp_position: Box<Account<'info, LpPosition>>,
@@ -49,6 +50,26 @@ pub struct MarketCollectEmission<'info> {
 }
 
 impl<'i> MarketCollectEmission<'i> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        // The owner may claim into any account, so the destination is only read for operators
+        if self.owner.key() == self.lp_position.owner {
+            return Ok(());
+        }
+
+        let dst_owner = {
+            let data = self.token_emission_dst.try_borrow_data()?;
+            TokenAccount::try_deserialize(&mut &data[..])?.owner
+        };
+
+        PositionDelegate::verify_claim(
+            self.lp_position.key(),
+            self.lp_position.owner,
+            self.owner.key(),
+            dst_owner,
+            remaining_accounts,
+        )
+    }
+
     fn transfer_emission_ctx(&self) -> CpiContext<'_, '_, '_, 'i, Transfer<'i>> {
         CpiContext::new(
             self.token_program.to_account_info(),
@@ -61,6 +82,7 @@ impl<'i> MarketCollectEmission<'i> {
     }
 }
 
+#[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
 pub fn handler(
     ctx: Context<MarketCollectEmission>,
     emission_index: u16,
@@ -93,7 +115,7 @@ pub fn handler(
         ctx.accounts.market.emissions.trackers[emission_index]
             .last_seen_staged
             .checked_sub(amount)
-            .unwrap();
+            .ok_or(ExponentCoreError::MathOverflow)?;
 
     let event = MarketCollectEmissionEventV2 {
         owner: ctx.accounts.owner.key(),
@@ -0,0 +1,127 @@
+use crate::{error::ExponentCoreError, instructions::trade_pt::get_sy_exchange_rate, MarketTwo};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use precise_number::Number;
+
+#[derive(Accounts)]
+pub struct MarketQuote<'info> {
+    #[account(
+        has_one = address_lookup_table,
+        has_one = sy_program,
+        has_one = mint_lp,
+    )]
+    pub market: Account<'info, MarketTwo>,
+
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: constrained by market
+    pub address_lookup_table: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by market
+    pub sy_program: UncheckedAccount<'info>,
+}
+
+/// Read-only quote of the market's current prices, returned through return data
+/// If `net_trader_pt` is given, also quote a trade_pt of that size without applying it
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, MarketQuote<'info>>,
+    net_trader_pt: Option<i64>,
+) -> Result<MarketQuoteResult> {
+    let now = Clock::get()?.unix_timestamp as u64;
+    let market = &ctx.accounts.market;
+
+    require!(market.is_active(now), ExponentCoreError::MarketExpired);
+
+    let sy_exchange_rate = get_sy_exchange_rate(
+        &ctx.accounts.address_lookup_table,
+        &market.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let financials = &market.financials;
+
+    // asset per PT, which PT converges to at expiry
+    let pt_exchange_rate = financials.exchange_rate(now);
+    let pt_price_in_asset = 1.0 / pt_exchange_rate;
+
+    let lp_supply = ctx.accounts.mint_lp.supply;
+    let lp_price_in_asset = if lp_supply == 0 {
+        0.0
+    } else {
+        financials.lp_price_in_asset(now, sy_exchange_rate, lp_supply)
+    };
+
+    let trade = net_trader_pt
+        .map(|net_trader_pt| {
+            let r = financials.quote_trade_pt(
+                market.curve_kind,
+                sy_exchange_rate,
+                net_trader_pt,
+                now,
+                market.is_current_flash_swap,
+                market.trade_fees(now),
+            )?;
+            Ok::<_, Error>(TradePtQuote {
+                net_trader_pt: r.net_trader_pt,
+                net_trader_sy: r.net_trader_sy,
+                sy_fee: r.sy_fee,
+                dynamic_sy_fee: r.dynamic_sy_fee,
+                treasury_fee_amount: r.treasury_fee_amount,
+            })
+        })
+        .transpose()?;
+
+    Ok(MarketQuoteResult {
+        market: market.key(),
+        sy_exchange_rate,
+        pt_price_in_asset,
+        yt_price_in_asset: 1.0 - pt_price_in_asset,
+        ln_implied_rate: financials.last_ln_implied_rate,
+        implied_apy: financials.last_ln_implied_rate.exp() - 1.0,
+        lp_price_in_asset,
+        fee_rate: financials.fee_rate(now),
+        rate_scalar: financials.rate_scalar(now),
+        rate_anchor: financials.rate_anchor(sy_exchange_rate, now),
+        pt_balance: financials.pt_balance,
+        sy_balance: financials.sy_balance,
+        lp_supply,
+        trade,
+        unix_timestamp: now as i64,
+    })
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MarketQuoteResult {
+    pub market: Pubkey,
+    pub sy_exchange_rate: Number,
+    /// Spot price of 1 PT in the underlying asset
+    pub pt_price_in_asset: f64,
+    /// Spot price of 1 YT in the underlying asset
+    pub yt_price_in_asset: f64,
+    pub ln_implied_rate: f64,
+    /// Annualized implied rate (APY) for PT
+    pub implied_apy: f64,
+    /// Price of 1 LP token in the underlying asset, zero if there are no LP tokens
+    pub lp_price_in_asset: f64,
+    /// Current fee rate of the curve, where 1.0 is no fee
+    pub fee_rate: f64,
+    pub rate_scalar: f64,
+    pub rate_anchor: f64,
+    pub pt_balance: u64,
+    pub sy_balance: u64,
+    pub lp_supply: u64,
+    /// Result of the hypothetical trade, if one was requested
+    pub trade: Option<TradePtQuote>,
+    pub unix_timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TradePtQuote {
+    pub net_trader_pt: i64,
+    pub net_trader_sy: i64,
+    pub sy_fee: u64,
+    /// Part of sy_fee from the dynamic fee
+    pub dynamic_sy_fee: u64,
+    pub treasury_fee_amount: u64,
+}
@@ -0,0 +1,48 @@
+use crate::{error::ExponentCoreError, MarketTwo};
+use anchor_lang::prelude::*;
+use exponent_time_curve::math::exchange_rate_from_ln_implied_rate;
+
+#[derive(Accounts)]
+pub struct MarketTwap<'info> {
+    pub market: Account<'info, MarketTwo>,
+}
+
+/// Permissionless read of the time-weighted implied rate over the last `window` seconds
+/// The result is returned through return data
+pub fn handler(ctx: Context<MarketTwap>, window: u32) -> Result<MarketTwapResult> {
+    require!(window > 0, ExponentCoreError::InvalidTwapWindow);
+
+    let now = Clock::get()?.unix_timestamp as u64;
+    let market = &ctx.accounts.market;
+
+    let ln_implied_rate = market
+        .twap_ln_implied_rate(now, window.into())
+        .ok_or(ExponentCoreError::TwapWindowNotCovered)?;
+
+    // PT converges to 1 asset at expiry, discounted by the implied rate until then
+    let sec_remaining = market.financials.expiration_ts.saturating_sub(now);
+    let pt_asset_exchange_rate =
+        exchange_rate_from_ln_implied_rate::<f64>(ln_implied_rate, sec_remaining);
+
+    Ok(MarketTwapResult {
+        market: market.key(),
+        window,
+        ln_implied_rate,
+        implied_rate: ln_implied_rate.exp() - 1.0,
+        pt_price_in_asset: 1.0 / pt_asset_exchange_rate,
+        unix_timestamp: now as i64,
+    })
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MarketTwapResult {
+    pub market: Pubkey,
+    pub window: u32,
+    /// Time-weighted average of the ln implied rate
+    pub ln_implied_rate: f64,
+    /// Annualized implied rate (APY) from the time-weighted ln implied rate
+    pub implied_rate: f64,
+    /// Price of 1 PT in the underlying asset at the time-weighted rate
+    pub pt_price_in_asset: f64,
+    pub unix_timestamp: i64,
+}
@@ -1,9 +1,18 @@
 pub mod init_lp_position;
 pub use init_lp_position::*;
 
+pub mod close_lp_position;
+pub use close_lp_position::*;
+
+pub mod split_lp_position;
+pub use split_lp_position::*;
+
 pub mod deposit_liquidity;
 pub use deposit_liquidity::*;
 
+pub mod deposit_liquidity_single_pt;
+pub use deposit_liquidity_single_pt::*;
+
 pub mod deposit_lp;
 pub use deposit_lp::*;
 
@@ -16,9 +25,21 @@ pub use withdraw_lp::*;
 pub mod trade_pt;
 pub use trade_pt::*;
 
+pub mod trade_pt_exact_sy;
+
+pub mod trade_pt_to_rate;
+
+pub mod roll_pt;
+pub use roll_pt::*;
+
+pub mod roll_lp;
+pub use roll_lp::*;
+
 pub mod buy_yt;
 pub use buy_yt::*;
 
+pub mod buy_yt_exact_sy_in;
+
 pub mod sell_yt;
 pub use sell_yt::*;
 
@@ -30,3 +51,9 @@ pub use claim_farm_emissions::*;
 
 pub mod market_collect_emission;
 pub use market_collect_emission::*;
+
+pub mod market_twap;
+pub use market_twap::*;
+
+pub mod market_quote;
+pub use market_quote::*;
@@ -0,0 +1,347 @@
+use crate::{error::ExponentCoreError, instructions::self_cpi, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_interface::*};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RollLp<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Expired market the LP tokens are withdrawn from
+    #[account(mut)]
+    pub market_src: Box<Account<'info, MarketTwo>>,
+
+    /// CHECK: Checked by withdraw_liquidity
+    #[account(mut)]
+    pub market_src_token_pt_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by withdraw_liquidity
+    #[account(mut)]
+    pub market_src_token_sy_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by withdraw_liquidity
+    pub market_src_address_lookup_table: UncheckedAccount<'info>,
+
+    /// Mint for the expired market's LP tokens
+    /// CHECK: Checked by withdraw_liquidity
+    #[account(mut)]
+    pub mint_lp_src: UncheckedAccount<'info>,
+
+    /// Token account for the expired market's LP tokens owned by the owner
+    #[account(mut)]
+    pub token_lp_src: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Expired vault the PT leg is redeemed from
+    #[account(mut)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub vault_escrow_sy: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by redeem_pt
+    pub vault_address_lookup_table: UncheckedAccount<'info>,
+
+    /// Mint of the expired PT
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub mint_pt_src: UncheckedAccount<'info>,
+
+    /// Token account for the expired PT owned by the owner, which receives the PT leg of the withdrawal
+    #[account(mut)]
+    pub token_pt_src: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token account for SY owned by the owner, which collects the SY and pays into the target market
+    #[account(mut)]
+    pub token_sy: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Market of the next maturity, which the liquidity is deposited into
+    #[account(mut)]
+    pub market: Box<Account<'info, MarketTwo>>,
+
+    /// Token account for the market's PT owned by the owner
+    #[account(mut)]
+    pub token_pt_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Checked by trade_pt and deposit_liquidity
+    #[account(mut)]
+    pub market_token_sy_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt and deposit_liquidity
+    #[account(mut)]
+    pub market_token_pt_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt and deposit_liquidity
+    pub market_address_lookup_table: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub token_fee_treasury_sy: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by deposit_liquidity
+    #[account(mut)]
+    pub mint_lp: UncheckedAccount<'info>,
+
+    /// Token account for the market's LP tokens owned by the owner
+    #[account(mut)]
+    pub token_lp_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked by withdraw_liquidity, redeem_pt, trade_pt and deposit_liquidity
+    pub sy_program: UncheckedAccount<'info>,
+}
+
+impl<'i> RollLp<'i> {
+    fn validate(&self, min_ln_implied_rate: f64, max_ln_implied_rate: f64) -> Result<()> {
+        require_keys_eq!(
+            self.market_src.vault,
+            self.vault.key(),
+            ExponentCoreError::MarketNotLinkedToVault
+        );
+
+        require!(
+            self.market.mint_sy == self.vault.mint_sy,
+            ExponentCoreError::RollTargetSyMismatch
+        );
+
+        require!(
+            min_ln_implied_rate <= max_ln_implied_rate,
+            ExponentCoreError::ImpliedRateOutOfBounds
+        );
+
+        Ok(())
+    }
+
+    fn to_withdraw_liquidity_accounts(&self) -> self_cpi::WithdrawLiquidityAccounts<'i> {
+        self_cpi::WithdrawLiquidityAccounts {
+            withdrawer: self.owner.to_account_info(),
+            market: self.market_src.to_account_info(),
+            token_pt_dst: self.token_pt_src.to_account_info(),
+            token_sy_dst: self.token_sy.to_account_info(),
+            token_pt_escrow: self.market_src_token_pt_escrow.to_account_info(),
+            token_sy_escrow: self.market_src_token_sy_escrow.to_account_info(),
+            token_lp_src: self.token_lp_src.to_account_info(),
+            mint_lp: self.mint_lp_src.to_account_info(),
+            address_lookup_table: self.market_src_address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+
+    fn to_redeem_pt_accounts(&self) -> self_cpi::RedeemPtAccounts<'i> {
+        self_cpi::RedeemPtAccounts {
+            owner: self.owner.to_account_info(),
+            authority: self.vault_authority.to_account_info(),
+            vault: self.vault.to_account_info(),
+            sy_dst: self.token_sy.to_account_info(),
+            escrow_sy: self.vault_escrow_sy.to_account_info(),
+            pt_src: self.token_pt_src.to_account_info(),
+            mint_pt: self.mint_pt_src.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            address_lookup_table: self.vault_address_lookup_table.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+
+    fn to_trade_pt_accounts(&self) -> self_cpi::TradePtAccounts<'i> {
+        self_cpi::TradePtAccounts {
+            trader: self.owner.to_account_info(),
+            market: self.market.to_account_info(),
+            token_sy_trader: self.token_sy.to_account_info(),
+            token_pt_trader: self.token_pt_dst.to_account_info(),
+            token_sy_escrow: self.market_token_sy_escrow.to_account_info(),
+            token_pt_escrow: self.market_token_pt_escrow.to_account_info(),
+            address_lookup_table: self.market_address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+            token_fee_treasury_sy: self.token_fee_treasury_sy.to_account_info(),
+        }
+    }
+
+    fn to_deposit_liquidity_accounts(&self) -> self_cpi::DepositLiquidityAccounts<'i> {
+        self_cpi::DepositLiquidityAccounts {
+            depositor: self.owner.to_account_info(),
+            market: self.market.to_account_info(),
+            token_pt_src: self.token_pt_dst.to_account_info(),
+            token_sy_src: self.token_sy.to_account_info(),
+            token_pt_escrow: self.market_token_pt_escrow.to_account_info(),
+            token_sy_escrow: self.market_token_sy_escrow.to_account_info(),
+            token_lp_dst: self.token_lp_dst.to_account_info(),
+            mint_lp: self.mint_lp.to_account_info(),
+            address_lookup_table: self.market_address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+}
+
+/// Calculate how much of `total_amount_sy` to spend on PT, so that the PT bought and the SY kept
+/// are in the same proportion as the market's liquidity
+/// This is the reverse of calc_pt_to_sell in deposit_liquidity_single_pt
+pub fn calc_sy_to_buy_pt(
+    total_amount_sy: u64,
+    sy_exchange_rate: f64,
+    exchange_rate: f64,
+    market_pt_liq: u64,
+    market_sy_liq: u64,
+) -> u64 {
+    let amt_sy = total_amount_sy as f64;
+
+    // we want to split amt_sy into two lots: A & B
+    // B will be spent on PT -- at the amount B * sy_exchange_rate * exchange_rate
+    // A remains
+    // The proportion (B * sy_exchange_rate * exchange_rate)/A should be the same as the proportion of PT/SY in the market
+    // Eq 1: A + B = amt_sy
+    // Eq 2: (B * sy_exchange_rate * exchange_rate) / A = market_pt_liq / market_sy_liq
+    // => B = (amt_sy * market_pt_liq) / (market_pt_liq + market_sy_liq * sy_exchange_rate * exchange_rate)
+    let pt_per_sy = sy_exchange_rate * exchange_rate;
+    let to_buy =
+        amt_sy * market_pt_liq as f64 / (market_pt_liq as f64 + market_sy_liq as f64 * pt_per_sy);
+
+    to_buy.floor() as u64
+}
+
+/// Roll LP tokens of an expired market into liquidity in a market with a later maturity on the same SY
+///
+/// Withdraws the liquidity, redeems its PT leg with redeem_pt, then buys enough PT in the target market
+/// to deposit the SY in the market's proportion.
+/// The market's ln implied rate after the PT purchase must be within `min_ln_implied_rate` and `max_ln_implied_rate`,
+/// and the deposit must mint at least `min_lp_out`.
+/// Any PT or SY that does not fit the market's proportion stays with the owner.
+#[access_control(ctx.accounts.validate(min_ln_implied_rate, max_ln_implied_rate))]
+pub fn handler<'i>(
+    ctx: Context<'_, '_, '_, 'i, RollLp<'i>>,
+    lp_in: u64,
+    min_lp_out: u64,
+    min_ln_implied_rate: f64,
+    max_ln_implied_rate: f64,
+) -> Result<RollLpEvent> {
+    let now = Clock::get()?.unix_timestamp as u64;
+
+    let withdraw = self_cpi::do_cpi_withdraw_liquidity(
+        ctx.accounts.to_withdraw_liquidity_accounts(),
+        ctx.remaining_accounts,
+        lp_in,
+        0,
+        0,
+    )?;
+
+    // Reload the source market, because withdrawing liquidity changes market state
+    ctx.accounts.market_src.reload()?;
+
+    let redeem = self_cpi::do_cpi_redeem_pt(
+        ctx.accounts.to_redeem_pt_accounts(),
+        ctx.remaining_accounts,
+        withdraw.pt_out,
+    )?;
+
+    // Reload the vault, because redeeming PT changes vault state
+    ctx.accounts.vault.reload()?;
+
+    let total_sy = withdraw
+        .sy_out
+        .checked_add(redeem.amount_sy_out)
+        .ok_or(ExponentCoreError::MathOverflow)?;
+
+    // both markets are on the same SY, so the rate redeem_pt saw is the target market's
+    let sy_exchange_rate = redeem
+        .sy_exchange_rate
+        .to_f64()
+        .ok_or(ExponentCoreError::MathOverflow)?;
+    let financials = &ctx.accounts.market.financials;
+    let sy_to_buy_pt = calc_sy_to_buy_pt(
+        total_sy,
+        sy_exchange_rate,
+        financials.exchange_rate(now),
+        financials.pt_balance,
+        financials.sy_balance,
+    );
+
+    require!(
+        sy_to_buy_pt > 0 && sy_to_buy_pt < total_sy,
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    let trade = self_cpi::do_cpi_trade_pt_exact_sy(
+        ctx.accounts.to_trade_pt_accounts(),
+        ctx.remaining_accounts,
+        sy_to_buy_pt,
+        0,
+    )?;
+
+    // Reload the market, because trading PT changes market state
+    ctx.accounts.market.reload()?;
+
+    let ln_implied_rate = ctx.accounts.market.financials.last_ln_implied_rate;
+    require!(
+        ln_implied_rate >= min_ln_implied_rate && ln_implied_rate <= max_ln_implied_rate,
+        ExponentCoreError::ImpliedRateOutOfBounds
+    );
+
+    let pt_intent = trade.net_trader_pt.unsigned_abs();
+    let sy_intent = total_sy - trade.net_trader_sy.unsigned_abs();
+
+    let deposit = self_cpi::do_cpi_deposit_liquidity(
+        ctx.accounts.to_deposit_liquidity_accounts(),
+        ctx.remaining_accounts,
+        pt_intent,
+        sy_intent,
+        min_lp_out,
+    )?;
+
+    // Reload the market, because depositing liquidity changes market state
+    ctx.accounts.market.reload()?;
+
+    let event = RollLpEvent {
+        owner: ctx.accounts.owner.key(),
+        market_src: ctx.accounts.market_src.key(),
+        vault: ctx.accounts.vault.key(),
+        market: ctx.accounts.market.key(),
+        lp_in,
+        pt_redeemed: withdraw.pt_out,
+        sy_withdrawn: withdraw.sy_out,
+        sy_redeemed: redeem.amount_sy_out,
+        sy_spent_on_pt: trade.net_trader_sy.unsigned_abs(),
+        pt_in: deposit.pt_in,
+        sy_in: deposit.sy_in,
+        lp_out: deposit.lp_out,
+        ln_implied_rate,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct RollLpEvent {
+    pub owner: Pubkey,
+    pub market_src: Pubkey,
+    pub vault: Pubkey,
+    pub market: Pubkey,
+    pub lp_in: u64,
+    pub pt_redeemed: u64,
+    pub sy_withdrawn: u64,
+    pub sy_redeemed: u64,
+    pub sy_spent_on_pt: u64,
+    pub pt_in: u64,
+    pub sy_in: u64,
+    pub lp_out: u64,
+    pub ln_implied_rate: f64,
+    pub timestamp: i64,
+}
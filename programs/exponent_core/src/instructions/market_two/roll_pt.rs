@@ -0,0 +1,178 @@
+use crate::{error::ExponentCoreError, instructions::self_cpi, state::*};
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_interface::*};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RollPt<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Expired vault the PT is redeemed from
+    #[account(mut)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub vault_escrow_sy: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by redeem_pt
+    pub vault_address_lookup_table: UncheckedAccount<'info>,
+
+    /// Mint of the expired PT
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub mint_pt_src: UncheckedAccount<'info>,
+
+    /// Token account for the expired PT owned by the owner
+    #[account(mut)]
+    pub token_pt_src: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token account for SY owned by the owner, which receives the redeemed SY and pays for the new PT
+    #[account(mut)]
+    pub token_sy: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Market of the next maturity, which the PT is bought from
+    #[account(mut)]
+    pub market: Box<Account<'info, MarketTwo>>,
+
+    /// Token account for the market's PT owned by the owner
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub token_pt_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub market_token_sy_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub market_token_pt_escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt
+    pub market_address_lookup_table: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by trade_pt
+    #[account(mut)]
+    pub token_fee_treasury_sy: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked by redeem_pt and trade_pt
+    pub sy_program: UncheckedAccount<'info>,
+}
+
+impl<'i> RollPt<'i> {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.market.mint_sy == self.vault.mint_sy,
+            ExponentCoreError::RollTargetSyMismatch
+        );
+
+        Ok(())
+    }
+
+    fn to_redeem_pt_accounts(&self) -> self_cpi::RedeemPtAccounts<'i> {
+        self_cpi::RedeemPtAccounts {
+            owner: self.owner.to_account_info(),
+            authority: self.vault_authority.to_account_info(),
+            vault: self.vault.to_account_info(),
+            sy_dst: self.token_sy.to_account_info(),
+            escrow_sy: self.vault_escrow_sy.to_account_info(),
+            pt_src: self.token_pt_src.to_account_info(),
+            mint_pt: self.mint_pt_src.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            address_lookup_table: self.vault_address_lookup_table.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+
+    fn to_trade_pt_accounts(&self) -> self_cpi::TradePtAccounts<'i> {
+        self_cpi::TradePtAccounts {
+            trader: self.owner.to_account_info(),
+            market: self.market.to_account_info(),
+            token_sy_trader: self.token_sy.to_account_info(),
+            token_pt_trader: self.token_pt_dst.to_account_info(),
+            token_sy_escrow: self.market_token_sy_escrow.to_account_info(),
+            token_pt_escrow: self.market_token_pt_escrow.to_account_info(),
+            address_lookup_table: self.market_address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+            token_fee_treasury_sy: self.token_fee_treasury_sy.to_account_info(),
+        }
+    }
+}
+
+/// Roll matured PT into the PT of a market with a later maturity on the same SY
+///
+/// Redeems the PT for SY with redeem_pt, and spends all of it on PT in the target market with trade_pt_exact_sy.
+/// The trade must return at least `min_pt_out`, and leave the market's ln implied rate at or above `min_ln_implied_rate`.
+#[access_control(ctx.accounts.validate())]
+pub fn handler<'i>(
+    ctx: Context<'_, '_, '_, 'i, RollPt<'i>>,
+    amount_pt: u64,
+    min_pt_out: u64,
+    min_ln_implied_rate: f64,
+) -> Result<RollPtEvent> {
+    let redeem = self_cpi::do_cpi_redeem_pt(
+        ctx.accounts.to_redeem_pt_accounts(),
+        ctx.remaining_accounts,
+        amount_pt,
+    )?;
+
+    // Reload the vault, because redeeming PT changes vault state
+    ctx.accounts.vault.reload()?;
+
+    let trade = self_cpi::do_cpi_trade_pt_exact_sy(
+        ctx.accounts.to_trade_pt_accounts(),
+        ctx.remaining_accounts,
+        redeem.amount_sy_out,
+        min_pt_out,
+    )?;
+
+    // Reload the market, because trading PT changes market state
+    ctx.accounts.market.reload()?;
+
+    let ln_implied_rate = ctx.accounts.market.financials.last_ln_implied_rate;
+    require!(
+        ln_implied_rate >= min_ln_implied_rate,
+        ExponentCoreError::ImpliedRateOutOfBounds
+    );
+
+    let event = RollPtEvent {
+        owner: ctx.accounts.owner.key(),
+        vault: ctx.accounts.vault.key(),
+        market: ctx.accounts.market.key(),
+        amount_pt_in: amount_pt,
+        sy_redeemed: redeem.amount_sy_out,
+        sy_spent: trade.net_trader_sy.unsigned_abs(),
+        pt_out: trade.net_trader_pt.unsigned_abs(),
+        ln_implied_rate,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct RollPtEvent {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub market: Pubkey,
+    pub amount_pt_in: u64,
+    pub sy_redeemed: u64,
+    pub sy_spent: u64,
+    pub pt_out: u64,
+    pub ln_implied_rate: f64,
+    pub timestamp: i64,
+}
@@ -188,35 +188,31 @@ impl<'i> SellYt<'i> {
     }
 
     pub fn validate(&self) -> Result<()> {
-        require!(
-            self.market.check_status_flags(STATUS_CAN_SELL_YT),
-            ExponentCoreError::SellingYtDisabled
-        );
-
-        Ok(())
+        validate_sell_yt(&self.market)
     }
 }
 
+/// Check that the market allows selling YT
+pub fn validate_sell_yt(market: &MarketTwo) -> Result<()> {
+    require!(
+        market.check_status_flags(STATUS_CAN_SELL_YT),
+        ExponentCoreError::SellingYtDisabled
+    );
+
+    Ok(())
+}
+
 #[access_control(ctx.accounts.validate())]
 pub fn handler<'i>(
     ctx: Context<'_, '_, '_, 'i, SellYt<'i>>,
     yt_in: u64,
     min_sy_out: u64,
 ) -> Result<SellYtEvent> {
-    // The market must have at least twice the amount of PT as YT in order to sell YT
-    // This because the trader borrows the YT amount from the market
-    // And then buys the YT amount back from the market
-    // So they take 2x the YT amount of PT from the market
-    assert!(
-        ctx.accounts.market.financials.pt_balance >= yt_in * 2,
-        "insufficient PT liquidity in the market"
-    );
+    handle_sell_yt_borrow(&ctx.accounts.market, yt_in)?;
 
     // Flash borrow PT for the user
     // This does not mutate the market financials' PT balance
-    ctx.accounts
-        .borrow_pt(yt_in)
-        .expect("Insufficient PT liquidity in the market");
+    ctx.accounts.borrow_pt(yt_in)?;
 
     // Do the merge of PT & YT into SY
     // Get the amount of SY received from the merge operation
@@ -224,46 +220,35 @@ pub fn handler<'i>(
         ctx.accounts.merge_py_account(),
         ctx.remaining_accounts,
         yt_in,
-    )
-    .map(|res| res.amount_sy_out)
-    .expect("Merge failed");
+    )?
+    .amount_sy_out;
 
     // the maximum spend of SY is the amount received from the merge operation
     let sy_constraint: i64 = sy_recv
         .try_into()
-        .expect("overflow converting sy u64 to i64");
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
 
-    let sy_constraint = sy_constraint * -1;
+    let sy_constraint = -sy_constraint;
 
     ctx.accounts.market.is_current_flash_swap = true;
     ctx.accounts.market.exit(&crate::ID)?;
 
     // perform the purchase of PT with the SY
+    let pt_buy: i64 = yt_in
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
     let sy_spent = ctx
         .accounts
-        .do_cpi_buy_pt(
-            ctx.remaining_accounts,
-            yt_in.try_into().expect("overflow converting yt_in to i64"),
-            sy_constraint,
-        )
-        .expect("Trade PT failed");
+        .do_cpi_buy_pt(ctx.remaining_accounts, pt_buy, sy_constraint)?;
 
     // must reload the market to get the updated financials
     ctx.accounts.market.reload()?;
 
     ctx.accounts.market.is_current_flash_swap = false;
 
-    // The leftover SY is the difference between the amount received from Merge and spent SY buying back PT
-    let sy_leftover = sy_recv
-        .checked_sub(sy_spent)
-        .expect("spent more SY than received from merge");
-
-    // check that the user kept at least min_sy_out
-    assert!(sy_leftover >= min_sy_out, "did not meet min_sy_out");
+    let sy_leftover = handle_sell_yt_leftover(sy_recv, sy_spent, min_sy_out)?;
 
-    ctx.accounts
-        .repay_pt(yt_in)
-        .expect("Insufficient balance to repay PT");
+    ctx.accounts.repay_pt(yt_in)?;
 
     let event = SellYtEvent {
         trader: ctx.accounts.trader.key(),
@@ -286,6 +271,35 @@ pub fn handler<'i>(
     Ok(event)
 }
 
+/// Check the market holds enough PT to lend for the sale of `yt_in`
+pub fn handle_sell_yt_borrow(market: &MarketTwo, yt_in: u64) -> Result<()> {
+    // The market must have at least twice the amount of PT as YT in order to sell YT
+    // This because the trader borrows the YT amount from the market
+    // And then buys the YT amount back from the market
+    // So they take 2x the YT amount of PT from the market
+    require!(
+        yt_in
+            .checked_mul(2)
+            .is_some_and(|pt_needed| market.financials.pt_balance >= pt_needed),
+        ExponentCoreError::InsufficientPtLiquidity
+    );
+
+    Ok(())
+}
+
+/// Returns the SY the trader keeps from the merge, after buying back the borrowed PT
+pub fn handle_sell_yt_leftover(sy_recv: u64, sy_spent: u64, min_sy_out: u64) -> Result<u64> {
+    // The leftover SY is the difference between the amount received from Merge and spent SY buying back PT
+    let sy_leftover = sy_recv
+        .checked_sub(sy_spent)
+        .ok_or(ExponentCoreError::SlippageExceeded)?;
+
+    // check that the user kept at least min_sy_out
+    require!(sy_leftover >= min_sy_out, ExponentCoreError::MinSyOutNotMet);
+
+    Ok(sy_leftover)
+}
+
 #[event]
 pub struct SellYtEvent {
     pub trader: Pubkey,
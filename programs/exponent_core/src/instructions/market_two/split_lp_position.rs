@@ -0,0 +1,132 @@
+use crate::{
+    util::now, utils::do_get_position_state, LpPosition, MarketTwo, PersonalYieldTrackers,
+};
+use amount_value::Amount;
+use anchor_lang::prelude::*;
+
+/// Move staked LP, with the same share of its staged emissions and farm rewards, into another owner's position
+///
+/// Both positions are staged first, so the LP keeps earning in the new position exactly as it did in the old one.
+/// Used by transfer_lp_position to move the whole position, and by split_lp_position to move part of it.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SplitLpPosition<'info> {
+    /// Owner of the source position
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = sy_program,
+        has_one = address_lookup_table,
+    )]
+    pub market: Box<Account<'info, MarketTwo>>,
+
+    /// Position the LP is moved out of
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = market,
+        realloc = LpPosition::static_size_of(market.emissions.trackers.len(), market.lp_farm.farm_emissions.len()),
+        realloc::payer = owner,
+        realloc::zero = false,
+    )]
+    pub src_lp_position: Box<Account<'info, LpPosition>>,
+
+    /// Position the LP is moved into, owned by anyone
+    #[account(
+        mut,
+        has_one = market,
+        realloc = LpPosition::static_size_of(market.emissions.trackers.len(), market.lp_farm.farm_emissions.len()),
+        realloc::payer = owner,
+        realloc::zero = false,
+        constraint = dst_lp_position.key() != src_lp_position.key()
+    )]
+    pub dst_lp_position: Box<Account<'info, LpPosition>>,
+
+    /// CHECK: constrained by market
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by market
+    pub address_lookup_table: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SplitLpPosition>, amount: Amount) -> Result<SplitLpPositionEvent> {
+    let current_unix_timestamp = now();
+    let is_transfer = matches!(amount, Amount::All);
+    let amount = amount.to_u64(ctx.accounts.src_lp_position.lp_balance)?;
+
+    let position_state = do_get_position_state(
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.market.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+        &[&ctx.accounts.market.signer_seeds()],
+    )?;
+
+    let current_lp_escrow_amount = ctx.accounts.market.lp_escrow_amount;
+    ctx.accounts
+        .market
+        .update_emissions_from_position_state(&position_state, current_lp_escrow_amount);
+
+    ctx.accounts
+        .market
+        .lp_farm
+        .increase_share_indexes(current_unix_timestamp, current_lp_escrow_amount);
+
+    let emission_indexes = ctx.accounts.market.emissions.get_last_seen_indices();
+    let farm_indexes = ctx.accounts.market.lp_farm.get_last_seen_indices();
+
+    ctx.accounts
+        .src_lp_position
+        .stage_all(&emission_indexes, &farm_indexes)?;
+    ctx.accounts
+        .dst_lp_position
+        .stage_all(&emission_indexes, &farm_indexes)?;
+
+    ctx.accounts
+        .src_lp_position
+        .split_into(&mut ctx.accounts.dst_lp_position, amount)?;
+
+    let event = SplitLpPositionEvent {
+        owner: ctx.accounts.owner.key(),
+        market: ctx.accounts.market.key(),
+        src_lp_position: ctx.accounts.src_lp_position.key(),
+        dst_lp_position: ctx.accounts.dst_lp_position.key(),
+        dst_owner: ctx.accounts.dst_lp_position.owner,
+        amount,
+        is_transfer,
+        src_lp_balance_after: ctx.accounts.src_lp_position.lp_balance,
+        dst_lp_balance_after: ctx.accounts.dst_lp_position.lp_balance,
+        unix_timestamp: current_unix_timestamp as i64,
+        src_emissions: ctx.accounts.src_lp_position.emissions.clone(),
+        src_farms: ctx.accounts.src_lp_position.farms.clone(),
+        dst_emissions: ctx.accounts.dst_lp_position.emissions.clone(),
+        dst_farms: ctx.accounts.dst_lp_position.farms.clone(),
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct SplitLpPositionEvent {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub src_lp_position: Pubkey,
+    pub dst_lp_position: Pubkey,
+    pub dst_owner: Pubkey,
+    pub amount: u64,
+    /// Set by transfer_lp_position, which moves the whole position, rather than split_lp_position
+    pub is_transfer: bool,
+    pub src_lp_balance_after: u64,
+    pub dst_lp_balance_after: u64,
+    pub unix_timestamp: i64,
+    pub src_emissions: PersonalYieldTrackers,
+    pub src_farms: PersonalYieldTrackers,
+    pub dst_emissions: PersonalYieldTrackers,
+    pub dst_farms: PersonalYieldTrackers,
+}
@@ -1,7 +1,7 @@
 use crate::{
     cpi_common::CpiAccounts,
     error::ExponentCoreError,
-    state::MarketTwo,
+    state::{MarketTwo, TradeResult},
     util::token_transfer,
     utils::{do_deposit_sy, do_get_sy_state, do_withdraw_sy},
     STATUS_CAN_BUY_PT, STATUS_CAN_SELL_PT,
@@ -144,19 +144,7 @@ impl<'i> TradePt<'i> {
     }
 
     pub fn validate(&self, net_trader_pt: i64) -> Result<()> {
-        if net_trader_pt > 0 {
-            require!(
-                self.market.check_status_flags(STATUS_CAN_BUY_PT),
-                ExponentCoreError::BuyingPtDisabled
-            );
-        } else {
-            require!(
-                self.market.check_status_flags(STATUS_CAN_SELL_PT),
-                ExponentCoreError::SellingPtDisabled
-            );
-        };
-
-        Ok(())
+        validate_trade_direction(&self.market, net_trader_pt)
     }
 }
 
@@ -167,7 +155,10 @@ pub fn handler<'info>(
     sy_constraint: i64,
 ) -> Result<TradePtEvent> {
     let now = Clock::get()?.unix_timestamp as u64;
-    assert!(ctx.accounts.market.is_active(now), "market is expired");
+    require!(
+        ctx.accounts.market.is_active(now),
+        ExponentCoreError::MarketExpired
+    );
 
     let sy_exchange_rate = get_sy_exchange_rate(
         &ctx.accounts.address_lookup_table,
@@ -176,48 +167,37 @@ pub fn handler<'info>(
         ctx.accounts.sy_program.key(),
     )?;
 
-    let is_current_flash_swap = ctx.accounts.market.is_current_flash_swap;
+    execute_trade(ctx, sy_exchange_rate, now, net_trader_pt, sy_constraint)
+}
 
-    let treasury_fee_sy_bps = ctx.accounts.market.fee_treasury_sy_bps;
-    let trade_result = ctx.accounts.market.financials.trade_pt(
+/// Apply a PT trade to the market and settle the tokens with the trader
+/// Shared by the trade_pt instruction variants once the PT amount is known
+pub(crate) fn execute_trade<'info>(
+    ctx: Context<'_, '_, '_, 'info, TradePt<'info>>,
+    sy_exchange_rate: Number,
+    now: u64,
+    net_trader_pt: i64,
+    sy_constraint: i64,
+) -> Result<TradePtEvent> {
+    let trade_result = handle_trade_pt(
+        &mut ctx.accounts.market,
         sy_exchange_rate,
-        net_trader_pt,
         now,
-        is_current_flash_swap,
-        treasury_fee_sy_bps,
-    );
-
-    // sanity check
-    // net_trader_sy and net_trader_pt must have opposite signs
-    assert!(
-        (net_trader_pt > 0 && trade_result.net_trader_sy < 0)
-            || (net_trader_pt < 0 && trade_result.net_trader_sy > 0),
-        "Invalid trade result -- SY change and PT change must have opposite signs"
-    );
-
-    // Slippage tolerance check -- handles both buying and selling PT
-    // - If buying PT, then net_trader_sy is negative (SY goes from trader into the pool)
-    //   The `sy_constraint` is a negative number, and represents the maximum amount of SY that can be spent in the trade.
-    //   ie, the amount of SY leaving the trader must be greater than the constraint
-    //
-    // - If selling PT, then net_trader_sy is positive (SY goes out of the pool to the trader)
-    //   The `sy_constraint` is a positive number, and represents the minimum amount of SY that can be received in the trade.
-    assert!(
-        trade_result.net_trader_sy >= sy_constraint,
-        "Slippage exceeded for buying PT"
-    );
+        net_trader_pt,
+        sy_constraint,
+    )?;
 
     // if the trader is receiving PT, then the net PT is positive
     let is_buy_pt = net_trader_pt > 0;
 
     // Transfer PT between trader & market
     ctx.accounts
-        .do_transfer_pt(trade_result.net_trader_pt.abs() as u64, is_buy_pt)?;
+        .do_transfer_pt(trade_result.net_trader_pt.unsigned_abs(), is_buy_pt)?;
 
     // Transfer SY between trader & market & sy program
     transfer_sy(
         is_buy_pt,
-        trade_result.net_trader_sy.abs() as u64,
+        trade_result.net_trader_sy.unsigned_abs(),
         &ctx.accounts.address_lookup_table,
         &ctx.accounts.market.cpi_accounts,
         &ctx.accounts.to_account_infos(),
@@ -240,6 +220,8 @@ pub fn handler<'info>(
         net_trader_sy: trade_result.net_trader_sy,
         fee_sy: trade_result.sy_fee,
         sy_exchange_rate,
+        base_fee_sy: trade_result.sy_fee - trade_result.dynamic_sy_fee,
+        dynamic_fee_sy: trade_result.dynamic_sy_fee,
         timestamp: Clock::get()?.unix_timestamp,
     };
 
@@ -261,6 +243,10 @@ pub struct TradePtEvent {
     pub fee_sy: u64,
     pub sy_exchange_rate: Number,
     pub timestamp: i64,
+    /// Part of fee_sy from the market's base fee
+    pub base_fee_sy: u64,
+    /// Part of fee_sy from the dynamic fee
+    pub dynamic_fee_sy: u64,
 }
 
 /// Transfer SY between SY Program & Market escrow & Trader
@@ -278,7 +264,9 @@ fn transfer_sy<'info>(
     treasury_fee_amount: u64,
 ) -> Result<()> {
     if is_buy_pt {
-        let amount_to_deposit = amount.checked_sub(treasury_fee_amount).unwrap();
+        let amount_to_deposit = amount
+            .checked_sub(treasury_fee_amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
         // First transfer SY from trader to escrow
         token_transfer(token_transfer_ctx, amount_to_deposit)?;
         // Then transfer SY from escrow to SY program
@@ -297,7 +285,9 @@ fn transfer_sy<'info>(
     } else {
         // First withdraw SY from SY program to escrow
         do_withdraw_sy(
-            amount.checked_add(treasury_fee_amount).unwrap(),
+            amount
+                .checked_add(treasury_fee_amount)
+                .ok_or(ExponentCoreError::MathOverflow)?,
             alt,
             cpi_accounts,
             regular_accounts,
@@ -316,7 +306,7 @@ fn transfer_sy<'info>(
     Ok(())
 }
 
-fn get_sy_exchange_rate(
+pub(crate) fn get_sy_exchange_rate(
     alt: &AccountInfo,
     cpi_accounts: &CpiAccounts,
     rem_accounts: &[AccountInfo],
@@ -326,3 +316,69 @@ fn get_sy_exchange_rate(
 
     Ok(sy_state.exchange_rate)
 }
+
+/// Check that the market allows trading PT in the direction of `net_trader_pt`
+pub fn validate_trade_direction(market: &MarketTwo, net_trader_pt: i64) -> Result<()> {
+    if net_trader_pt > 0 {
+        require!(
+            market.check_status_flags(STATUS_CAN_BUY_PT),
+            ExponentCoreError::BuyingPtDisabled
+        );
+    } else {
+        require!(
+            market.check_status_flags(STATUS_CAN_SELL_PT),
+            ExponentCoreError::SellingPtDisabled
+        );
+    };
+
+    Ok(())
+}
+
+/// Handle the trade logic for the market
+/// Returns the trade result, which the caller settles with the trader
+pub fn handle_trade_pt(
+    market: &mut MarketTwo,
+    sy_exchange_rate: Number,
+    now: u64,
+    net_trader_pt: i64,
+    sy_constraint: i64,
+) -> Result<TradeResult> {
+    let is_current_flash_swap = market.is_current_flash_swap;
+
+    // the rate before this trade is the one that held since the last observation
+    market.record_rate_observation(now);
+
+    let fees = market.trade_fees(now);
+    let curve_kind = market.curve_kind;
+    let trade_result = market.financials.trade_pt(
+        curve_kind,
+        sy_exchange_rate,
+        net_trader_pt,
+        now,
+        is_current_flash_swap,
+        fees,
+    )?;
+
+    // sanity check
+    // net_trader_sy and net_trader_pt must have opposite signs
+    // a trade too small to move any SY fails this check
+    require!(
+        (net_trader_pt > 0 && trade_result.net_trader_sy < 0)
+            || (net_trader_pt < 0 && trade_result.net_trader_sy > 0),
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    // Slippage tolerance check -- handles both buying and selling PT
+    // - If buying PT, then net_trader_sy is negative (SY goes from trader into the pool)
+    //   The `sy_constraint` is a negative number, and represents the maximum amount of SY that can be spent in the trade.
+    //   ie, the amount of SY leaving the trader must be greater than the constraint
+    //
+    // - If selling PT, then net_trader_sy is positive (SY goes out of the pool to the trader)
+    //   The `sy_constraint` is a positive number, and represents the minimum amount of SY that can be received in the trade.
+    require!(
+        trade_result.net_trader_sy >= sy_constraint,
+        ExponentCoreError::SlippageExceeded
+    );
+
+    Ok(trade_result)
+}
@@ -0,0 +1,92 @@
+use crate::{error::ExponentCoreError, state::MarketTwo, STATUS_CAN_BUY_PT};
+use anchor_lang::prelude::*;
+use precise_number::Number;
+
+use super::trade_pt::{execute_trade, get_sy_exchange_rate, TradePt, TradePtEvent};
+
+impl TradePt<'_> {
+    pub fn validate_exact_sy_in(&self) -> Result<()> {
+        validate_trade_pt_exact_sy(&self.market)
+    }
+}
+
+/// Check that the market allows buying PT with an exact amount of SY
+pub fn validate_trade_pt_exact_sy(market: &MarketTwo) -> Result<()> {
+    require!(
+        market.check_status_flags(STATUS_CAN_BUY_PT),
+        ExponentCoreError::BuyingPtDisabled
+    );
+
+    Ok(())
+}
+
+/// Buy PT by spending an exact amount of SY
+///
+/// The amount of PT is solved from the curve so that the trader pays at most `sy_in`,
+/// using the same fee and rounding rules as trade_pt.
+/// The solve is seeded at the spot price and capped at MAX_SOLVER_PROBES curve evaluations,
+/// then at MAX_SOLVER_PROBES exact trade quotes to fit its answer within `sy_in`, which can only understate the PT bought.
+/// Any SY left over from rounding stays with the trader.
+#[access_control(ctx.accounts.validate_exact_sy_in())]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, TradePt<'info>>,
+    sy_in: u64,
+    min_pt_out: u64,
+) -> Result<TradePtEvent> {
+    let now = Clock::get()?.unix_timestamp as u64;
+    require!(
+        ctx.accounts.market.is_active(now),
+        ExponentCoreError::MarketExpired
+    );
+
+    let sy_exchange_rate = get_sy_exchange_rate(
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.market.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let (net_trader_pt, sy_constraint) = handle_trade_pt_exact_sy(
+        &ctx.accounts.market,
+        sy_exchange_rate,
+        now,
+        sy_in,
+        min_pt_out,
+    )?;
+
+    execute_trade(ctx, sy_exchange_rate, now, net_trader_pt, sy_constraint)
+}
+
+/// Solve the PT bought by spending `sy_in`
+/// Returns the net_trader_pt and sy_constraint to trade
+pub fn handle_trade_pt_exact_sy(
+    market: &MarketTwo,
+    sy_exchange_rate: Number,
+    now: u64,
+    sy_in: u64,
+    min_pt_out: u64,
+) -> Result<(i64, i64)> {
+    let pt_out = market.financials.pt_out_for_sy_in(
+        market.curve_kind,
+        sy_exchange_rate,
+        sy_in,
+        now,
+        market.is_current_flash_swap,
+        market.trade_fees(now),
+    );
+
+    require!(
+        pt_out > 0 && pt_out >= min_pt_out,
+        ExponentCoreError::MinPtOutNotMet
+    );
+
+    let net_trader_pt: i64 = pt_out
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
+    let sy_constraint: i64 = sy_in
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
+
+    // the solved trade can never spend more than sy_in
+    Ok((net_trader_pt, -sy_constraint))
+}
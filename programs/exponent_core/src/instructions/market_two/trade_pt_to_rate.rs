@@ -0,0 +1,95 @@
+use crate::{error::ExponentCoreError, state::MarketTwo};
+use anchor_lang::prelude::*;
+use precise_number::Number;
+
+use super::trade_pt::{
+    execute_trade, get_sy_exchange_rate, validate_trade_direction, TradePt, TradePtEvent,
+};
+
+/// Trade PT until the market's implied rate reaches `target_ln_implied_rate`
+///
+/// The amount of PT is solved from the curve, buying PT to lower the rate or selling PT to raise it,
+/// using the same fee rules as trade_pt.
+/// The solve is seeded at the curve's closed-form PT balance for the target and capped at
+/// MAX_SOLVER_PROBES trade evaluations.
+/// `price_limit` is the worst average price of PT in SY the trader accepts:
+/// the most paid per PT when buying, or the least received per PT when selling.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, TradePt<'info>>,
+    target_ln_implied_rate: f64,
+    price_limit: Number,
+) -> Result<TradePtEvent> {
+    let now = Clock::get()?.unix_timestamp as u64;
+    require!(
+        ctx.accounts.market.is_active(now),
+        ExponentCoreError::MarketExpired
+    );
+
+    let sy_exchange_rate = get_sy_exchange_rate(
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.market.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let (net_trader_pt, sy_constraint) = handle_trade_pt_to_rate(
+        &ctx.accounts.market,
+        sy_exchange_rate,
+        now,
+        target_ln_implied_rate,
+        price_limit,
+    )?;
+
+    execute_trade(ctx, sy_exchange_rate, now, net_trader_pt, sy_constraint)
+}
+
+/// Solve the PT traded to move the market to `target_ln_implied_rate`
+/// Returns the net_trader_pt and sy_constraint to trade
+pub fn handle_trade_pt_to_rate(
+    market: &MarketTwo,
+    sy_exchange_rate: Number,
+    now: u64,
+    target_ln_implied_rate: f64,
+    price_limit: Number,
+) -> Result<(i64, i64)> {
+    let net_trader_pt = market.financials.net_trader_pt_for_ln_implied_rate(
+        market.curve_kind,
+        sy_exchange_rate,
+        target_ln_implied_rate,
+        now,
+        market.is_current_flash_swap,
+    );
+
+    // the market is already at the target
+    require!(
+        net_trader_pt != 0,
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    // the direction is only known once the trade is solved
+    validate_trade_direction(market, net_trader_pt)?;
+
+    let sy_constraint = sy_constraint_from_price_limit(net_trader_pt, price_limit)?;
+
+    Ok((net_trader_pt, sy_constraint))
+}
+
+/// Convert a worst average price into the SY bound that trade_pt checks
+pub fn sy_constraint_from_price_limit(net_trader_pt: i64, price_limit: Number) -> Result<i64> {
+    let sy_at_limit =
+        Number::from_natural_u64(net_trader_pt.unsigned_abs()).checked_mul(&price_limit);
+
+    if net_trader_pt > 0 {
+        // buying PT: spend at most the limit
+        // a limit beyond what any trade could spend leaves the trade unbounded
+        let max_sy_in = sy_at_limit
+            .and_then(|sy| i64::try_from(sy.floor_u128()).ok())
+            .unwrap_or(i64::MAX);
+        Ok(-max_sy_in)
+    } else {
+        // selling PT: receive at least the limit
+        sy_at_limit
+            .and_then(|sy| i64::try_from(sy.ceil()).ok())
+            .ok_or(ExponentCoreError::SlippageExceeded.into())
+    }
+}
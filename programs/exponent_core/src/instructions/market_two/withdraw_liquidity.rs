@@ -131,22 +131,34 @@ impl<'i> WithdrawLiquidity<'i> {
     }
 
     pub fn validate(&mut self, lp_in: u64) -> Result<()> {
-        require!(
-            self.market
-                .check_status_flags(STATUS_CAN_WITHDRAW_LIQUIDITY),
-            ExponentCoreError::WithdrawingLiquidityDisabled
-        );
-
-        self.market.liquidity_net_balance_limits.verify_limits(
+        validate_withdraw_liquidity(
+            &mut self.market,
             Clock::get()?.unix_timestamp as u32,
             self.mint_lp.supply,
-            -(lp_in as i64),
-        )?;
-
-        Ok(())
+            lp_in,
+        )
     }
 }
 
+/// Check that the market allows withdrawing liquidity, and count `lp_in` against its net balance limits at `now`
+pub fn validate_withdraw_liquidity(
+    market: &mut MarketTwo,
+    now: u32,
+    lp_supply: u64,
+    lp_in: u64,
+) -> Result<()> {
+    require!(
+        market.check_status_flags(STATUS_CAN_WITHDRAW_LIQUIDITY),
+        ExponentCoreError::WithdrawingLiquidityDisabled
+    );
+
+    market
+        .liquidity_net_balance_limits
+        .verify_limits(now, lp_supply, -(lp_in as i64))?;
+
+    Ok(())
+}
+
 #[access_control(ctx.accounts.validate(lp_in))]
 pub fn handler<'info>(
     ctx: Context<'_, '_, '_, 'info, WithdrawLiquidity<'info>>,
@@ -154,11 +166,12 @@ pub fn handler<'info>(
     min_pt_out: u64,
     min_sy_out: u64,
 ) -> Result<WithdrawLiquidityEvent> {
-    let r = ctx
-        .accounts
-        .market
-        .financials
-        .rm_liquidity(lp_in, ctx.accounts.mint_lp.supply);
+    let curve_kind = ctx.accounts.market.curve_kind;
+    let r = ctx.accounts.market.financials.rm_liquidity(
+        curve_kind,
+        lp_in,
+        ctx.accounts.mint_lp.supply,
+    )?;
 
     if r.sy_out < min_sy_out {
         return Err(ExponentCoreError::MinSyOutNotMet.into());
@@ -1,5 +1,6 @@
 use crate::{
-    util::now, utils::do_get_position_state, LpPosition, MarketTwo, PersonalYieldTrackers,
+    error::ExponentCoreError, util::now, utils::do_get_position_state, LpPosition, MarketTwo,
+    PersonalYieldTrackers,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
@@ -74,7 +75,11 @@ impl<'i> WithdrawLp<'i> {
 
     /// Transfer LP tokens from escrow to dst
     fn do_transfer_lp_out(&mut self, amount: u64) -> Result<()> {
-        self.market.lp_escrow_amount = self.market.lp_escrow_amount.checked_sub(amount).unwrap();
+        self.market.lp_escrow_amount = self
+            .market
+            .lp_escrow_amount
+            .checked_sub(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
         #[allow(deprecated)]
         token_2022::transfer(
             self.transfer_lp_out_context()
@@ -108,10 +113,10 @@ pub fn handler(ctx: Context<WithdrawLp>, amount: u64) -> Result<WithdrawLpEventV
     ctx.accounts.lp_position.stage_all(
         &ctx.accounts.market.emissions.get_last_seen_indices(),
         &ctx.accounts.market.lp_farm.get_last_seen_indices(),
-    );
+    )?;
 
     // Decrease LP balance
-    ctx.accounts.lp_position.rm_lp(amount);
+    ctx.accounts.lp_position.rm_lp(amount)?;
 
     // Transfer LP tokens into account
     ctx.accounts.do_transfer_lp_out(amount)?;
@@ -1,9 +1,11 @@
 pub mod market_two;
+pub mod position;
 mod self_cpi;
 pub mod util;
 pub mod vault;
 pub mod wrappers;
 
 pub use market_two::*;
+pub use position::*;
 pub use vault::*;
 pub use wrappers::*;
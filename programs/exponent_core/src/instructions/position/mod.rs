@@ -0,0 +1,15 @@
+pub mod remove_position_delegate;
+pub mod set_position_delegate;
+
+// Both modules define `handler`, so they are exported by name
+pub use remove_position_delegate::{RemovePositionDelegate, RemovePositionDelegateEvent};
+pub use set_position_delegate::{SetPositionDelegate, SetPositionDelegateEvent};
+
+// Client modules generated by #[derive(Accounts)], which #[program] looks up from the crate root
+pub(crate) use remove_position_delegate::__client_accounts_remove_position_delegate;
+pub(crate) use set_position_delegate::__client_accounts_set_position_delegate;
+
+#[cfg(feature = "cpi")]
+pub(crate) use remove_position_delegate::__cpi_client_accounts_remove_position_delegate;
+#[cfg(feature = "cpi")]
+pub(crate) use set_position_delegate::__cpi_client_accounts_set_position_delegate;
@@ -0,0 +1,58 @@
+use super::set_position_delegate::position_owner;
+use crate::{error::ExponentCoreError, state::*};
+use anchor_lang::prelude::*;
+
+/// Remove the operator of a position, and return the delegate's rent to the owner
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemovePositionDelegate<'info> {
+    /// Owner of the position
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: must be a YieldTokenPosition or LpPosition owned by `owner`, checked in the handler
+    pub position: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = position,
+    )]
+    pub position_delegate: Box<Account<'info, PositionDelegate>>,
+}
+
+impl<'i> RemovePositionDelegate<'i> {
+    fn validate(&self) -> Result<()> {
+        require_keys_eq!(
+            position_owner(&self.position)?,
+            self.owner.key(),
+            ExponentCoreError::NotPositionOperator
+        );
+
+        Ok(())
+    }
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<RemovePositionDelegate>) -> Result<RemovePositionDelegateEvent> {
+    let event = RemovePositionDelegateEvent {
+        owner: ctx.accounts.owner.key(),
+        position: ctx.accounts.position.key(),
+        position_delegate: ctx.accounts.position_delegate.key(),
+        operator: ctx.accounts.position_delegate.operator,
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct RemovePositionDelegateEvent {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub position_delegate: Pubkey,
+    pub operator: Pubkey,
+    pub unix_timestamp: i64,
+}
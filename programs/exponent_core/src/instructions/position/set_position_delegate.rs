@@ -0,0 +1,98 @@
+use crate::{error::ExponentCoreError, seeds::POSITION_DELEGATE_SEED, state::*};
+use anchor_lang::prelude::*;
+
+/// Appoint an operator for a YieldTokenPosition or LpPosition
+///
+/// The operator may collect interest, emissions and farm rewards of the position, but not withdraw from it
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPositionDelegate<'info> {
+    /// Owner of the position
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: must be a YieldTokenPosition or LpPosition owned by `owner`, checked in the handler
+    pub position: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PositionDelegate::SIZE_OF,
+        seeds = [
+            POSITION_DELEGATE_SEED,
+            position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub position_delegate: Box<Account<'info, PositionDelegate>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'i> SetPositionDelegate<'i> {
+    fn validate(&self) -> Result<()> {
+        require_keys_eq!(
+            position_owner(&self.position)?,
+            self.owner.key(),
+            ExponentCoreError::NotPositionOperator
+        );
+
+        Ok(())
+    }
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(
+    ctx: Context<SetPositionDelegate>,
+    operator: Pubkey,
+    beneficiary: Option<Pubkey>,
+) -> Result<SetPositionDelegateEvent> {
+    let delegate = &mut ctx.accounts.position_delegate;
+    delegate.position = ctx.accounts.position.key();
+    delegate.operator = operator;
+    delegate.beneficiary = beneficiary;
+
+    let event = SetPositionDelegateEvent {
+        owner: ctx.accounts.owner.key(),
+        position: ctx.accounts.position.key(),
+        position_delegate: ctx.accounts.position_delegate.key(),
+        operator,
+        beneficiary,
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct SetPositionDelegateEvent {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub position_delegate: Pubkey,
+    pub operator: Pubkey,
+    pub beneficiary: Option<Pubkey>,
+    pub unix_timestamp: i64,
+}
+
+/// Read the owner of a YieldTokenPosition or LpPosition
+pub(crate) fn position_owner(position: &AccountInfo) -> Result<Pubkey> {
+    require_keys_eq!(
+        *position.owner,
+        crate::ID,
+        ExponentCoreError::InvalidPosition
+    );
+
+    let data = position.try_borrow_data()?;
+
+    if let Ok(p) = YieldTokenPosition::try_deserialize(&mut &data[..]) {
+        return Ok(p.owner);
+    }
+
+    if let Ok(p) = LpPosition::try_deserialize(&mut &data[..]) {
+        return Ok(p.owner);
+    }
+
+    err!(ExponentCoreError::InvalidPosition)
+}
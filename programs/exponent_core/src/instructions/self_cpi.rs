@@ -5,6 +5,7 @@ use crate::{
     __cpi_client_accounts_deposit_lp::DepositLp,
     __cpi_client_accounts_deposit_yt::DepositYt,
     __cpi_client_accounts_merge::Merge,
+    __cpi_client_accounts_redeem_pt::RedeemPt,
     __cpi_client_accounts_sell_yt::SellYt,
     __cpi_client_accounts_strip::Strip,
     __cpi_client_accounts_trade_pt::TradePt,
@@ -27,11 +28,12 @@ use anchor_lang::{
 };
 
 use super::{
-    BuyYtEvent, DepositLiquidityEvent, MergeEvent, SellYtEvent, StripEvent, TradePtEvent,
-    WithdrawLiquidityEvent,
+    BuyYtEvent, DepositLiquidityEvent, MergeEvent, RedeemPtEvent, SellYtEvent, StripEvent,
+    TradePtEvent, WithdrawLiquidityEvent,
 };
 
 pub type MergeAccounts<'i> = Merge<'i>;
+pub type RedeemPtAccounts<'i> = RedeemPt<'i>;
 pub type StripAccounts<'i> = Strip<'i>;
 pub type TradePtAccounts<'i> = TradePt<'i>;
 pub type BuyYtAccounts<'i> = BuyYt<'i>;
@@ -144,6 +146,22 @@ pub fn do_cpi_merge<'i>(
     deser_return_data()
 }
 
+pub fn do_cpi_redeem_pt<'i>(
+    accounts: RedeemPtAccounts<'i>,
+    remaining_accounts: &[AccountInfo<'i>],
+    amount_pt: u64,
+) -> Result<RedeemPtEvent> {
+    let mut data: Vec<u8> = vec![];
+
+    let discriminator = [49];
+    data.extend_from_slice(discriminator.as_slice());
+    data.extend(&amount_pt.to_le_bytes());
+
+    do_cpi(accounts, remaining_accounts, data)?;
+
+    deser_return_data()
+}
+
 pub fn do_cpi_trade_pt<'i>(
     accounts: TradePtAccounts<'i>,
     remaining_accounts: &[AccountInfo<'i>],
@@ -162,6 +180,24 @@ pub fn do_cpi_trade_pt<'i>(
     deser_return_data()
 }
 
+pub fn do_cpi_trade_pt_exact_sy<'i>(
+    accounts: TradePtAccounts<'i>,
+    remaining_accounts: &[AccountInfo<'i>],
+    sy_in: u64,
+    min_pt_out: u64,
+) -> Result<TradePtEvent> {
+    let mut data: Vec<u8> = vec![];
+
+    let discriminator = [42];
+    data.extend_from_slice(discriminator.as_slice());
+    data.extend(&sy_in.to_le_bytes());
+    data.extend(&min_pt_out.to_le_bytes());
+
+    do_cpi(accounts, remaining_accounts, data)?;
+
+    deser_return_data()
+}
+
 pub fn do_cpi_collect_interest<'i>(
     accounts: CollectInterestAccounts<'i>,
     remaining_accounts: &[AccountInfo<'i>],
@@ -196,6 +232,24 @@ pub fn do_cpi_buy_yt<'i>(
     deser_return_data()
 }
 
+pub fn do_cpi_buy_yt_exact_sy_in<'i>(
+    accounts: BuyYtAccounts<'i>,
+    remaining_accounts: &[AccountInfo<'i>],
+    sy_in: u64,
+    min_yt_out: u64,
+) -> Result<BuyYtEvent> {
+    let mut data: Vec<u8> = vec![];
+
+    let discriminator = [43];
+    data.extend_from_slice(discriminator.as_slice());
+    data.extend(&sy_in.to_le_bytes());
+    data.extend(&min_yt_out.to_le_bytes());
+
+    do_cpi(accounts, remaining_accounts, data)?;
+
+    deser_return_data()
+}
+
 pub fn do_cpi_sell_yt<'i>(
     accounts: SellYtAccounts<'i>,
     remaining_accounts: &[AccountInfo<'i>],
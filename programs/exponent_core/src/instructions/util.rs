@@ -1,6 +1,8 @@
 use anchor_lang::{prelude::*, solana_program::address_lookup_table::state::AddressLookupTable};
 use anchor_spl::token_2022::{self, Transfer};
 
+use crate::error::ExponentCoreError;
+
 pub fn deserialize_lookup_table(account: &AccountInfo) -> Vec<Pubkey> {
     AddressLookupTable::deserialize(&account.data.borrow())
         .unwrap()
@@ -17,6 +19,26 @@ pub fn token_transfer<'i>(
     token_2022::transfer(ctx, amount)
 }
 
+/// Close a program account that may never have been created, returning its rent to `dst`
+/// An address the program does not own is left as it is
+pub fn close_if_initialized<'i>(account: &AccountInfo<'i>, dst: &AccountInfo<'i>) -> Result<()> {
+    if account.owner != &crate::ID {
+        return Ok(());
+    }
+
+    let dst_lamports = dst
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(ExponentCoreError::MathOverflow)?;
+    **dst.try_borrow_mut_lamports()? = dst_lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.assign(&System::id());
+    account.realloc(0, false)?;
+
+    Ok(())
+}
+
 pub fn now() -> u32 {
     Clock::get().unwrap().unix_timestamp as u32
 }
@@ -0,0 +1,105 @@
+use crate::{
+    error::ExponentCoreError,
+    state::{MarketTwo, Vault},
+};
+use anchor_lang::prelude::*;
+use exponent_admin::Admin;
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    /// CHECK: deserialized as a vault created before the appended fields
+    #[account(mut, owner = crate::ID)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub admin_state: Account<'info, Admin>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl MigrateVault<'_> {
+    pub fn validate(&self) -> Result<()> {
+        self.admin_state
+            .principles
+            .cold_admin
+            .is_admin(&self.signer.key())?;
+
+        Ok(())
+    }
+}
+
+/// Extend a vault created before the fields appended to Vault, so the program can load it
+/// Deploying the upgrade requires running this once for every existing vault, before the vault is used again
+/// The account grows by Vault::MIGRATION_SIZE, and the appended fields take their defaults: no settled emergency and no interest haircut
+/// Every market created for the vault is passed as a remaining account, and is recorded as linked to it
+/// A market left out can be linked later with the `LinkMarket` vault setting
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<MigrateVault>) -> Result<()> {
+    let vault_info = ctx.accounts.vault.to_account_info();
+
+    let mut vault = Vault::from_unmigrated(&vault_info.try_borrow_data()?)?;
+
+    for market in ctx.remaining_accounts {
+        vault.link_market(linked_market_seed_id(market, vault_info.key())?);
+    }
+
+    let new_size = Vault::size_of_static(vault.emissions.len()) + vault.cpi_accounts.size_of();
+
+    // vaults that already hold the appended fields have room for them
+    require!(
+        vault_info.data_len() < new_size,
+        ExponentCoreError::VaultAlreadyMigrated
+    );
+
+    let lamports_required = Rent::get()?.minimum_balance(new_size);
+    let lamports_to_transfer = lamports_required.saturating_sub(vault_info.lamports());
+
+    if lamports_to_transfer > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.signer.to_account_info(),
+                    to: vault_info.clone(),
+                },
+            ),
+            lamports_to_transfer,
+        )?;
+    }
+
+    vault_info.realloc(new_size, true)?;
+
+    let mut data = vault_info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    vault.try_serialize(&mut writer)
+}
+
+/// Seed id of a market of the vault, which may not hold the fields appended to MarketTwo yet
+/// The market must be the PDA for its seed id, so that every market linked to the vault was created by it
+pub(crate) fn linked_market_seed_id(market: &AccountInfo, vault: Pubkey) -> Result<u8> {
+    require_keys_eq!(
+        *market.owner,
+        crate::ID,
+        ExponentCoreError::MarketNotLinkedToVault
+    );
+
+    let market_two = MarketTwo::from_unmigrated(&market.try_borrow_data()?)?;
+
+    require_keys_eq!(
+        market_two.vault,
+        vault,
+        ExponentCoreError::MarketNotLinkedToVault
+    );
+
+    let market_pda = Pubkey::create_program_address(&market_two.signer_seeds(), &crate::ID)
+        .map_err(|_| ExponentCoreError::MarketNotLinkedToVault)?;
+    require_keys_eq!(
+        market_pda,
+        market.key(),
+        ExponentCoreError::MarketNotLinkedToVault
+    );
+
+    Ok(market_two.seed_id[0])
+}
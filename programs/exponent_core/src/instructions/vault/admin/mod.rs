@@ -1,11 +1,17 @@
 pub mod add_emission;
 pub mod add_lp_tokens_metadata;
 pub mod initialize_vault;
+pub mod migrate_vault;
 pub mod modify_vault_setting;
+pub mod resolve_emergency;
+pub mod settle_emergency;
 pub mod treasury;
 
 pub use add_emission::*;
 pub use add_lp_tokens_metadata::*;
 pub use initialize_vault::*;
+pub use migrate_vault::*;
 pub use modify_vault_setting::*;
+pub use resolve_emergency::*;
+pub use settle_emergency::*;
 pub use treasury::*;
@@ -1,7 +1,12 @@
 use anchor_lang::prelude::*;
 use exponent_admin::Admin;
+use precise_number::Number;
 
-use crate::{cpi_common::CpiAccounts, Vault};
+use super::migrate_vault::linked_market_seed_id;
+use crate::{
+    cpi_common::CpiAccounts, error::ExponentCoreError, util::now, utils::do_get_sy_state,
+    MarketTwo, Vault,
+};
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub enum AdminAction {
@@ -32,6 +37,13 @@ pub enum AdminAction {
     },
     ChangeAddressLookupTable(Pubkey),
     RemoveVaultEmission(u8),
+    /// Expire the vault now, at the current SY state, and wind down its markets
+    /// Remaining accounts are every market linked to the vault, writable and in any order,
+    /// followed by the vault's address lookup table and the accounts for fetching the SY state
+    ForceExpire,
+    /// Record markets created for the vault before it tracked its markets
+    /// Remaining accounts are the markets, which must be the vault's market PDAs
+    LinkMarket,
 }
 
 #[derive(Accounts)]
@@ -216,7 +228,109 @@ pub fn handler(ctx: Context<ModifyVaultSetting>, action: AdminAction) -> Result<
 
             vault.emissions.remove(emission_index as usize);
         }
+        AdminAction::ForceExpire => {
+            ctx.accounts
+                .admin_state
+                .principles
+                .cold_admin
+                .is_admin(ctx.accounts.signer.key)?;
+
+            let current_unix_timestamp = now();
+
+            let market_count = vault.linked_market_count();
+            require!(
+                ctx.remaining_accounts.len() >= market_count,
+                ExponentCoreError::LinkedMarketMissing
+            );
+            let (markets, sy_accounts) = ctx.remaining_accounts.split_at(market_count);
+
+            let address_lookup_table = sy_accounts
+                .iter()
+                .find(|a| a.key() == vault.address_lookup_table)
+                .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+
+            let sy_state = do_get_sy_state(
+                address_lookup_table,
+                &vault.cpi_accounts,
+                sy_accounts,
+                vault.sy_program,
+            )?;
+
+            vault.force_expire(&sy_state, current_unix_timestamp)?;
+
+            let expiration_ts = vault.expiration_ts();
+
+            // one distinct market per linked seed id leaves no market of the vault tradeable
+            let mut wound_down = Vec::with_capacity(market_count);
+            for market in markets {
+                let seed_id = wind_down_market(market, vault.key(), expiration_ts.into())?;
+                require!(
+                    vault.is_market_linked(seed_id) && !wound_down.contains(&seed_id),
+                    ExponentCoreError::LinkedMarketMissing
+                );
+                wound_down.push(seed_id);
+            }
+
+            emit!(VaultWindDownEvent {
+                signer: ctx.accounts.signer.key(),
+                vault: vault.key(),
+                markets: markets.iter().map(|m| m.key()).collect(),
+                expiration_ts,
+                final_sy_exchange_rate: vault.final_sy_exchange_rate,
+                final_emission_indexes: vault.emissions.iter().map(|e| e.final_index).collect(),
+                unix_timestamp: current_unix_timestamp as i64,
+            });
+        }
+        AdminAction::LinkMarket => {
+            ctx.accounts
+                .admin_state
+                .principles
+                .cold_admin
+                .is_admin(ctx.accounts.signer.key)?;
+
+            let vault_key = vault.key();
+            for market in ctx.remaining_accounts {
+                vault.link_market(linked_market_seed_id(market, vault_key)?);
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Set a market of the vault to withdraw-only, expiring with the vault
+/// Returns the market's seed id
+fn wind_down_market(market: &AccountInfo, vault: Pubkey, expiration_ts: u64) -> Result<u8> {
+    require_keys_eq!(
+        *market.owner,
+        crate::ID,
+        ExponentCoreError::MarketNotLinkedToVault
+    );
+
+    let mut data = market.try_borrow_mut_data()?;
+    let mut market_two = MarketTwo::try_deserialize(&mut &data[..])?;
+
+    require_keys_eq!(
+        market_two.vault,
+        vault,
+        ExponentCoreError::MarketNotLinkedToVault
+    );
+
+    market_two.wind_down(expiration_ts);
+
+    let mut writer: &mut [u8] = &mut data;
+    market_two.try_serialize(&mut writer)?;
+
+    Ok(market_two.seed_id[0])
+}
+
+#[event]
+pub struct VaultWindDownEvent {
+    pub signer: Pubkey,
+    pub vault: Pubkey,
+    pub markets: Vec<Pubkey>,
+    pub expiration_ts: u32,
+    pub final_sy_exchange_rate: Number,
+    pub final_emission_indexes: Vec<Number>,
+    pub unix_timestamp: i64,
+}
@@ -0,0 +1,177 @@
+use anchor_lang::prelude::*;
+use exponent_admin::Admin;
+use precise_number::Number;
+use sy_common::SyState;
+
+use crate::{
+    error::ExponentCoreError,
+    instructions::vault::common::{update_vault_yield, yield_position_earn},
+    state::*,
+    util::now,
+    utils::{do_get_sy_state, py_to_sy},
+};
+
+/// Accept a permanent fall in the SY exchange rate, and take the vault out of emergency mode
+///
+/// Resolving is the first of two steps, since the loss is only known in full once YT has staged the interest owed to it.
+/// - The all time high rate is reset to the current rate, so YT positions stage the interest they earned up to it.
+/// - Stripping, merging, redeeming PT, collecting interest and emergency withdrawals are disabled,
+///   so no PT or YT holder exits ahead of the others.
+/// - Keepers then stage every YT position with the permissionless `stage_yield`,
+///   and the admin shares the loss between PT and YT with `settle_emergency`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveEmergency<'info> {
+    pub signer: Signer<'info>,
+
+    pub admin_state: Box<Account<'info, Admin>>,
+
+    #[account(
+        mut,
+        has_one = sy_program,
+        has_one = address_lookup_table,
+        has_one = yield_position,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Vault-owned yield position
+    #[account(mut)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    /// CHECK: constrained by vault
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by vault
+    pub address_lookup_table: UncheckedAccount<'info>,
+}
+
+impl ResolveEmergency<'_> {
+    fn validate(&self) -> Result<()> {
+        self.admin_state
+            .principles
+            .cold_admin
+            .is_admin(self.signer.key)?;
+
+        Ok(())
+    }
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<ResolveEmergency>) -> Result<ResolveEmergencyEvent> {
+    let current_unix_timestamp = now();
+
+    let sy_state = do_get_sy_state(
+        &ctx.accounts.address_lookup_table.to_account_info(),
+        &ctx.accounts.vault.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let resolution = handle_resolve_emergency(
+        &mut ctx.accounts.vault,
+        &mut ctx.accounts.yield_position,
+        &sy_state,
+        current_unix_timestamp,
+    )?;
+
+    let event = ResolveEmergencyEvent {
+        signer: ctx.accounts.signer.key(),
+        vault: ctx.accounts.vault.key(),
+        all_time_high_sy_exchange_rate: resolution.all_time_high_sy_exchange_rate,
+        sy_exchange_rate: resolution.sy_exchange_rate,
+        total_sy_in_escrow: resolution.total_sy_in_escrow,
+        treasury_sy: resolution.treasury_sy,
+        uncollected_sy: resolution.uncollected_sy,
+        pt_supply: resolution.pt_supply,
+        pt_claim_sy: resolution.pt_claim_sy,
+        sy_for_pt: resolution.sy_for_pt,
+        pt_shortfall_sy: resolution.pt_shortfall_sy,
+        unix_timestamp: current_unix_timestamp as i64,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct ResolveEmergencyEvent {
+    pub signer: Pubkey,
+    pub vault: Pubkey,
+    pub all_time_high_sy_exchange_rate: Number,
+    pub sy_exchange_rate: Number,
+    pub total_sy_in_escrow: u64,
+    pub treasury_sy: u64,
+    pub uncollected_sy: u64,
+    pub pt_supply: u64,
+    pub pt_claim_sy: u64,
+    pub sy_for_pt: u64,
+    pub pt_shortfall_sy: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Snapshot of the impairment at the time it was resolved
+#[derive(Debug, Clone, Copy)]
+pub struct EmergencyResolution {
+    /// The all time high rate that was given up
+    pub all_time_high_sy_exchange_rate: Number,
+    /// The current rate, which becomes the new all time high
+    pub sy_exchange_rate: Number,
+    pub total_sy_in_escrow: u64,
+    pub treasury_sy: u64,
+    pub uncollected_sy: u64,
+    pub pt_supply: u64,
+    /// SY needed to redeem all PT at face value at the current rate
+    pub pt_claim_sy: u64,
+    /// SY set aside for PT holders, until the loss is settled
+    pub sy_for_pt: u64,
+    /// SY that PT holders are short of face value, before YT positions stage interest earned below the current rate
+    pub pt_shortfall_sy: u64,
+}
+
+pub fn handle_resolve_emergency(
+    vault: &mut Vault,
+    vault_yield_position: &mut YieldTokenPosition,
+    sy_state: &SyState,
+    now: u32,
+) -> Result<EmergencyResolution> {
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
+
+    require!(
+        vault.is_in_emergency_mode(),
+        ExponentCoreError::VaultNotInEmergencyMode
+    );
+
+    let all_time_high_sy_exchange_rate = vault.all_time_high_sy_exchange_rate;
+    let sy_exchange_rate = vault.last_seen_sy_exchange_rate;
+
+    vault.all_time_high_sy_exchange_rate = sy_exchange_rate;
+
+    // An expired vault keeps its final rate, which must not pay out interest above the current rate
+    vault.final_sy_exchange_rate = vault.final_sy_exchange_rate.min(sy_exchange_rate);
+
+    // Staging resumes now, so the unstaked YT takes its interest before PT backing is measured
+    yield_position_earn(vault, vault_yield_position)?;
+    vault.set_sy_for_pt();
+
+    let pt_claim_sy = py_to_sy(sy_exchange_rate, vault.pt_supply);
+    let pt_shortfall_sy = pt_claim_sy.saturating_sub(vault.sy_for_pt);
+
+    // PT and YT exits wait for settle_emergency, which shares the loss once YT has staged its interest
+    vault.status &= !(STATUS_CAN_STRIP
+        | STATUS_CAN_MERGE
+        | STATUS_CAN_COLLECT_INTEREST
+        | STATUS_CAN_EMERGENCY_WITHDRAW);
+
+    Ok(EmergencyResolution {
+        all_time_high_sy_exchange_rate,
+        sy_exchange_rate,
+        total_sy_in_escrow: vault.total_sy_in_escrow,
+        treasury_sy: vault.treasury_sy,
+        uncollected_sy: vault.uncollected_sy,
+        pt_supply: vault.pt_supply,
+        pt_claim_sy,
+        sy_for_pt: vault.sy_for_pt,
+        pt_shortfall_sy,
+    })
+}
@@ -0,0 +1,202 @@
+use anchor_lang::prelude::*;
+use exponent_admin::Admin;
+use precise_number::Number;
+use sy_common::SyState;
+
+use crate::{
+    error::ExponentCoreError,
+    instructions::vault::common::update_vault_yield,
+    state::*,
+    util::now,
+    utils::{do_get_sy_state, py_to_sy},
+};
+
+/// Share the loss accepted by `resolve_emergency` between PT holders and YT holders
+///
+/// Keepers stage YT positions with `stage_yield` first, so that PT backing is measured against the interest owed to YT.
+/// A vault settles at most one emergency.
+///
+/// Policy: the loss is shared pro rata by value.
+/// - PT is owed its face value at the current rate, and YT is owed the interest it has staged.
+/// - If the SY left after the treasury cannot cover both, each claim keeps the same share of its value.
+///   Uncollected SY is cut to that share, and every unit of staged interest pays out that much less SY through `interest_haircut`.
+///   Interest earned up to the settlement but staged after it takes the same haircut, keyed on `emergency_settlement_index`.
+///   PT backing is cut to the SY left over, and PT holders redeem or merge at the haircut `pt_redemption_rate`.
+/// - The flags disabled by `resolve_emergency` are left off, and the admin re-enables them with `modify_vault_setting`.
+///   Stripping should stay off until PT backing is full again, since newly stripped PT would share the shortfall.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleEmergency<'info> {
+    pub signer: Signer<'info>,
+
+    pub admin_state: Box<Account<'info, Admin>>,
+
+    #[account(
+        mut,
+        has_one = sy_program,
+        has_one = address_lookup_table,
+        has_one = yield_position,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Vault-owned yield position
+    #[account(mut)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    /// CHECK: constrained by vault
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by vault
+    pub address_lookup_table: UncheckedAccount<'info>,
+}
+
+impl SettleEmergency<'_> {
+    fn validate(&self) -> Result<()> {
+        self.admin_state
+            .principles
+            .cold_admin
+            .is_admin(self.signer.key)?;
+
+        Ok(())
+    }
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<SettleEmergency>) -> Result<SettleEmergencyEvent> {
+    let current_unix_timestamp = now();
+
+    let sy_state = do_get_sy_state(
+        &ctx.accounts.address_lookup_table.to_account_info(),
+        &ctx.accounts.vault.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let settlement = handle_settle_emergency(
+        &mut ctx.accounts.vault,
+        &mut ctx.accounts.yield_position,
+        &sy_state,
+        current_unix_timestamp,
+    )?;
+
+    let event = SettleEmergencyEvent {
+        signer: ctx.accounts.signer.key(),
+        vault: ctx.accounts.vault.key(),
+        sy_exchange_rate: settlement.sy_exchange_rate,
+        total_sy_in_escrow: settlement.total_sy_in_escrow,
+        treasury_sy: settlement.treasury_sy,
+        pt_supply: settlement.pt_supply,
+        pt_claim_sy: settlement.pt_claim_sy,
+        uncollected_claim_sy: settlement.uncollected_claim_sy,
+        share_kept: settlement.share_kept,
+        sy_for_pt: settlement.sy_for_pt,
+        uncollected_sy: settlement.uncollected_sy,
+        interest_haircut: settlement.interest_haircut,
+        emergency_settlement_index: settlement.emergency_settlement_index,
+        unix_timestamp: current_unix_timestamp as i64,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct SettleEmergencyEvent {
+    pub signer: Pubkey,
+    pub vault: Pubkey,
+    pub sy_exchange_rate: Number,
+    pub total_sy_in_escrow: u64,
+    pub treasury_sy: u64,
+    pub pt_supply: u64,
+    pub pt_claim_sy: u64,
+    pub uncollected_claim_sy: u64,
+    pub share_kept: Number,
+    pub sy_for_pt: u64,
+    pub uncollected_sy: u64,
+    pub interest_haircut: Number,
+    pub emergency_settlement_index: Number,
+    pub unix_timestamp: i64,
+}
+
+/// How the loss was shared when it was settled
+#[derive(Debug, Clone, Copy)]
+pub struct EmergencySettlement {
+    pub sy_exchange_rate: Number,
+    pub total_sy_in_escrow: u64,
+    pub treasury_sy: u64,
+    pub pt_supply: u64,
+    /// SY needed to redeem all PT at face value at the current rate
+    pub pt_claim_sy: u64,
+    /// Uncollected SY owed to YT before the haircut
+    pub uncollected_claim_sy: u64,
+    /// Share of its value that each claim keeps, which is one if the escrow covers every claim
+    pub share_kept: Number,
+    /// SY set aside for PT holders after the haircut
+    pub sy_for_pt: u64,
+    /// Uncollected SY owed to YT after the haircut
+    pub uncollected_sy: u64,
+    /// Share of staged interest withheld
+    pub interest_haircut: Number,
+    /// Final rate up to which earned interest takes the haircut
+    pub emergency_settlement_index: Number,
+}
+
+pub fn handle_settle_emergency(
+    vault: &mut Vault,
+    vault_yield_position: &mut YieldTokenPosition,
+    sy_state: &SyState,
+    now: u32,
+) -> Result<EmergencySettlement> {
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
+
+    require!(
+        !vault.is_in_emergency_mode(),
+        ExponentCoreError::VaultInEmergencyMode
+    );
+
+    require!(
+        vault.emergency_settlement_index == Number::ZERO,
+        ExponentCoreError::EmergencyAlreadySettled
+    );
+
+    let available_sy = vault
+        .total_sy_in_escrow
+        .checked_sub(vault.treasury_sy)
+        .ok_or(ExponentCoreError::MathOverflow)?;
+
+    let pt_claim_sy = py_to_sy(vault.last_seen_sy_exchange_rate, vault.pt_supply);
+    let uncollected_claim_sy = vault.uncollected_sy;
+    let claims_sy = pt_claim_sy as u128 + uncollected_claim_sy as u128;
+
+    let share_kept = if (available_sy as u128) < claims_sy {
+        Number::from_ratio(available_sy.into(), claims_sy)
+    } else {
+        Number::ONE
+    };
+
+    require!(
+        share_kept > Number::ZERO,
+        ExponentCoreError::EmergencyNotResolvable
+    );
+
+    vault.emergency_settlement_index = vault.final_sy_exchange_rate;
+    vault.interest_haircut = Number::ONE - share_kept;
+    vault.uncollected_sy = vault.settled_interest_sy(uncollected_claim_sy);
+
+    vault.set_sy_for_pt();
+
+    Ok(EmergencySettlement {
+        sy_exchange_rate: vault.last_seen_sy_exchange_rate,
+        total_sy_in_escrow: vault.total_sy_in_escrow,
+        treasury_sy: vault.treasury_sy,
+        pt_supply: vault.pt_supply,
+        pt_claim_sy,
+        uncollected_claim_sy,
+        share_kept,
+        sy_for_pt: vault.sy_for_pt,
+        uncollected_sy: vault.uncollected_sy,
+        interest_haircut: vault.interest_haircut,
+        emergency_settlement_index: vault.emergency_settlement_index,
+    })
+}
@@ -119,10 +119,11 @@ pub fn handler(
 
     match kind {
         CollectTreasuryEmissionKind::YieldPosition => {
-            ctx.accounts.yield_position.emissions[index as usize].collect(amount_to_send);
+            ctx.accounts.yield_position.emissions[index as usize].collect(amount_to_send)?;
         }
         CollectTreasuryEmissionKind::TreasuryEmission => {
-            ctx.accounts.vault.emissions[index as usize].collect_treasury_emission(amount_to_send);
+            ctx.accounts.vault.emissions[index as usize]
+                .collect_treasury_emission(amount_to_send)?;
         }
     }
 
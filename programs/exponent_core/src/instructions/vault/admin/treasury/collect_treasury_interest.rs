@@ -1,6 +1,6 @@
 use crate::{
-    instructions::util::deserialize_lookup_table, state::*, util::token_transfer,
-    utils::cpi_withdraw_sy,
+    error::ExponentCoreError, instructions::util::deserialize_lookup_table, state::*,
+    util::token_transfer, utils::cpi_withdraw_sy,
 };
 use amount_value::Amount;
 use anchor_lang::prelude::*;
@@ -92,12 +92,22 @@ pub fn handler(
 ) -> Result<()> {
     let lookup_table = deserialize_lookup_table(&ctx.accounts.address_lookup_table);
 
-    let amount_to_send = match kind {
+    // staged interest is collected in units, which pay out SY after the haircut from settled emergencies
+    let (amount_staged, amount_to_send) = match kind {
         CollectTreasuryInterestKind::YieldPosition => {
-            amount.to_u64(ctx.accounts.yield_position.interest.staged)?
+            require!(
+                ctx.accounts
+                    .vault
+                    .check_status_flags(STATUS_CAN_COLLECT_INTEREST),
+                ExponentCoreError::CollectingInterestDisabled
+            );
+
+            let staged = amount.to_u64(ctx.accounts.yield_position.interest.staged)?;
+            (staged, ctx.accounts.vault.staged_interest_sy(staged))
         }
         CollectTreasuryInterestKind::TreasuryInterest => {
-            amount.to_u64(ctx.accounts.vault.treasury_sy)?
+            let amount_sy = amount.to_u64(ctx.accounts.vault.treasury_sy)?;
+            (amount_sy, amount_sy)
         }
     };
 
@@ -113,15 +123,15 @@ pub fn handler(
 
     match kind {
         CollectTreasuryInterestKind::YieldPosition => {
-            ctx.accounts.yield_position.interest.collect(amount_to_send);
-            ctx.accounts.vault.dec_uncollected_sy(amount_to_send);
+            ctx.accounts.yield_position.interest.collect(amount_staged)?;
+            ctx.accounts.vault.dec_uncollected_sy(amount_to_send)?;
         }
         CollectTreasuryInterestKind::TreasuryInterest => {
-            ctx.accounts.vault.collect_treasury_interest(amount_to_send);
+            ctx.accounts.vault.collect_treasury_interest(amount_to_send)?;
         }
     }
 
-    ctx.accounts.vault.dec_total_sy_in_escrow(amount_to_send);
+    ctx.accounts.vault.dec_total_sy_in_escrow(amount_to_send)?;
 
 
     Ok(())
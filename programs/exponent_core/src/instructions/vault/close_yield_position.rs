@@ -0,0 +1,91 @@
+use crate::{
+    error::ExponentCoreError,
+    seeds::{COMPOUNDING_CONFIG_SEED, POSITION_DELEGATE_SEED},
+    state::*,
+    util::close_if_initialized,
+};
+use anchor_lang::prelude::*;
+
+/// Close an empty YieldTokenPosition and return its rent to the owner
+///
+/// The position must hold no YT, and all of its interest and emissions must be collected.
+/// This is allowed at any time, including after the vault has expired.
+/// The position's delegate and compounding config are closed with it, so a position re-created at the same address starts without them.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseYieldPosition<'info> {
+    /// Owner of the YieldTokenPosition
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = vault,
+        // the vault's own position is never closed
+        constraint = yield_position.key() != vault.yield_position
+    )]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    /// CHECK: the position's delegate, closed if one was set
+    #[account(
+        mut,
+        seeds = [
+            POSITION_DELEGATE_SEED,
+            yield_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub position_delegate: UncheckedAccount<'info>,
+
+    /// CHECK: the position's compounding config, closed if compounding was enabled
+    #[account(
+        mut,
+        seeds = [
+            COMPOUNDING_CONFIG_SEED,
+            yield_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub compounding_config: UncheckedAccount<'info>,
+}
+
+impl<'i> CloseYieldPosition<'i> {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.yield_position.is_empty(),
+            ExponentCoreError::PositionNotEmpty
+        );
+
+        Ok(())
+    }
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(ctx: Context<CloseYieldPosition>) -> Result<CloseYieldPositionEvent> {
+    let owner = ctx.accounts.owner.to_account_info();
+    close_if_initialized(&ctx.accounts.position_delegate, &owner)?;
+    close_if_initialized(&ctx.accounts.compounding_config, &owner)?;
+
+    let event = CloseYieldPositionEvent {
+        owner: ctx.accounts.owner.key(),
+        vault: ctx.accounts.vault.key(),
+        yield_position: ctx.accounts.yield_position.key(),
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct CloseYieldPositionEvent {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub yield_position: Pubkey,
+    pub unix_timestamp: i64,
+}
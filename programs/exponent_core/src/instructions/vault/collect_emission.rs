@@ -1,7 +1,7 @@
 use crate::{
     cpi_common::to_account_metas, error::ExponentCoreError,
     instructions::util::deserialize_lookup_table, util::token_transfer, utils::cpi_claim_emission,
-    Vault, YieldTokenPosition, YieldTokenTracker, STATUS_CAN_COLLECT_EMISSIONS,
+    PositionDelegate, Vault, YieldTokenPosition, YieldTokenTracker, STATUS_CAN_COLLECT_EMISSIONS,
 };
 use amount_value::Amount;
 use anchor_lang::prelude::*;
@@ -11,6 +11,7 @@ use anchor_spl::token_interface::{TokenAccount, TokenInterface, Transfer};
 #[derive(Accounts)]
 #[instruction(index: u16, amount: Amount)]
 pub struct CollectEmission<'info> {
+    /// Owner of the position, or its operator
     #[account(mut)]
     pub owner: Signer<'info>,
 
@@ -24,7 +25,6 @@ pub struct CollectEmission<'info> {
 
     #[account(
         mut,
-        has_one = owner,
         has_one = vault
     )]
     pub position: Account<'info, YieldTokenPosition>,
@@ -76,16 +76,23 @@ impl<'i> CollectEmission<'i> {
         token_transfer(ctx, amount)
     }
 
-    fn validate(&self) -> Result<()> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> Result<()> {
         require!(
             self.vault.check_status_flags(STATUS_CAN_COLLECT_EMISSIONS),
             ExponentCoreError::CollectingEmissionsDisabled
         );
-        Ok(())
+
+        PositionDelegate::verify_claim(
+            self.position.key(),
+            self.position.owner,
+            self.owner.key(),
+            self.emission_dst.owner,
+            remaining_accounts,
+        )
     }
 }
 
-#[access_control(ctx.accounts.validate())]
+#[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
 pub fn handler(
     ctx: Context<CollectEmission>,
     index: u16,
@@ -112,7 +119,7 @@ pub fn handler(
         ctx.accounts.vault.emissions[index as usize].fee_bps,
     )?;
 
-    ctx.accounts.position.emissions[index as usize].collect(amount_to_send);
+    ctx.accounts.position.emissions[index as usize].collect(amount_to_send)?;
 
     // Transfer emissions to the user
     ctx.accounts
@@ -170,18 +177,14 @@ pub struct CollectEmissionEventV2 {
 }
 
 fn handle_collect_emission(amount_to_send: u64, fee_bps: u16) -> Result<(u64, u64)> {
-    let fee_bps_u64 = fee_bps as u64;
-
-    let treasury_amount = (amount_to_send * fee_bps_u64 + 9999) / 10000;
-
-    // Calculate the user amount rounded down
-    let user_amount = amount_to_send.saturating_sub(treasury_amount);
-
-    // Assert that the sum of user_amount and treasury_amount equals amount_to_send
-    assert!(
-        user_amount + treasury_amount == amount_to_send,
-        "The sum of user_amount and treasury_amount should be equal to amount_to_send"
-    );
+    let treasury_amount = (amount_to_send as u128 * fee_bps as u128 + 9999) / 10000;
+    let treasury_amount =
+        u64::try_from(treasury_amount).map_err(|_| ExponentCoreError::MathOverflow)?;
+
+    // Calculate the user amount rounded down, so the two amounts add up to amount_to_send
+    let user_amount = amount_to_send
+        .checked_sub(treasury_amount)
+        .ok_or(ExponentCoreError::MathOverflow)?;
 
     Ok((user_amount, treasury_amount))
 }
@@ -17,7 +17,7 @@ use anchor_spl::{token::Token, token_2022::Transfer, token_interface::TokenAccou
 #[event_cpi]
 #[derive(Accounts)]
 pub struct CollectInterest<'info> {
-    /// Owner of the YieldTokenPosition
+    /// Owner of the YieldTokenPosition, or its operator
     #[account(mut)]
     pub owner: Signer<'info>,
 
@@ -25,7 +25,6 @@ pub struct CollectInterest<'info> {
     #[account(
         mut,
         has_one = vault,
-        has_one = owner,
     )]
     pub yield_position: Box<Account<'info, YieldTokenPosition>>,
 
@@ -82,22 +81,36 @@ impl<'i> CollectInterest<'i> {
         token_transfer(ctx, amount)
     }
 
-    fn validate(&self) -> Result<()> {
-        require!(
-            self.vault.check_status_flags(STATUS_CAN_COLLECT_INTEREST),
-            ExponentCoreError::CollectingInterestDisabled
-        );
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        validate_collect_interest(&self.vault)?;
 
-        Ok(())
+        PositionDelegate::verify_claim(
+            self.yield_position.key(),
+            self.yield_position.owner,
+            self.owner.key(),
+            self.token_sy_dst.owner,
+            remaining_accounts,
+        )
     }
 }
 
-#[access_control(ctx.accounts.validate())]
+/// Check that the vault allows collecting interest
+pub fn validate_collect_interest(vault: &Vault) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_COLLECT_INTEREST),
+        ExponentCoreError::CollectingInterestDisabled
+    );
+
+    Ok(())
+}
+
+#[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
 pub fn handler<'info>(
     ctx: Context<'_, '_, '_, 'info, CollectInterest<'info>>,
     amount: Amount,
 ) -> Result<CollectInterestEventV2> {
-    let amount_sy = amount.to_u64(ctx.accounts.yield_position.interest.staged)?;
+    let amount_staged = amount.to_u64(ctx.accounts.yield_position.interest.staged)?;
+    let amount_sy = ctx.accounts.vault.staged_interest_sy(amount_staged);
 
     ctx.accounts
         .vault
@@ -112,7 +125,7 @@ pub fn handler<'info>(
     // As a consequence, users receive emission rewards only up to the last vault update timestamp.
     // To capture the most recent emission rewards, users should call stage_yield prior to
     // collect_interest to synchronize vault emission indexes with the current state.
-    yield_position_earn(&mut ctx.accounts.vault, &mut ctx.accounts.yield_position);
+    yield_position_earn(&mut ctx.accounts.vault, &mut ctx.accounts.yield_position)?;
 
     do_withdraw_sy(
         amount_sy,
@@ -127,8 +140,8 @@ pub fn handler<'info>(
     let (user_sy, fee_sy) = handle_collect_interest(
         &mut ctx.accounts.vault,
         &mut ctx.accounts.yield_position,
-        amount_sy,
-    );
+        amount_staged,
+    )?;
 
     ctx.accounts
         .transfer_sy(ctx.accounts.token_sy_dst.to_account_info(), user_sy)?;
@@ -176,21 +189,26 @@ pub struct CollectInterestEventV2 {
     pub user_emissions: Vec<YieldTokenTracker>,
 }
 
+/// Collect `amount_staged` units of staged interest, which pay out `Vault::staged_interest_sy` SY
 pub fn handle_collect_interest(
     vault: &mut Vault,
     yield_position: &mut YieldTokenPosition,
-    amount_sy: u64,
-) -> (u64, u64) {
+    amount_staged: u64,
+) -> Result<(u64, u64)> {
+    let amount_sy = vault.staged_interest_sy(amount_staged);
     let (user_sy, fee_sy) = calc_collect_interest(amount_sy, vault.interest_bps_fee);
 
     // update the balances
-    vault.dec_total_sy_in_escrow(amount_sy);
+    vault.dec_total_sy_in_escrow(amount_sy)?;
 
-    yield_position.interest.collect(amount_sy);
+    yield_position.interest.collect(amount_staged)?;
 
-    vault.dec_uncollected_sy(amount_sy);
+    vault.dec_uncollected_sy(amount_sy)?;
 
-    (user_sy, fee_sy)
+    // The position may have staged interest just before collecting
+    vault.set_sy_for_pt();
+
+    Ok((user_sy, fee_sy))
 }
 
 fn calc_collect_interest(amount_sy: u64, interest_bps_fee: u16) -> (u64, u64) {
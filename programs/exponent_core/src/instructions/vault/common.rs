@@ -1,15 +1,19 @@
+use anchor_lang::prelude::*;
 use sy_common::SyState;
 
 use crate::state::{Vault, YieldTokenPosition};
 
 /// Apply earnings for a yield position
-pub fn yield_position_earn(vault: &mut Vault, yield_position: &mut YieldTokenPosition) {
-    yield_position.earn_all_with_tracking(vault);
+pub fn yield_position_earn(
+    vault: &mut Vault,
+    yield_position: &mut YieldTokenPosition,
+) -> Result<()> {
+    yield_position.earn_all_with_tracking(vault)
 }
 
 /// Update the vault's SY rate and the emission tracker indexes
-fn update_indexes(vault: &mut Vault, sy_state: &SyState, now: u32) {
-    vault.update_from_sy_state(sy_state, now);
+fn update_indexes(vault: &mut Vault, sy_state: &SyState, now: u32) -> Result<()> {
+    vault.update_from_sy_state(sy_state, now)
 }
 
 pub fn update_vault_yield(
@@ -17,10 +21,15 @@ pub fn update_vault_yield(
     vault_yield_position: &mut YieldTokenPosition,
     now: u32,
     sy_state: &SyState,
-) {
+) -> Result<()> {
     // First, set the latest indexes from the SY state
-    update_indexes(vault, sy_state, now);
+    update_indexes(vault, sy_state, now)?;
 
     // then stage an earnings with the vault's YT position
-    yield_position_earn(vault, vault_yield_position);
+    yield_position_earn(vault, vault_yield_position)?;
+
+    // Staged interest is taken from active SY, which backs PT if PT is under-backed after a resolved emergency
+    vault.set_sy_for_pt();
+
+    Ok(())
 }
@@ -0,0 +1,322 @@
+use super::{
+    collect_interest::handle_collect_interest,
+    common::{update_vault_yield, yield_position_earn},
+    strip::handle_strip,
+};
+use crate::{
+    error::ExponentCoreError,
+    state::*,
+    util::{now, token_transfer},
+    utils::{do_get_sy_state, do_withdraw_sy},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_interface::*};
+use precise_number::Number;
+use sy_common::SyState;
+
+/// Compound the SY interest of a YieldTokenPosition that has opted in with a CompoundingConfig
+///
+/// Permissionless: any keeper may crank this, and is paid the tip set by the position owner.
+/// The staged SY is collected, stripped into PT & YT, the YT is deposited back into the position,
+/// and the PT is minted to the token account chosen by the owner.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CompoundInterest<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// Keeper's SY token account for receiving the tip
+    #[account(mut)]
+    pub keeper_sy_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The owner's opt-in for compounding
+    #[account(
+        has_one = pt_dst,
+        constraint = compounding_config.yield_position == user_yield_position.key()
+    )]
+    pub compounding_config: Box<Account<'info, CompoundingConfig>>,
+
+    /// Position whose interest is compounded
+    #[account(
+        mut,
+        has_one = vault,
+        realloc = YieldTokenPosition::size_of(vault.emissions.len()),
+        realloc::payer = keeper,
+        realloc::zero = false,
+        // no duplicate YT positions
+        constraint = user_yield_position.key() != yield_position.key()
+    )]
+    pub user_yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = sy_program,
+        has_one = mint_yt,
+        has_one = mint_pt,
+        has_one = escrow_sy,
+        has_one = escrow_yt,
+        has_one = treasury_sy_token_account,
+        has_one = address_lookup_table,
+        has_one = yield_position,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: constrained by vault
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub escrow_sy: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault-owned escrow account for YT, which receives the YT re-deposited into the position
+    #[account(mut)]
+    pub escrow_yt: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Destination for the PT, constrained by the compounding config
+    #[account(mut)]
+    pub pt_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub mint_yt: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub mint_pt: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub treasury_sy_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: constrained by vault
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by vault
+    pub address_lookup_table: UncheckedAccount<'info>,
+
+    /// Vault-owned yield position
+    #[account(mut)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'i> CompoundInterest<'i> {
+    fn transfer_sy(&self, to: AccountInfo<'i>, amount: u64) -> Result<()> {
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.escrow_sy.to_account_info(),
+                to,
+                authority: self.authority.to_account_info(),
+            },
+        );
+
+        token_transfer(ctx.with_signer(&[&self.vault.signer_seeds()]), amount)
+    }
+
+    fn mint_to(&self, mint: AccountInfo<'i>, to: AccountInfo<'i>, amount: u64) -> Result<()> {
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            MintTo {
+                mint,
+                to,
+                authority: self.authority.to_account_info(),
+            },
+        );
+
+        anchor_spl::token_2022::mint_to(ctx.with_signer(&[&self.vault.signer_seeds()]), amount)
+    }
+
+    fn validate(&self) -> Result<()> {
+        validate_compound_interest(&self.vault, now())
+    }
+}
+
+/// Check that the vault allows collecting interest and stripping it back into YT at `now`
+pub fn validate_compound_interest(vault: &Vault, now: u32) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_COLLECT_INTEREST),
+        ExponentCoreError::CollectingInterestDisabled
+    );
+
+    require!(
+        vault.check_status_flags(STATUS_CAN_STRIP),
+        ExponentCoreError::StrippingDisabled
+    );
+
+    require!(vault.is_active(now), ExponentCoreError::VaultIsNotActive);
+
+    Ok(())
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, CompoundInterest<'info>>,
+) -> Result<CompoundInterestEvent> {
+    let current_unix_timestamp = now();
+
+    let sy_state = do_get_sy_state(
+        &ctx.accounts.address_lookup_table.to_account_info(),
+        &ctx.accounts.vault.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let amounts = handle_compound_interest(
+        &mut ctx.accounts.vault,
+        &mut ctx.accounts.yield_position,
+        &mut ctx.accounts.user_yield_position,
+        current_unix_timestamp,
+        &sy_state,
+        ctx.accounts.compounding_config.keeper_tip_bps,
+    )?;
+
+    // Only the SY that leaves the vault is withdrawn from the SY program, the stripped SY stays in
+    do_withdraw_sy(
+        amounts.amount_to_treasury + amounts.amount_to_keeper,
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.vault.cpi_accounts,
+        &ctx.accounts.to_account_infos(),
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+        &[&ctx.accounts.vault.signer_seeds()],
+    )?;
+
+    ctx.accounts.transfer_sy(
+        ctx.accounts.treasury_sy_token_account.to_account_info(),
+        amounts.amount_to_treasury,
+    )?;
+
+    ctx.accounts.transfer_sy(
+        ctx.accounts.keeper_sy_dst.to_account_info(),
+        amounts.amount_to_keeper,
+    )?;
+
+    ctx.accounts.mint_to(
+        ctx.accounts.mint_pt.to_account_info(),
+        ctx.accounts.pt_dst.to_account_info(),
+        amounts.amount_py,
+    )?;
+
+    ctx.accounts.mint_to(
+        ctx.accounts.mint_yt.to_account_info(),
+        ctx.accounts.escrow_yt.to_account_info(),
+        amounts.amount_py,
+    )?;
+
+    let event = CompoundInterestEvent {
+        keeper: ctx.accounts.keeper.key(),
+        vault: ctx.accounts.vault.key(),
+        user_yield_position: ctx.accounts.user_yield_position.key(),
+        pt_dst: ctx.accounts.pt_dst.key(),
+        amount_sy_collected: amounts.amount_sy_collected,
+        amount_to_treasury: amounts.amount_to_treasury,
+        amount_to_keeper: amounts.amount_to_keeper,
+        amount_sy_stripped: amounts.amount_sy_stripped,
+        amount_py: amounts.amount_py,
+        sy_exchange_rate: ctx.accounts.vault.last_seen_sy_exchange_rate,
+        user_yt_balance_after: ctx.accounts.user_yield_position.yt_balance,
+        unix_timestamp: current_unix_timestamp as i64,
+        user_interest: ctx.accounts.user_yield_position.interest,
+        user_emissions: ctx.accounts.user_yield_position.emissions.clone(),
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct CompoundInterestEvent {
+    pub keeper: Pubkey,
+    pub vault: Pubkey,
+    pub user_yield_position: Pubkey,
+    pub pt_dst: Pubkey,
+    pub amount_sy_collected: u64,
+    pub amount_to_treasury: u64,
+    pub amount_to_keeper: u64,
+    pub amount_sy_stripped: u64,
+    pub amount_py: u64,
+    pub sy_exchange_rate: Number,
+    pub user_yt_balance_after: u64,
+    pub unix_timestamp: i64,
+    pub user_interest: YieldTokenTracker,
+    pub user_emissions: Vec<YieldTokenTracker>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompoundInterestAmounts {
+    /// Staged SY taken from the position
+    pub amount_sy_collected: u64,
+    /// Interest fee paid to the treasury
+    pub amount_to_treasury: u64,
+    /// Tip paid to the keeper
+    pub amount_to_keeper: u64,
+    /// SY stripped into PT & YT
+    pub amount_sy_stripped: u64,
+    /// PT minted to the owner, and YT deposited into the position
+    pub amount_py: u64,
+}
+
+/// Collect all staged interest of a user's position, pay the treasury and the keeper, and strip the rest
+/// The YT is moved from the vault's position into the user's position
+pub fn handle_compound_interest(
+    vault: &mut Vault,
+    vault_yield_position: &mut YieldTokenPosition,
+    user_yield_position: &mut YieldTokenPosition,
+    now: u32,
+    sy_state: &SyState,
+    keeper_tip_bps: u16,
+) -> Result<CompoundInterestAmounts> {
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
+
+    require!(
+        !vault.is_in_emergency_mode(),
+        ExponentCoreError::VaultInEmergencyMode
+    );
+
+    yield_position_earn(vault, user_yield_position)?;
+
+    let amount_staged = user_yield_position.interest.staged;
+    let amount_sy_collected = vault.staged_interest_sy(amount_staged);
+
+    vault
+        .claim_limits
+        .verify_claim_limits(amount_sy_collected, now)?;
+
+    let (user_sy, amount_to_treasury) =
+        handle_collect_interest(vault, user_yield_position, amount_staged)?;
+
+    let amount_to_keeper = (user_sy as u128 * keeper_tip_bps as u128 / 10_000) as u64;
+    let amount_sy_stripped = user_sy - amount_to_keeper;
+
+    require!(amount_sy_stripped > 0, ExponentCoreError::NothingToCompound);
+
+    require!(
+        vault.is_min_op_size_strip(amount_sy_stripped),
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    let amount_py = handle_strip(
+        vault,
+        vault_yield_position,
+        now,
+        sy_state,
+        amount_sy_stripped,
+    )?;
+
+    // The minted YT goes to escrow, so it is credited to the user's position instead of the vault's
+    vault_yield_position.dec_yt_balance(amount_py)?;
+    user_yield_position.inc_yt_balance(amount_py)?;
+
+    vault.set_sy_for_pt();
+
+    Ok(CompoundInterestAmounts {
+        amount_sy_collected,
+        amount_to_treasury,
+        amount_to_keeper,
+        amount_sy_stripped,
+        amount_py,
+    })
+}
@@ -82,20 +82,20 @@ impl<'i> DepositYt<'i> {
     }
 
     fn validate(&self) -> Result<()> {
-        let current_unix_timestamp = now();
+        validate_deposit_yt(&self.vault, now())
+    }
+}
 
-        require!(
-            self.vault.check_status_flags(STATUS_CAN_DEPOSIT_YT),
-            ExponentCoreError::DepositingYtDisabled
-        );
+/// Check that the vault allows depositing YT into positions at `now`
+pub fn validate_deposit_yt(vault: &Vault, now: u32) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_DEPOSIT_YT),
+        ExponentCoreError::DepositingYtDisabled
+    );
 
-        require!(
-            self.vault.is_active(current_unix_timestamp),
-            ExponentCoreError::VaultExpired
-        );
+    require!(vault.is_active(now), ExponentCoreError::VaultExpired);
 
-        Ok(())
-    }
+    Ok(())
 }
 
 #[access_control(ctx.accounts.validate())]
@@ -185,7 +185,7 @@ pub fn handle_deposit_yt(
     amount: u64,
 ) -> Result<()> {
     // First, update the vault indexes and sync its own yield position
-    update_vault_yield(vault, vault_yield_position, now, sy_state);
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
 
     // Do not allow deposits if the current sy exchange rate is lower than the all-time high
     // This prevents users from increasing their position amount with a lower last_seen_index, which would break the economics.
@@ -195,13 +195,13 @@ pub fn handle_deposit_yt(
     );
 
     // Then, stage an earnings with the user's YT position
-    yield_position_earn(vault, user_yield_position);
+    yield_position_earn(vault, user_yield_position)?;
 
     // increase the user's YT balance
-    user_yield_position.inc_yt_balance(amount);
+    user_yield_position.inc_yt_balance(amount)?;
 
     // decrease the vault's YT balance
-    vault_yield_position.dec_yt_balance(amount);
+    vault_yield_position.dec_yt_balance(amount)?;
 
     // After increasing uncollected_sy, set_sy_for_pt
     vault.set_sy_for_pt();
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+/// Opt a YieldTokenPosition out of permissionless compounding, and return the config's rent to the owner
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DisableCompounding<'info> {
+    /// Owner of the YieldTokenPosition
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(has_one = owner)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = yield_position,
+    )]
+    pub compounding_config: Box<Account<'info, CompoundingConfig>>,
+}
+
+pub fn handler(ctx: Context<DisableCompounding>) -> Result<DisableCompoundingEvent> {
+    let event = DisableCompoundingEvent {
+        owner: ctx.accounts.owner.key(),
+        yield_position: ctx.accounts.yield_position.key(),
+        compounding_config: ctx.accounts.compounding_config.key(),
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct DisableCompoundingEvent {
+    pub owner: Pubkey,
+    pub yield_position: Pubkey,
+    pub compounding_config: Pubkey,
+    pub unix_timestamp: i64,
+}
@@ -0,0 +1,362 @@
+use super::{
+    collect_interest::handle_collect_interest, common::yield_position_earn,
+    merge::adjust_vault_balances,
+};
+use crate::{
+    error::ExponentCoreError,
+    state::*,
+    util::{now, token_transfer},
+    utils::do_withdraw_sy,
+};
+use amount_value::Amount;
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_2022::Burn, token_interface::*};
+use precise_number::Number;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmergencyWithdrawKind {
+    /// Burn PT for its share of the SY backing PT at the all time high rate, requires pt_src
+    /// While the vault is active, the same amount of YT is burned from yt_src, as with merge
+    Pt,
+    /// Withdraw the staged interest of a yield position, requires user_yield_position
+    Interest,
+}
+
+/// Exit a vault while it is in emergency mode or paused, once the admin has set STATUS_CAN_EMERGENCY_WITHDRAW
+///
+/// The SY rate is not refreshed, so this works even if the SY program cannot report its state.
+/// SY already held in the vault's escrow is paid out first, and only the shortfall is withdrawn from the SY program.
+/// So while the escrow covers the exit, it also works if the SY program cannot withdraw.
+/// PT is paid at `pt_emergency_redemption_rate`, and YT depositors get the interest staged so far.
+/// While the vault is active, PT must be burned with its YT, since the YT would otherwise keep earning interest out of the SY backing PT.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: constrained by vault
+    /// This authority owns the escrow_sy account & the robot account with the SY program
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = sy_program,
+        has_one = escrow_sy,
+        has_one = address_lookup_table,
+        has_one = mint_yt,
+        has_one = mint_pt,
+        has_one = treasury_sy_token_account,
+        has_one = yield_position,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Destination account for SY withdrawn from vault
+    #[account(mut)]
+    pub sy_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault-owned account for SY tokens
+    #[account(mut)]
+    pub escrow_sy: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The owner's PT token account, for withdrawing PT
+    #[account(mut)]
+    pub pt_src: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The owner's YT token account, for withdrawing PT while the vault is active
+    #[account(mut)]
+    pub yt_src: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Mint for PT -- needed for burning
+    #[account(mut)]
+    pub mint_pt: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Mint for YT -- needed for burning
+    #[account(mut)]
+    pub mint_yt: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The owner's position, for withdrawing staged interest
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = vault,
+    )]
+    pub user_yield_position: Option<Box<Account<'info, YieldTokenPosition>>>,
+
+    /// Yield position for the vault robot account
+    #[account(mut)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    #[account(mut)]
+    pub treasury_sy_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: constrained by vault
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by vault
+    pub address_lookup_table: UncheckedAccount<'info>,
+}
+
+impl<'i> EmergencyWithdraw<'i> {
+    fn transfer_sy(&self, to: AccountInfo<'i>, amount: u64) -> Result<()> {
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.escrow_sy.to_account_info(),
+                to,
+                authority: self.authority.to_account_info(),
+            },
+        );
+
+        token_transfer(ctx.with_signer(&[&self.vault.signer_seeds()]), amount)
+    }
+
+    fn burn(&self, from: AccountInfo<'i>, mint: AccountInfo<'i>, amount: u64) -> Result<()> {
+        let ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                from,
+                mint,
+                authority: self.owner.to_account_info(),
+            },
+        );
+
+        anchor_spl::token_2022::burn(ctx, amount)
+    }
+
+    /// Burn the PT, and the YT too if the vault is still active
+    fn burn_py(&self, amount: u64, now: u32) -> Result<()> {
+        let pt_src = self
+            .pt_src
+            .as_ref()
+            .ok_or(ExponentCoreError::EmergencyWithdrawAccountMissing)?;
+        self.burn(
+            pt_src.to_account_info(),
+            self.mint_pt.to_account_info(),
+            amount,
+        )?;
+
+        if self.vault.is_active(now) {
+            let yt_src = self
+                .yt_src
+                .as_ref()
+                .ok_or(ExponentCoreError::EmergencyWithdrawAccountMissing)?;
+            self.burn(
+                yt_src.to_account_info(),
+                self.mint_yt.to_account_info(),
+                amount,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        validate_emergency_withdraw(&self.vault)
+    }
+}
+
+/// Check that the vault allows emergency withdrawals
+pub fn validate_emergency_withdraw(vault: &Vault) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_EMERGENCY_WITHDRAW),
+        ExponentCoreError::EmergencyWithdrawDisabled
+    );
+
+    Ok(())
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, EmergencyWithdraw<'info>>,
+    kind: EmergencyWithdrawKind,
+    amount: Amount,
+) -> Result<EmergencyWithdrawEvent> {
+    let current_unix_timestamp = now();
+
+    let (amount_in, amount_to_user, amount_to_treasury) = match kind {
+        EmergencyWithdrawKind::Pt => {
+            let pt_src = ctx
+                .accounts
+                .pt_src
+                .as_ref()
+                .ok_or(ExponentCoreError::EmergencyWithdrawAccountMissing)?;
+            let amount_pt = amount.to_u64(pt_src.amount)?;
+
+            let amount_sy = handle_emergency_withdraw_pt(
+                &mut ctx.accounts.vault,
+                &mut ctx.accounts.yield_position,
+                current_unix_timestamp,
+                amount_pt,
+            )?;
+
+            (amount_pt, amount_sy, 0)
+        }
+        EmergencyWithdrawKind::Interest => {
+            let yield_position = ctx
+                .accounts
+                .user_yield_position
+                .as_mut()
+                .ok_or(ExponentCoreError::EmergencyWithdrawAccountMissing)?;
+            let amount_staged = amount.to_u64(yield_position.interest.staged)?;
+
+            let (user_sy, fee_sy) = handle_emergency_withdraw_interest(
+                &mut ctx.accounts.vault,
+                yield_position,
+                amount_staged,
+                current_unix_timestamp,
+            )?;
+
+            (amount_staged, user_sy, fee_sy)
+        }
+    };
+
+    fund_escrow(
+        ctx.accounts.escrow_sy.amount,
+        amount_to_user + amount_to_treasury,
+        |shortfall| {
+            do_withdraw_sy(
+                shortfall,
+                &ctx.accounts.address_lookup_table,
+                &ctx.accounts.vault.cpi_accounts,
+                &ctx.accounts.to_account_infos(),
+                ctx.remaining_accounts,
+                ctx.accounts.sy_program.key(),
+                &[&ctx.accounts.vault.signer_seeds()],
+            )?;
+
+            Ok(())
+        },
+    )?;
+
+    ctx.accounts
+        .transfer_sy(ctx.accounts.sy_dst.to_account_info(), amount_to_user)?;
+
+    ctx.accounts.transfer_sy(
+        ctx.accounts.treasury_sy_token_account.to_account_info(),
+        amount_to_treasury,
+    )?;
+
+    if kind == EmergencyWithdrawKind::Pt {
+        ctx.accounts.burn_py(amount_in, current_unix_timestamp)?;
+    }
+
+    let event = EmergencyWithdrawEvent {
+        owner: ctx.accounts.owner.key(),
+        vault: ctx.accounts.vault.key(),
+        kind,
+        amount_in,
+        amount_to_user,
+        amount_to_treasury,
+        pt_emergency_redemption_rate: ctx.accounts.vault.pt_emergency_redemption_rate(),
+        sy_for_pt: ctx.accounts.vault.sy_for_pt,
+        pt_supply: ctx.accounts.vault.pt_supply,
+        uncollected_sy: ctx.accounts.vault.uncollected_sy,
+        total_sy_in_escrow: ctx.accounts.vault.total_sy_in_escrow,
+        unix_timestamp: current_unix_timestamp as i64,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct EmergencyWithdrawEvent {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub kind: EmergencyWithdrawKind,
+    /// PT burned, or staged interest withdrawn
+    pub amount_in: u64,
+    pub amount_to_user: u64,
+    pub amount_to_treasury: u64,
+    pub pt_emergency_redemption_rate: Number,
+    pub sy_for_pt: u64,
+    pub pt_supply: u64,
+    pub uncollected_sy: u64,
+    pub total_sy_in_escrow: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Burn PT for its share of the SY backing PT at the all time high rate, with its YT while the vault is active
+pub fn handle_emergency_withdraw_pt(
+    vault: &mut Vault,
+    vault_yield_position: &mut YieldTokenPosition,
+    now: u32,
+    amount_pt: u64,
+) -> Result<u64> {
+    if vault.is_active(now) {
+        yield_position_earn(vault, vault_yield_position)?;
+        vault_yield_position.dec_yt_balance(amount_pt)?;
+    }
+
+    // Flooring the result to return less SY than the actual amount
+    let amount_sy =
+        (Number::from_natural_u64(amount_pt) * vault.pt_emergency_redemption_rate()).floor_u64();
+
+    adjust_vault_balances(vault, amount_sy, amount_pt)?;
+
+    vault.set_sy_for_pt();
+
+    Ok(amount_sy)
+}
+
+/// Withdraw staged interest, less the interest fee, without staging more
+pub fn handle_emergency_withdraw_interest(
+    vault: &mut Vault,
+    yield_position: &mut YieldTokenPosition,
+    amount_staged: u64,
+    now: u32,
+) -> Result<(u64, u64)> {
+    vault
+        .claim_limits
+        .verify_claim_limits(vault.staged_interest_sy(amount_staged), now)?;
+
+    handle_collect_interest(vault, yield_position, amount_staged)
+}
+
+/// Make sure the escrow holds `amount_sy`, withdrawing only what it is short of from the SY program
+fn fund_escrow(
+    escrow_balance: u64,
+    amount_sy: u64,
+    withdraw_sy: impl FnOnce(u64) -> Result<()>,
+) -> Result<()> {
+    let shortfall = amount_sy.saturating_sub(escrow_balance);
+    if shortfall > 0 {
+        withdraw_sy(shortfall)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn broken_sy_program(_amount: u64) -> Result<()> {
+        Err(ProgramError::InvalidAccountData.into())
+    }
+
+    #[test]
+    fn escrow_covers_the_exit_without_the_sy_program() {
+        assert!(fund_escrow(1_000, 1_000, broken_sy_program).is_ok());
+        assert!(fund_escrow(1_000, 0, broken_sy_program).is_ok());
+    }
+
+    #[test]
+    fn only_the_shortfall_is_withdrawn_from_the_sy_program() {
+        let mut withdrawn = 0;
+        fund_escrow(400, 1_000, |amount| {
+            withdrawn = amount;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(withdrawn, 600);
+
+        assert!(fund_escrow(400, 1_000, broken_sy_program).is_err());
+    }
+}
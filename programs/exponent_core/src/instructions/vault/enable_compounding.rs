@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{seeds::COMPOUNDING_CONFIG_SEED, state::*};
+
+/// Opt a YieldTokenPosition into permissionless compounding of its interest
+///
+/// Any keeper may then crank compound_interest for the position, and is paid `keeper_tip_bps` of the compounded interest
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EnableCompounding<'info> {
+    /// Owner of the YieldTokenPosition
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(has_one = owner)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = CompoundingConfig::SIZE_OF,
+        seeds = [
+            COMPOUNDING_CONFIG_SEED,
+            yield_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub compounding_config: Box<Account<'info, CompoundingConfig>>,
+
+    /// Token account that receives the PT stripped from the interest
+    pub pt_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<EnableCompounding>,
+    keeper_tip_bps: u16,
+) -> Result<EnableCompoundingEvent> {
+    CompoundingConfig::validate_keeper_tip(keeper_tip_bps)?;
+
+    let config = &mut ctx.accounts.compounding_config;
+    config.yield_position = ctx.accounts.yield_position.key();
+    config.pt_dst = ctx.accounts.pt_dst.key();
+    config.keeper_tip_bps = keeper_tip_bps;
+
+    let event = EnableCompoundingEvent {
+        owner: ctx.accounts.owner.key(),
+        yield_position: ctx.accounts.yield_position.key(),
+        compounding_config: ctx.accounts.compounding_config.key(),
+        pt_dst: ctx.accounts.pt_dst.key(),
+        keeper_tip_bps,
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct EnableCompoundingEvent {
+    pub owner: Pubkey,
+    pub yield_position: Pubkey,
+    pub compounding_config: Pubkey,
+    pub pt_dst: Pubkey,
+    pub keeper_tip_bps: u16,
+    pub unix_timestamp: i64,
+}
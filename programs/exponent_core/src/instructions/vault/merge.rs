@@ -130,18 +130,23 @@ impl<'a> Merge<'a> {
     }
 
     fn validate(&self, amount_py: u64) -> Result<()> {
-        require!(
-            self.vault.check_status_flags(STATUS_CAN_MERGE),
-            ExponentCoreError::MergingDisabled
-        );
+        validate_merge(&self.vault, amount_py)
+    }
+}
 
-        require!(
-            self.vault.is_min_op_size_merge(amount_py),
-            ExponentCoreError::OperationAmountTooSmall
-        );
+/// Check that the vault allows merging `amount_py` PT & YT
+pub fn validate_merge(vault: &Vault, amount_py: u64) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_MERGE),
+        ExponentCoreError::MergingDisabled
+    );
 
-        Ok(())
-    }
+    require!(
+        vault.is_min_op_size_merge(amount_py),
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    Ok(())
 }
 
 // `amount` is amount of PT & YT to be merged together for redemption
@@ -176,8 +181,7 @@ pub fn handler<'info>(
         ctx.remaining_accounts,
         ctx.accounts.sy_program.key(),
         &[&ctx.accounts.vault.signer_seeds()],
-    )
-    .expect("failed to withdraw sy from CPI");
+    )?;
 
     // Transfer SY to the owner
     ctx.accounts.transfer_sy(amount_sy)?;
@@ -236,16 +240,20 @@ pub struct MergeEvent {
 }
 
 /// Given an amount of PT, calculate the amount of SY to be redeemed
-fn calc_amount_sy(vault: &Vault, amount_py: u64) -> u64 {
+pub(crate) fn calc_amount_sy(vault: &Vault, amount_py: u64) -> u64 {
     let pt_redemption_rate = vault.pt_redemption_rate();
 
     // Flooring the result to return less SY than the actual amount
     (Number::from_natural_u64(amount_py) * pt_redemption_rate).floor_u64()
 }
 
-fn adjust_vault_balances(vault: &mut Vault, amount_sy: u64, amount_pt: u64) {
-    vault.dec_total_sy_in_escrow(amount_sy);
-    vault.dec_pt_supply(amount_pt);
+pub(crate) fn adjust_vault_balances(
+    vault: &mut Vault,
+    amount_sy: u64,
+    amount_pt: u64,
+) -> Result<()> {
+    vault.dec_total_sy_in_escrow(amount_sy)?;
+    vault.dec_pt_supply(amount_pt)
 }
 
 pub fn handle_merge(
@@ -255,7 +263,7 @@ pub fn handle_merge(
     sy_state: &SyState,
     amount_py: u64,
 ) -> Result<u64> {
-    update_vault_yield(vault, vault_yield_position, now, sy_state);
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
 
     require!(
         !vault.is_in_emergency_mode(),
@@ -264,12 +272,12 @@ pub fn handle_merge(
 
     // If the vault is active, then the YT must be burned
     if vault.is_active(now) {
-        vault_yield_position.dec_yt_balance(amount_py);
+        vault_yield_position.dec_yt_balance(amount_py)?;
     }
 
     let amount_sy = calc_amount_sy(vault, amount_py);
 
-    adjust_vault_balances(vault, amount_sy, amount_py);
+    adjust_vault_balances(vault, amount_sy, amount_py)?;
 
     vault.set_sy_for_pt();
 
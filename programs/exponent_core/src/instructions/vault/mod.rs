@@ -1,17 +1,31 @@
+pub mod close_yield_position;
 pub mod collect_emission;
 pub mod collect_interest;
+pub mod compound_interest;
 pub mod deposit_yt;
+pub mod disable_compounding;
+pub mod emergency_withdraw;
+pub mod enable_compounding;
 pub mod initialize_yield_position;
 pub mod merge;
+pub mod redeem_pt;
+pub mod split_yield_position;
 pub mod stage_yield;
 pub mod strip;
 pub mod withdraw_yt;
 
+pub use close_yield_position::*;
 pub use collect_emission::*;
 pub use collect_interest::*;
+pub use compound_interest::*;
 pub use deposit_yt::*;
+pub use disable_compounding::*;
+pub use emergency_withdraw::*;
+pub use enable_compounding::*;
 pub use initialize_yield_position::*;
 pub use merge::*;
+pub use redeem_pt::*;
+pub use split_yield_position::*;
 pub use stage_yield::*;
 pub use strip::*;
 pub use withdraw_yt::*;
@@ -0,0 +1,219 @@
+use super::merge::{adjust_vault_balances, calc_amount_sy};
+use crate::{
+    error::ExponentCoreError,
+    state::*,
+    util::{now, token_transfer},
+    utils::{do_get_sy_state, do_withdraw_sy},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_2022::Burn, token_interface::*};
+use precise_number::Number;
+use sy_common::SyState;
+
+/// Burn PT after the vault has expired in order to receive SY from the vault's escrow account
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RedeemPt<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: constrained by vault
+    /// This authority owns the escrow_sy account & the robot account with the SY program
+    /// Needs to be mutable to be used in withdraw_sy CPI
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = sy_program,
+        has_one = escrow_sy,
+        has_one = address_lookup_table,
+        has_one = mint_pt,
+        has_one = authority,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Destination account for SY withdrawn from vault
+    #[account(mut)]
+    pub sy_dst: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault-owned account for SY tokens
+    #[account(mut)]
+    pub escrow_sy: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The owner's PT token account
+    #[account(mut)]
+    pub pt_src: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Mint for PT -- needed for burning
+    #[account(mut)]
+    pub mint_pt: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: constrained by vault
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by vault
+    pub address_lookup_table: UncheckedAccount<'info>,
+}
+
+impl<'a> RedeemPt<'a> {
+    fn burn_pt_context(&self) -> CpiContext<'_, '_, '_, 'a, Burn<'a>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                from: self.pt_src.to_account_info(),
+                mint: self.mint_pt.to_account_info(),
+                authority: self.owner.to_account_info(),
+            },
+        )
+    }
+
+    fn transfer_sy_context(&self) -> CpiContext<'_, '_, '_, 'a, Transfer<'a>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.escrow_sy.to_account_info(),
+                to: self.sy_dst.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        )
+    }
+
+    /// Transfer SY to the user
+    fn transfer_sy(&self, amount: u64) -> Result<()> {
+        token_transfer(
+            self.transfer_sy_context()
+                .with_signer(&[&self.vault.signer_seeds()]),
+            amount,
+        )
+    }
+
+    fn validate(&self, amount_pt: u64) -> Result<()> {
+        validate_redeem_pt(&self.vault, now(), amount_pt)
+    }
+}
+
+/// Check that the vault has expired and allows redeeming `amount_pt` PT at `now`
+pub fn validate_redeem_pt(vault: &Vault, now: u32, amount_pt: u64) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_MERGE),
+        ExponentCoreError::MergingDisabled
+    );
+
+    require!(vault.is_expired(now), ExponentCoreError::VaultIsNotExpired);
+
+    require!(
+        vault.is_min_op_size_merge(amount_pt),
+        ExponentCoreError::OperationAmountTooSmall
+    );
+
+    Ok(())
+}
+
+/// `amount_pt` is the amount of PT to redeem for SY at the vault's PT redemption rate
+#[access_control(ctx.accounts.validate(amount_pt))]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RedeemPt<'info>>,
+    amount_pt: u64,
+) -> Result<RedeemPtEvent> {
+    let current_unix_timestamp = now();
+
+    // get the latest exchange rate for SY using the CPI interface & return data
+    let sy_state = do_get_sy_state(
+        &ctx.accounts.address_lookup_table.to_account_info(),
+        &ctx.accounts.vault.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    let amount_sy = handle_redeem_pt(
+        &mut ctx.accounts.vault,
+        current_unix_timestamp,
+        &sy_state,
+        amount_pt,
+    )?;
+
+    do_withdraw_sy(
+        amount_sy,
+        &ctx.accounts.address_lookup_table,
+        &ctx.accounts.vault.cpi_accounts,
+        &ctx.accounts.to_account_infos(),
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+        &[&ctx.accounts.vault.signer_seeds()],
+    )?;
+
+    // Transfer SY to the owner
+    ctx.accounts.transfer_sy(amount_sy)?;
+
+    anchor_spl::token_2022::burn(ctx.accounts.burn_pt_context(), amount_pt)?;
+
+    let event = RedeemPtEvent {
+        owner: ctx.accounts.owner.key(),
+        vault: ctx.accounts.vault.key(),
+        sy_dst: ctx.accounts.sy_dst.key(),
+        pt_src: ctx.accounts.pt_src.key(),
+        mint_pt: ctx.accounts.mint_pt.key(),
+        amount_pt_in: amount_pt,
+        amount_sy_out: amount_sy,
+        sy_exchange_rate: sy_state.exchange_rate,
+        final_sy_exchange_rate: ctx.accounts.vault.final_sy_exchange_rate,
+        pt_redemption_rate: ctx.accounts.vault.pt_redemption_rate(),
+        total_sy_in_escrow: ctx.accounts.vault.total_sy_in_escrow,
+        pt_supply: ctx.accounts.vault.pt_supply,
+        sy_for_pt: ctx.accounts.vault.sy_for_pt,
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct RedeemPtEvent {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub sy_dst: Pubkey,
+    pub pt_src: Pubkey,
+    pub mint_pt: Pubkey,
+    pub amount_pt_in: u64,
+    pub amount_sy_out: u64,
+    pub sy_exchange_rate: Number,
+    pub final_sy_exchange_rate: Number,
+    pub pt_redemption_rate: Number,
+    pub total_sy_in_escrow: u64,
+    pub pt_supply: u64,
+    pub sy_for_pt: u64,
+    pub unix_timestamp: i64,
+}
+
+/// Redeem PT for SY from an expired vault
+///
+/// YT earns nothing after expiry, so staging the vault's YT position can wait for the next merge or stage,
+/// and only the vault's SY rate is updated here.
+pub fn handle_redeem_pt(
+    vault: &mut Vault,
+    now: u32,
+    sy_state: &SyState,
+    amount_pt: u64,
+) -> Result<u64> {
+    require!(vault.is_expired(now), ExponentCoreError::VaultIsNotExpired);
+
+    vault.update_from_sy_state(sy_state, now)?;
+
+    require!(
+        !vault.is_in_emergency_mode(),
+        ExponentCoreError::VaultInEmergencyMode
+    );
+
+    let amount_sy = calc_amount_sy(vault, amount_pt);
+
+    adjust_vault_balances(vault, amount_sy, amount_pt)?;
+
+    vault.set_sy_for_pt();
+
+    Ok(amount_sy)
+}
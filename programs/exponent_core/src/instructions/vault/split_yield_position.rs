@@ -0,0 +1,176 @@
+use super::common::{update_vault_yield, yield_position_earn};
+use crate::{error::ExponentCoreError, state::*, util::now, utils::do_get_sy_state};
+use amount_value::Amount;
+use anchor_lang::prelude::*;
+use sy_common::SyState;
+
+/// Move deposited YT, with the same share of its staged interest and emissions, into another owner's position
+///
+/// Both positions are staged first, so the YT keeps earning in the new position exactly as it did in the old one.
+/// Used by transfer_yield_position to move the whole position, and by split_yield_position to move part of it.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SplitYieldPosition<'info> {
+    /// Owner of the source position
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = sy_program,
+        has_one = address_lookup_table,
+        has_one = yield_position,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// Position the YT is moved out of
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = vault,
+        realloc = YieldTokenPosition::size_of(vault.emissions.len()),
+        realloc::payer = owner,
+        realloc::zero = false,
+        constraint = src_yield_position.key() != yield_position.key()
+    )]
+    pub src_yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    /// Position the YT is moved into, owned by anyone
+    #[account(
+        mut,
+        has_one = vault,
+        realloc = YieldTokenPosition::size_of(vault.emissions.len()),
+        realloc::payer = owner,
+        realloc::zero = false,
+        constraint = dst_yield_position.key() != yield_position.key(),
+        constraint = dst_yield_position.key() != src_yield_position.key()
+    )]
+    pub dst_yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    /// Vault-owned yield position
+    #[account(mut)]
+    pub yield_position: Box<Account<'info, YieldTokenPosition>>,
+
+    /// CHECK: constrained by vault
+    pub sy_program: UncheckedAccount<'info>,
+
+    /// CHECK: constrained by vault
+    pub address_lookup_table: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'i> SplitYieldPosition<'i> {
+    fn validate(&self) -> Result<()> {
+        validate_split_yield_position(&self.vault)
+    }
+}
+
+/// Check that the vault allows moving YT out of one position and into another
+pub fn validate_split_yield_position(vault: &Vault) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_WITHDRAW_YT),
+        ExponentCoreError::WithdrawingYtDisabled
+    );
+
+    require!(
+        vault.check_status_flags(STATUS_CAN_DEPOSIT_YT),
+        ExponentCoreError::DepositingYtDisabled
+    );
+
+    Ok(())
+}
+
+#[access_control(ctx.accounts.validate())]
+pub fn handler(
+    ctx: Context<SplitYieldPosition>,
+    amount: Amount,
+) -> Result<SplitYieldPositionEvent> {
+    let current_unix_timestamp = now();
+    let is_transfer = matches!(amount, Amount::All);
+    let amount = amount.to_u64(ctx.accounts.src_yield_position.yt_balance)?;
+
+    let sy_state = do_get_sy_state(
+        &ctx.accounts.address_lookup_table.to_account_info(),
+        &ctx.accounts.vault.cpi_accounts,
+        ctx.remaining_accounts,
+        ctx.accounts.sy_program.key(),
+    )?;
+
+    handle_split_yield_position(
+        &mut ctx.accounts.vault,
+        &mut ctx.accounts.yield_position,
+        &mut ctx.accounts.src_yield_position,
+        &mut ctx.accounts.dst_yield_position,
+        &sy_state,
+        current_unix_timestamp,
+        amount,
+    )?;
+
+    let event = SplitYieldPositionEvent {
+        owner: ctx.accounts.owner.key(),
+        vault: ctx.accounts.vault.key(),
+        src_yield_position: ctx.accounts.src_yield_position.key(),
+        dst_yield_position: ctx.accounts.dst_yield_position.key(),
+        dst_owner: ctx.accounts.dst_yield_position.owner,
+        amount,
+        is_transfer,
+        src_yt_balance_after: ctx.accounts.src_yield_position.yt_balance,
+        dst_yt_balance_after: ctx.accounts.dst_yield_position.yt_balance,
+        unix_timestamp: current_unix_timestamp as i64,
+        src_interest: ctx.accounts.src_yield_position.interest,
+        src_emissions: ctx.accounts.src_yield_position.emissions.clone(),
+        dst_interest: ctx.accounts.dst_yield_position.interest,
+        dst_emissions: ctx.accounts.dst_yield_position.emissions.clone(),
+    };
+
+    emit_cpi!(event);
+
+    Ok(event)
+}
+
+#[event]
+pub struct SplitYieldPositionEvent {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub src_yield_position: Pubkey,
+    pub dst_yield_position: Pubkey,
+    pub dst_owner: Pubkey,
+    pub amount: u64,
+    /// Set by transfer_yield_position, which moves the whole position, rather than split_yield_position
+    pub is_transfer: bool,
+    pub src_yt_balance_after: u64,
+    pub dst_yt_balance_after: u64,
+    pub unix_timestamp: i64,
+    pub src_interest: YieldTokenTracker,
+    pub src_emissions: Vec<YieldTokenTracker>,
+    pub dst_interest: YieldTokenTracker,
+    pub dst_emissions: Vec<YieldTokenTracker>,
+}
+
+pub fn handle_split_yield_position(
+    vault: &mut Vault,
+    vault_yield_position: &mut YieldTokenPosition,
+    src_yield_position: &mut YieldTokenPosition,
+    dst_yield_position: &mut YieldTokenPosition,
+    sy_state: &SyState,
+    now: u32,
+    amount: u64,
+) -> Result<()> {
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
+
+    // Staging is skipped in emergency mode, so the positions could be at different indexes
+    require!(
+        !vault.is_in_emergency_mode(),
+        ExponentCoreError::VaultInEmergencyMode
+    );
+
+    yield_position_earn(vault, src_yield_position)?;
+    yield_position_earn(vault, dst_yield_position)?;
+
+    src_yield_position.split_into(dst_yield_position, amount)?;
+
+    vault.set_sy_for_pt();
+
+    Ok(())
+}
@@ -113,7 +113,7 @@ pub fn handle_stage_yt_yield(
 ) -> Result<()> {
     // update vault indexees from SY state
     // and stage any yield to the vault's robot account
-    update_vault_yield(vault, vault_yield_position, now, sy_state);
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
 
 
     require!(
@@ -121,7 +121,7 @@ pub fn handle_stage_yt_yield(
         ExponentCoreError::VaultInEmergencyMode
     );
 
-    yield_position_earn(vault, user_yield_position);
+    yield_position_earn(vault, user_yield_position)?;
 
     // Set SY for PT
     vault.set_sy_for_pt();
@@ -125,25 +125,25 @@ impl<'a> Strip<'a> {
     }
 
     fn validate(&self, amount: u64) -> Result<()> {
-        let current_timestamp = now();
+        validate_strip(&self.vault, now(), amount)
+    }
+}
 
-        require!(
-            self.vault.check_status_flags(STATUS_CAN_STRIP),
-            ExponentCoreError::StrippingDisabled
-        );
+/// Check that the vault allows stripping `amount` SY at `now`
+pub fn validate_strip(vault: &Vault, now: u32, amount: u64) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_STRIP),
+        ExponentCoreError::StrippingDisabled
+    );
 
-        require!(
-            self.vault.is_active(current_timestamp),
-            ExponentCoreError::VaultIsNotActive
-        );
+    require!(vault.is_active(now), ExponentCoreError::VaultIsNotActive);
 
-        require!(
-            self.vault.is_min_op_size_strip(amount),
-            ExponentCoreError::OperationAmountTooSmall
-        );
+    require!(
+        vault.is_min_op_size_strip(amount),
+        ExponentCoreError::OperationAmountTooSmall
+    );
 
-        Ok(())
-    }
+    Ok(())
 }
 
 // `amount` is amount of SY to strip
@@ -243,7 +243,7 @@ pub fn handle_strip(
         vault_yield_position,
         current_unix_timestamp,
         &sy_state,
-    );
+    )?;
 
     require!(
         !vault.is_in_emergency_mode(),
@@ -254,12 +254,12 @@ pub fn handle_strip(
     let amount_py = sy_to_py(vault.last_seen_sy_exchange_rate, amount_sy);
 
     // Increase the vault's balance of SY held in escrow
-    vault.inc_total_sy_in_escrow(amount_sy);
+    vault.inc_total_sy_in_escrow(amount_sy)?;
 
     // Increase the total PT supply
-    vault.inc_pt_supply(amount_py);
+    vault.inc_pt_supply(amount_py)?;
 
-    vault_yield_position.inc_yt_balance(amount_py);
+    vault_yield_position.inc_yt_balance(amount_py)?;
 
     vault.set_sy_for_pt();
 
@@ -81,21 +81,22 @@ impl<'i> WithdrawYt<'i> {
 
     /// Only allow withdrawing YT if the vault is active and has the withdraw YT flag enabled
     fn validate(&self) -> Result<()> {
-        let current_unix_timestamp = now();
-        require!(
-            self.vault.check_status_flags(STATUS_CAN_WITHDRAW_YT),
-            ExponentCoreError::WithdrawingYtDisabled
-        );
-
-        require!(
-            self.vault.is_active(current_unix_timestamp),
-            ExponentCoreError::VaultExpired
-        );
-
-        Ok(())
+        validate_withdraw_yt(&self.vault, now())
     }
 }
 
+/// Check that the vault allows withdrawing YT from positions at `now`
+pub fn validate_withdraw_yt(vault: &Vault, now: u32) -> Result<()> {
+    require!(
+        vault.check_status_flags(STATUS_CAN_WITHDRAW_YT),
+        ExponentCoreError::WithdrawingYtDisabled
+    );
+
+    require!(vault.is_active(now), ExponentCoreError::VaultExpired);
+
+    Ok(())
+}
+
 #[access_control(ctx.accounts.validate())]
 pub fn handler(ctx: Context<WithdrawYt>, amount: u64) -> Result<WithdrawYtEventV2> {
     let cur_ts = now();
@@ -113,7 +114,7 @@ pub fn handler(ctx: Context<WithdrawYt>, amount: u64) -> Result<WithdrawYtEventV
         &sy_state,
         cur_ts,
         amount,
-    );
+    )?;
 
     token_transfer(
         ctx.accounts
@@ -185,15 +186,17 @@ pub fn handle_withdraw_yt(
     sy_state: &SyState,
     now: u32,
     amount: u64,
-) {
-    update_vault_yield(vault, vault_yield_position, now, sy_state);
+) -> Result<()> {
+    update_vault_yield(vault, vault_yield_position, now, sy_state)?;
 
     // Note that withdraw YT only can occur if the vault is active
-    yield_position_earn(vault, user_yield_position);
+    yield_position_earn(vault, user_yield_position)?;
 
-    user_yield_position.dec_yt_balance(amount);
-    vault_yield_position.inc_yt_balance(amount);
+    user_yield_position.dec_yt_balance(amount)?;
+    vault_yield_position.inc_yt_balance(amount)?;
 
     // After increasing uncollected_sy, set_sy_for_pt
     vault.set_sy_for_pt();
+
+    Ok(())
 }
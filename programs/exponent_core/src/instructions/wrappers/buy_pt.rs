@@ -1,4 +1,5 @@
 use crate::{
+    error::ExponentCoreError,
     instructions::self_cpi,
     state::*,
     utils::{sy_cpi, sy_to_py_ceil},
@@ -29,21 +30,24 @@ pub fn handler<'info>(
         .financials
         .clone()
         .trade_pt(
+            ctx.accounts.market.curve_kind,
             sy_state.exchange_rate,
             pt_amount as i64,
             current_unix_timestamp as u64,
             false,
-            ctx.accounts.market.fee_treasury_sy_bps,
-        )
+            ctx.accounts
+                .market
+                .trade_fees(current_unix_timestamp as u64),
+        )?
         .net_trader_sy;
 
     // Calculate required base token amount from sy exchange rate and required sy amount
     let required_base_amount = sy_to_py_ceil(sy_state.exchange_rate, sy_amount.abs() as u64);
 
     // Ensure the required base amount is within the user's specified limit
-    assert!(
+    require!(
         required_base_amount <= max_base_amount,
-        "Exceeded max base amount"
+        ExponentCoreError::SlippageExceeded
     );
 
     // Mint SY tokens
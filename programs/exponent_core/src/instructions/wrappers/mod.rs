@@ -10,6 +10,8 @@ pub use sell_pt::*;
 pub mod wrapper_buy_yt;
 pub use wrapper_buy_yt::*;
 
+pub mod wrapper_buy_yt_exact_base_in;
+
 pub mod wrapper_sell_yt;
 pub use wrapper_sell_yt::*;
 
@@ -33,3 +35,6 @@ pub use wrapper_strip::*;
 
 pub mod wrapper_merge;
 pub use wrapper_merge::*;
+
+pub mod wrapper_redeem_pt;
+pub use wrapper_redeem_pt::*;
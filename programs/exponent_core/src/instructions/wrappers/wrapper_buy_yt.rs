@@ -1,4 +1,5 @@
 use crate::{
+    error::ExponentCoreError,
     instructions::self_cpi::{self},
     state::*,
     util::now,
@@ -22,9 +23,8 @@ pub fn handler<'info>(
         &ctx.accounts.market.cpi_accounts,
         ctx.remaining_accounts,
         ctx.accounts.sy_program.key(),
-    )
-    .map(|x| x.exchange_rate)
-    .expect("failed to get sy state");
+    )?
+    .exchange_rate;
 
     // simulate the buy_yt instruction to determine how much asset to spend
     let asset_spend = sim_buy_yt(
@@ -32,12 +32,14 @@ pub fn handler<'info>(
         sy_exchange_rate,
         now() as u64,
         &mut ctx.accounts.market.financials.clone(),
-        ctx.accounts.market.fee_treasury_sy_bps,
-    );
+        ctx.accounts.market.curve_kind,
+        ctx.accounts.market.trade_fees(now() as u64),
+    )?;
 
-    if asset_spend > max_base_amount {
-        panic!("slippage exceeded:asset_spend is greater than max_base_amount");
-    }
+    require!(
+        asset_spend <= max_base_amount,
+        ExponentCoreError::SlippageExceeded
+    );
 
     let mint_sy_accounts = &ctx.remaining_accounts[..mint_sy_accounts_length as usize];
     let mint_sy_return_data = sy_cpi::cpi_mint_sy(
@@ -48,28 +50,7 @@ pub fn handler<'info>(
     )?;
 
     let buy_yt_return_data = self_cpi::do_cpi_buy_yt(
-        self_cpi::BuyYtAccounts {
-            trader: ctx.accounts.buyer.to_account_info(),
-            market: ctx.accounts.market.to_account_info(),
-            token_sy_trader: ctx.accounts.token_sy_trader.to_account_info(),
-            token_yt_trader: ctx.accounts.token_yt_trader.to_account_info(),
-            token_pt_trader: ctx.accounts.token_pt_trader.to_account_info(),
-            token_sy_escrow: ctx.accounts.token_sy_escrow.to_account_info(),
-            token_pt_escrow: ctx.accounts.token_pt_escrow.to_account_info(),
-            address_lookup_table: ctx.accounts.market_address_lookup_table.to_account_info(),
-            token_program: ctx.accounts.token_program.to_account_info(),
-            sy_program: ctx.accounts.sy_program.to_account_info(),
-            vault_authority: ctx.accounts.vault_authority.to_account_info(),
-            vault: ctx.accounts.vault.to_account_info(),
-            token_sy_escrow_vault: ctx.accounts.token_sy_escrow_vault.to_account_info(),
-            mint_yt: ctx.accounts.mint_yt.to_account_info(),
-            mint_pt: ctx.accounts.mint_pt.to_account_info(),
-            address_lookup_table_vault: ctx.accounts.vault_address_lookup_table.to_account_info(),
-            yield_position: ctx.accounts.yield_position.to_account_info(),
-            token_fee_treasury_sy: ctx.accounts.token_fee_treasury_sy.to_account_info(),
-            event_authority: ctx.accounts.event_authority.to_account_info(),
-            program: ctx.accounts.program.to_account_info(),
-        },
+        ctx.accounts.buy_yt_accounts(),
         &ctx.remaining_accounts[mint_sy_accounts_length as usize..],
         mint_sy_return_data.sy_out_amount,
         yt_out,
@@ -79,20 +60,7 @@ pub fn handler<'info>(
     ctx.accounts.vault.reload()?;
 
     self_cpi::do_cpi_deposit_yt(
-        self_cpi::DepositYtAccounts {
-            depositor: ctx.accounts.buyer.to_account_info(),
-            vault: ctx.accounts.vault.to_account_info(),
-            address_lookup_table: ctx.accounts.vault_address_lookup_table.to_account_info(),
-            escrow_yt: ctx.accounts.escrow_yt.to_account_info(),
-            token_program: ctx.accounts.token_program.to_account_info(),
-            sy_program: ctx.accounts.sy_program.to_account_info(),
-            user_yield_position: ctx.accounts.user_yield_position.to_account_info(),
-            yield_position: ctx.accounts.yield_position.to_account_info(),
-            yt_src: ctx.accounts.token_yt_trader.to_account_info(),
-            event_authority: ctx.accounts.event_authority.to_account_info(),
-            program: ctx.accounts.program.to_account_info(),
-            system_program: ctx.accounts.system_program.to_account_info(),
-        },
+        ctx.accounts.deposit_yt_accounts(),
         ctx.remaining_accounts,
         buy_yt_return_data.pt_out,
     )?;
@@ -116,31 +84,37 @@ fn sim_buy_yt(
     sy_exchange_rate: Number,
     now: u64,
     mf: &mut MarketFinancials,
-    fee_treasury_sy_bps: u16,
-) -> u64 {
+    curve_kind: CurveKind,
+    fees: TradeFees,
+) -> Result<u64> {
     // calculate how much SY must be stripped to get the target YT
     let target_sy = py_to_sy_ceil(sy_exchange_rate, yt_out);
 
     // figure out how much SY to borrow
     // by seeing how much SY will be received from selling the PT
     // the amount of PT is equal to the amount of YT desired
-    let pt_sell: i64 = yt_out.try_into().unwrap();
+    let pt_sell: i64 = yt_out
+        .try_into()
+        .map_err(|_| ExponentCoreError::MathOverflow)?;
 
     let sy_to_borrow = mf
         .trade_pt(
+            curve_kind,
             sy_exchange_rate,
-            -1i64 * pt_sell,
+            -pt_sell,
             now.into(),
             true,
-            fee_treasury_sy_bps,
-        )
+            fees,
+        )?
         .net_trader_sy
-        .abs() as u64;
+        .unsigned_abs();
 
-    let sy_spend = target_sy - sy_to_borrow;
+    let sy_spend = target_sy
+        .checked_sub(sy_to_borrow)
+        .ok_or(ExponentCoreError::SyInExceedsStrip)?;
 
     // convert sy_spend to asset
-    (sy_exchange_rate * sy_spend.into()).ceil_u64()
+    Ok((sy_exchange_rate * sy_spend.into()).ceil_u64())
 }
 
 #[event_cpi]
@@ -225,7 +199,49 @@ pub struct WrapperBuyYt<'info> {
     pub token_fee_treasury_sy: UncheckedAccount<'info>,
 }
 
-impl<'info> WrapperBuyYt<'info> {}
+impl<'info> WrapperBuyYt<'info> {
+    pub(crate) fn buy_yt_accounts(&self) -> self_cpi::BuyYtAccounts<'info> {
+        self_cpi::BuyYtAccounts {
+            trader: self.buyer.to_account_info(),
+            market: self.market.to_account_info(),
+            token_sy_trader: self.token_sy_trader.to_account_info(),
+            token_yt_trader: self.token_yt_trader.to_account_info(),
+            token_pt_trader: self.token_pt_trader.to_account_info(),
+            token_sy_escrow: self.token_sy_escrow.to_account_info(),
+            token_pt_escrow: self.token_pt_escrow.to_account_info(),
+            address_lookup_table: self.market_address_lookup_table.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            vault_authority: self.vault_authority.to_account_info(),
+            vault: self.vault.to_account_info(),
+            token_sy_escrow_vault: self.token_sy_escrow_vault.to_account_info(),
+            mint_yt: self.mint_yt.to_account_info(),
+            mint_pt: self.mint_pt.to_account_info(),
+            address_lookup_table_vault: self.vault_address_lookup_table.to_account_info(),
+            yield_position: self.yield_position.to_account_info(),
+            token_fee_treasury_sy: self.token_fee_treasury_sy.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+
+    pub(crate) fn deposit_yt_accounts(&self) -> self_cpi::DepositYtAccounts<'info> {
+        self_cpi::DepositYtAccounts {
+            depositor: self.buyer.to_account_info(),
+            vault: self.vault.to_account_info(),
+            address_lookup_table: self.vault_address_lookup_table.to_account_info(),
+            escrow_yt: self.escrow_yt.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            user_yield_position: self.user_yield_position.to_account_info(),
+            yield_position: self.yield_position.to_account_info(),
+            yt_src: self.token_yt_trader.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        }
+    }
+}
 
 #[event]
 pub struct WrapperBuyYtEvent {
@@ -0,0 +1,57 @@
+use crate::{
+    instructions::self_cpi::{self},
+    utils::sy_cpi,
+};
+use anchor_lang::prelude::*;
+
+use super::wrapper_buy_yt::{WrapperBuyYt, WrapperBuyYtEvent};
+
+/// Buy YT by spending an exact amount of base asset
+///
+/// The base asset is minted into SY, and the largest amount of YT that the minted SY can buy is solved on-chain.
+/// Any SY left over from rounding stays in the trader's SY account.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, WrapperBuyYt<'info>>,
+    // exact amount of base asset the trader wants to spend
+    base_in: u64,
+    // min amount of YT the trader is willing to receive
+    min_yt_out: u64,
+    // The number of accounts to be used for minting SY
+    mint_sy_accounts_length: u8,
+) -> Result<()> {
+    let mint_sy_accounts = &ctx.remaining_accounts[..mint_sy_accounts_length as usize];
+    let mint_sy_return_data = sy_cpi::cpi_mint_sy(
+        ctx.accounts.sy_program.key(),
+        base_in,
+        mint_sy_accounts,
+        mint_sy_accounts.to_vec().to_account_metas(None),
+    )?;
+
+    let buy_yt_return_data = self_cpi::do_cpi_buy_yt_exact_sy_in(
+        ctx.accounts.buy_yt_accounts(),
+        &ctx.remaining_accounts[mint_sy_accounts_length as usize..],
+        mint_sy_return_data.sy_out_amount,
+        min_yt_out,
+    )?;
+
+    ctx.accounts.market.reload()?;
+    ctx.accounts.vault.reload()?;
+
+    self_cpi::do_cpi_deposit_yt(
+        ctx.accounts.deposit_yt_accounts(),
+        ctx.remaining_accounts,
+        buy_yt_return_data.yt_out,
+    )?;
+
+    ctx.accounts.vault.reload()?;
+
+    emit_cpi!(WrapperBuyYtEvent {
+        market: ctx.accounts.market.key(),
+        buyer: ctx.accounts.buyer.key(),
+        base_in_amount: base_in,
+        yt_out_amount: buy_yt_return_data.yt_out,
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
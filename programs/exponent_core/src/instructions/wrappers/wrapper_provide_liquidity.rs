@@ -1,5 +1,6 @@
 use crate::{
     cpi_common::CpiInterfaceContext,
+    error::ExponentCoreError,
     instructions::self_cpi,
     util::deserialize_lookup_table,
     utils::{do_get_sy_state, sy_cpi},
@@ -227,9 +228,9 @@ pub fn handler<'i>(
         mint_sy_rem_accounts.to_vec().to_account_metas(None),
     )?;
 
-    assert!(
+    require!(
         mint_sy_return_data.sy_out_amount > 0,
-        "sy_amount cannot be 0"
+        ExponentCoreError::OperationAmountTooSmall
     );
 
     let sy_state = do_get_sy_state(
@@ -251,7 +252,7 @@ pub fn handler<'i>(
     let sy_remainder = mint_sy_return_data
         .sy_out_amount
         .checked_sub(to_strip)
-        .unwrap();
+        .ok_or(ExponentCoreError::MathOverflow)?;
 
     let vault_alt = deserialize_lookup_table(&ctx.accounts.vault_address_lookup_table);
 
@@ -1,4 +1,5 @@
 use crate::{
+    error::ExponentCoreError,
     instructions::self_cpi,
     utils::{sy_cpi, sy_to_py_ceil},
     MarketTwo,
@@ -141,29 +142,33 @@ pub fn handler<'i>(
     )?;
 
     let mut market_financials_clone = ctx.accounts.market.financials.clone();
+    let curve_kind = ctx.accounts.market.curve_kind;
+    let now = Clock::get()?.unix_timestamp as u64;
     let trade_simulation_result = market_financials_clone.trade_pt(
+        curve_kind,
         sy_state.exchange_rate,
         external_pt_to_buy as i64,
-        Clock::get()?.unix_timestamp as u64,
+        now,
         true,
-        ctx.accounts.market.fee_treasury_sy_bps,
-    );
+        ctx.accounts.market.trade_fees(now),
+    )?;
 
-    let sy_needed_for_pt = trade_simulation_result.net_trader_sy.abs() as u64;
+    let sy_needed_for_pt = trade_simulation_result.net_trader_sy.unsigned_abs();
 
     let lp_supply = ctx.accounts.mint_lp.supply;
 
     let sim_result = market_financials_clone.add_liquidity(
+        curve_kind,
         u64::MAX, // SY intent of max to see how much we need
         external_pt_to_buy,
         lp_supply,
-    );
+    )?;
 
     let sy_needed_for_liquidity = sim_result.sy_in;
 
     let total_sy_needed = sy_needed_for_pt
         .checked_add(sy_needed_for_liquidity)
-        .unwrap();
+        .ok_or(ExponentCoreError::MathOverflow)?;
 
     let base_amount_needed = sy_to_py_ceil(sy_state.exchange_rate, total_sy_needed);
 
@@ -182,7 +187,9 @@ pub fn handler<'i>(
         ctx.accounts.to_trade_pt_accounts(),
         interface_cpi_rem_accounts,
         external_pt_to_buy as i64,
-        (external_sy_constraint as i64).checked_mul(-1).unwrap(),
+        (external_sy_constraint as i64)
+            .checked_mul(-1)
+            .ok_or(ExponentCoreError::MathOverflow)?,
     )?;
 
     ctx.accounts.market.reload()?;
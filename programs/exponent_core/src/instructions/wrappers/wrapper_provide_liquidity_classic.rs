@@ -1,4 +1,4 @@
-use crate::{instructions::self_cpi, utils::sy_cpi, MarketTwo};
+use crate::{error::ExponentCoreError, instructions::self_cpi, utils::sy_cpi, MarketTwo};
 use anchor_lang::prelude::*;
 use anchor_spl::{token::Token, token_interface::*};
 
@@ -115,9 +115,9 @@ pub fn handler<'i>(
         mint_sy_rem_accounts.to_vec().to_account_metas(None),
     )?;
 
-    assert!(
+    require!(
         mint_sy_return_data.sy_out_amount > 0,
-        "sy_amount cannot be 0"
+        ExponentCoreError::OperationAmountTooSmall
     );
 
     // CPI to deposit liquidity
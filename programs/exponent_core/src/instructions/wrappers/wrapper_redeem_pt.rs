@@ -0,0 +1,115 @@
+use crate::{instructions::self_cpi, utils::sy_cpi, Vault};
+use anchor_lang::prelude::*;
+use anchor_spl::{token::Token, token_interface::*};
+use precise_number::Number;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WrapperRedeemPt<'info> {
+    #[account(mut)]
+    pub redeemer: Signer<'info>,
+
+    /// Token account for SY owned by the redeemer
+    #[account(mut)]
+    pub token_sy_redeemer: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub escrow_sy: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub token_pt_redeemer: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Checked by redeem_pt
+    #[account(mut)]
+    pub mint_pt: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked by redeem_pt
+    pub authority: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by redeem_pt
+    pub vault_address_lookup_table: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked by redeem_pt
+    pub sy_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'i> WrapperRedeemPt<'i> {
+    fn to_redeem_pt_accounts(&self) -> self_cpi::RedeemPtAccounts<'i> {
+        self_cpi::RedeemPtAccounts {
+            owner: self.redeemer.to_account_info(),
+            authority: self.authority.to_account_info(),
+            vault: self.vault.to_account_info(),
+            sy_dst: self.token_sy_redeemer.to_account_info(),
+            escrow_sy: self.escrow_sy.to_account_info(),
+            pt_src: self.token_pt_redeemer.to_account_info(),
+            mint_pt: self.mint_pt.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            sy_program: self.sy_program.to_account_info(),
+            address_lookup_table: self.vault_address_lookup_table.to_account_info(),
+            event_authority: self.event_authority.to_account_info(),
+            program: self.program.to_account_info(),
+        }
+    }
+}
+
+/// Redeem PT for SY after the vault has expired, then redeem the SY for base
+pub fn handler<'i>(
+    ctx: Context<'_, '_, '_, 'i, WrapperRedeemPt<'i>>,
+    amount_pt: u64,
+    redeem_sy_accounts_until: u8,
+) -> Result<()> {
+    let redeem_sy_rem_accounts = &ctx.remaining_accounts[..redeem_sy_accounts_until as usize];
+    let interface_cpi_rem_accounts = &ctx.remaining_accounts[redeem_sy_accounts_until as usize..];
+
+    let redeem_pt_return_data = self_cpi::do_cpi_redeem_pt(
+        ctx.accounts.to_redeem_pt_accounts(),
+        interface_cpi_rem_accounts,
+        amount_pt,
+    )?;
+
+    ctx.accounts.vault.reload()?;
+
+    let redeem_sy_return_data = sy_cpi::cpi_redeem_sy(
+        ctx.accounts.vault.sy_program,
+        redeem_pt_return_data.amount_sy_out,
+        redeem_sy_rem_accounts,
+        redeem_sy_rem_accounts.to_vec().to_account_metas(None),
+    )?;
+
+    let event = WrapperRedeemPtEvent {
+        user_address: *ctx.accounts.redeemer.key,
+        vault_address: ctx.accounts.vault.key(),
+        amount_pt_in: amount_pt,
+        amount_sy_redeemed: redeem_pt_return_data.amount_sy_out,
+        amount_base_out: redeem_sy_return_data.base_out_amount,
+        pt_redemption_rate: redeem_pt_return_data.pt_redemption_rate,
+        sy_exchange_rate: redeem_pt_return_data.sy_exchange_rate,
+        unix_timestamp: redeem_pt_return_data.unix_timestamp,
+    };
+
+    emit_cpi!(event);
+
+    Ok(())
+}
+
+#[event]
+pub struct WrapperRedeemPtEvent {
+    pub user_address: Pubkey,
+    pub vault_address: Pubkey,
+    pub amount_pt_in: u64,
+    pub amount_sy_redeemed: u64,
+    pub amount_base_out: u64,
+    pub pt_redemption_rate: Number,
+    pub sy_exchange_rate: Number,
+    pub unix_timestamp: i64,
+}
@@ -1,4 +1,4 @@
-use crate::{instructions::self_cpi, utils::sy_cpi, Vault};
+use crate::{error::ExponentCoreError, instructions::self_cpi, utils::sy_cpi, Vault};
 use anchor_lang::prelude::*;
 use anchor_spl::{token::Token, token_interface::*};
 
@@ -117,9 +117,9 @@ pub fn handler<'i>(
         mint_sy_rem_accounts.to_vec().to_account_metas(None),
     )?;
 
-    assert!(
+    require!(
         mint_sy_return_data.sy_out_amount > 0,
-        "sy_amount cannot be 0"
+        ExponentCoreError::OperationAmountTooSmall
     );
 
     let strip_return_data = self_cpi::do_cpi_strip(
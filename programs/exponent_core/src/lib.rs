@@ -12,6 +12,26 @@ use precise_number::Number;
 use solana_security_txt::security_txt;
 pub use state::*;
 
+/// The state transitions behind the instruction handlers, and the checks they run first, without any accounts
+/// Used to replay instructions off-chain
+pub mod handlers {
+    pub use crate::instructions::{
+        buy_yt_exact_sy_in::handle_buy_yt_exact_sy_in,
+        handle_buy_yt_borrow, handle_buy_yt_repay, handle_collect_interest,
+        handle_compound_interest, handle_deposit_yt, handle_emergency_withdraw_interest,
+        handle_emergency_withdraw_pt, handle_merge, handle_pt_to_sell, handle_redeem_pt,
+        handle_sell_yt_borrow, handle_sell_yt_leftover, handle_split_yield_position,
+        handle_stage_yt_yield, handle_strip, handle_trade_pt, handle_withdraw_yt,
+        trade_pt_exact_sy::{handle_trade_pt_exact_sy, validate_trade_pt_exact_sy},
+        trade_pt_to_rate::handle_trade_pt_to_rate,
+        validate_buy_yt, validate_collect_interest, validate_compound_interest,
+        validate_deposit_liquidity, validate_deposit_yt, validate_emergency_withdraw,
+        validate_merge, validate_redeem_pt, validate_sell_yt, validate_split_yield_position,
+        validate_strip, validate_trade_direction, validate_withdraw_liquidity,
+        validate_withdraw_yt, CompoundInterestAmounts,
+    };
+}
+
 #[cfg(all(not(feature = "idl-build"), not(test)))]
 mod allocator;
 
@@ -67,6 +87,31 @@ pub mod exponent_core {
         initialize_yield_position::handler(ctx)
     }
 
+    /// Close an empty yield position and return its rent to the owner
+    #[instruction(discriminator = [58])]
+    pub fn close_yield_position(
+        ctx: Context<CloseYieldPosition>,
+    ) -> Result<CloseYieldPositionEvent> {
+        close_yield_position::handler(ctx)
+    }
+
+    /// Move all deposited YT and staged earnings of a yield position into another owner's position
+    #[instruction(discriminator = [60])]
+    pub fn transfer_yield_position(
+        ctx: Context<SplitYieldPosition>,
+    ) -> Result<SplitYieldPositionEvent> {
+        split_yield_position::handler(ctx, Amount::All)
+    }
+
+    /// Move part of the deposited YT, and the same share of staged earnings, into another owner's position
+    #[instruction(discriminator = [61])]
+    pub fn split_yield_position(
+        ctx: Context<SplitYieldPosition>,
+        amount: u64,
+    ) -> Result<SplitYieldPositionEvent> {
+        split_yield_position::handler(ctx, Amount::Some(amount))
+    }
+
     /// Strip SY into PT + YT
     #[instruction(discriminator = [4])]
     pub fn strip<'info>(
@@ -86,6 +131,25 @@ pub mod exponent_core {
         merge::handler(ctx, amount)
     }
 
+    /// Redeem PT alone for SY once the vault has expired
+    #[instruction(discriminator = [49])]
+    pub fn redeem_pt<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemPt<'info>>,
+        amount: u64,
+    ) -> Result<RedeemPtEvent> {
+        redeem_pt::handler(ctx, amount)
+    }
+
+    /// Exit a vault in emergency mode or paused, by burning PT or withdrawing staged interest, without the SY rate
+    #[instruction(discriminator = [65])]
+    pub fn emergency_withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, EmergencyWithdraw<'info>>,
+        kind: EmergencyWithdrawKind,
+        amount: Amount,
+    ) -> Result<EmergencyWithdrawEvent> {
+        emergency_withdraw::handler(ctx, kind, amount)
+    }
+
     #[instruction(discriminator = [6])]
     pub fn collect_interest<'info>(
         ctx: Context<'_, '_, '_, 'info, CollectInterest<'info>>,
@@ -110,6 +174,47 @@ pub mod exponent_core {
         stage_yield::handler(ctx)
     }
 
+    /// Opt a YT position into permissionless compounding of its interest
+    #[instruction(discriminator = [53])]
+    pub fn enable_compounding(
+        ctx: Context<EnableCompounding>,
+        keeper_tip_bps: u16,
+    ) -> Result<EnableCompoundingEvent> {
+        enable_compounding::handler(ctx, keeper_tip_bps)
+    }
+
+    #[instruction(discriminator = [54])]
+    pub fn disable_compounding(
+        ctx: Context<DisableCompounding>,
+    ) -> Result<DisableCompoundingEvent> {
+        disable_compounding::handler(ctx)
+    }
+
+    /// Collect a position's interest, strip it, and deposit the YT back into the position
+    #[instruction(discriminator = [55])]
+    pub fn compound_interest<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompoundInterest<'info>>,
+    ) -> Result<CompoundInterestEvent> {
+        compound_interest::handler(ctx)
+    }
+
+    /// Appoint an operator that can claim the earnings of a YT or LP position
+    #[instruction(discriminator = [56])]
+    pub fn set_position_delegate(
+        ctx: Context<SetPositionDelegate>,
+        operator: Pubkey,
+        beneficiary: Option<Pubkey>,
+    ) -> Result<SetPositionDelegateEvent> {
+        set_position_delegate::handler(ctx, operator, beneficiary)
+    }
+
+    #[instruction(discriminator = [57])]
+    pub fn remove_position_delegate(
+        ctx: Context<RemovePositionDelegate>,
+    ) -> Result<RemovePositionDelegateEvent> {
+        remove_position_delegate::handler(ctx)
+    }
+
     #[instruction(discriminator = [10])]
     pub fn init_market_two<'info>(
         ctx: Context<'_, '_, '_, 'info, MarketTwoInit<'info>>,
@@ -147,6 +252,16 @@ pub mod exponent_core {
         instructions::market_two::deposit_liquidity::handler(ctx, pt_intent, sy_intent, min_lp_out)
     }
 
+    /// Provide liquidity with PT only, selling part of it for SY, and optionally stake the LP tokens
+    #[instruction(discriminator = [48])]
+    pub fn deposit_liquidity_single_pt<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositLiquiditySinglePt<'info>>,
+        amount_pt: u64,
+        min_lp_out: u64,
+    ) -> Result<DepositLiquiditySinglePtEvent> {
+        instructions::market_two::deposit_liquidity_single_pt::handler(ctx, amount_pt, min_lp_out)
+    }
+
     #[instruction(discriminator = [12])]
     pub fn market_two_withdraw_liquidity<'info>(
         ctx: Context<'_, '_, '_, 'info, WithdrawLiquidity<'info>>,
@@ -163,6 +278,27 @@ pub mod exponent_core {
         instructions::market_two::init_lp_position::handler(ctx)
     }
 
+    /// Close an empty LP position and return its rent to the owner
+    #[instruction(discriminator = [59])]
+    pub fn close_lp_position(ctx: Context<CloseLpPosition>) -> Result<CloseLpPositionEvent> {
+        instructions::market_two::close_lp_position::handler(ctx)
+    }
+
+    /// Move all staked LP and staged earnings of an LP position into another owner's position
+    #[instruction(discriminator = [62])]
+    pub fn transfer_lp_position(ctx: Context<SplitLpPosition>) -> Result<SplitLpPositionEvent> {
+        instructions::market_two::split_lp_position::handler(ctx, Amount::All)
+    }
+
+    /// Move part of the staked LP, and the same share of staged earnings, into another owner's position
+    #[instruction(discriminator = [63])]
+    pub fn split_lp_position(
+        ctx: Context<SplitLpPosition>,
+        amount: u64,
+    ) -> Result<SplitLpPositionEvent> {
+        instructions::market_two::split_lp_position::handler(ctx, Amount::Some(amount))
+    }
+
     /// Deposit LP tokens into a personal LP position account
     #[instruction(discriminator = [14])]
     pub fn market_deposit_lp(ctx: Context<DepositLp>, amount: u64) -> Result<DepositLpEventV2> {
@@ -193,6 +329,55 @@ pub mod exponent_core {
         instructions::market_two::trade_pt::handler(ctx, net_trader_pt, sy_constraint)
     }
 
+    /// Buy PT with an exact amount of SY
+    #[instruction(discriminator = [42])]
+    pub fn trade_pt_exact_sy<'info>(
+        ctx: Context<'_, '_, '_, 'info, TradePt<'info>>,
+        sy_in: u64,
+        min_pt_out: u64,
+    ) -> Result<TradePtEvent> {
+        trade_pt_exact_sy::handler(ctx, sy_in, min_pt_out)
+    }
+
+    /// Trade PT to move the market's implied rate to a target
+    #[instruction(discriminator = [47])]
+    pub fn trade_pt_to_rate<'info>(
+        ctx: Context<'_, '_, '_, 'info, TradePt<'info>>,
+        target_ln_implied_rate: f64,
+        price_limit: Number,
+    ) -> Result<TradePtEvent> {
+        trade_pt_to_rate::handler(ctx, target_ln_implied_rate, price_limit)
+    }
+
+    /// Redeem matured PT and buy PT of a later maturity with the SY
+    #[instruction(discriminator = [51])]
+    pub fn roll_pt<'info>(
+        ctx: Context<'_, '_, '_, 'info, RollPt<'info>>,
+        amount_pt: u64,
+        min_pt_out: u64,
+        min_ln_implied_rate: f64,
+    ) -> Result<RollPtEvent> {
+        roll_pt::handler(ctx, amount_pt, min_pt_out, min_ln_implied_rate)
+    }
+
+    /// Withdraw expired LP, redeem its PT and deposit the SY into a market of a later maturity
+    #[instruction(discriminator = [52])]
+    pub fn roll_lp<'info>(
+        ctx: Context<'_, '_, '_, 'info, RollLp<'info>>,
+        lp_in: u64,
+        min_lp_out: u64,
+        min_ln_implied_rate: f64,
+        max_ln_implied_rate: f64,
+    ) -> Result<RollLpEvent> {
+        roll_lp::handler(
+            ctx,
+            lp_in,
+            min_lp_out,
+            min_ln_implied_rate,
+            max_ln_implied_rate,
+        )
+    }
+
     /// Sell YT for SY
     #[instruction(discriminator = [1])]
     pub fn sell_yt<'i>(
@@ -213,6 +398,31 @@ pub mod exponent_core {
         buy_yt::handler(ctx, sy_in, yt_out)
     }
 
+    /// Buy YT with an exact amount of SY
+    #[instruction(discriminator = [43])]
+    pub fn buy_yt_exact_sy_in<'i>(
+        ctx: Context<'_, '_, '_, 'i, BuyYt<'i>>,
+        sy_in: u64,
+        min_yt_out: u64,
+    ) -> Result<BuyYtEvent> {
+        buy_yt_exact_sy_in::handler(ctx, sy_in, min_yt_out)
+    }
+
+    /// Read the time-weighted implied rate and PT price over a window of seconds
+    #[instruction(discriminator = [45])]
+    pub fn market_twap(ctx: Context<MarketTwap>, window: u32) -> Result<MarketTwapResult> {
+        market_twap::handler(ctx, window)
+    }
+
+    /// Read the market's current prices, and optionally quote a trade_pt without applying it
+    #[instruction(discriminator = [46])]
+    pub fn market_quote<'info>(
+        ctx: Context<'_, '_, '_, 'info, MarketQuote<'info>>,
+        net_trader_pt: Option<i64>,
+    ) -> Result<MarketQuoteResult> {
+        market_quote::handler(ctx, net_trader_pt)
+    }
+
     #[instruction(discriminator = [18])]
     pub fn add_emission<'info>(
         ctx: Context<'_, '_, '_, 'info, AddEmission<'info>>,
@@ -292,6 +502,18 @@ pub mod exponent_core {
         modify_vault_setting::handler(ctx, action)
     }
 
+    /// Accept a permanent fall in the SY rate, and take the vault out of emergency mode with exits paused until the loss is settled
+    #[instruction(discriminator = [64])]
+    pub fn resolve_emergency(ctx: Context<ResolveEmergency>) -> Result<ResolveEmergencyEvent> {
+        resolve_emergency::handler(ctx)
+    }
+
+    /// Share the loss accepted by resolve_emergency pro rata between PT and YT, once YT has staged its interest
+    #[instruction(discriminator = [67])]
+    pub fn settle_emergency(ctx: Context<SettleEmergency>) -> Result<SettleEmergencyEvent> {
+        settle_emergency::handler(ctx)
+    }
+
     #[instruction(discriminator = [27])]
     pub fn modify_market_setting<'i>(
         ctx: Context<'_, '_, '_, 'i, ModifyMarketSetting>,
@@ -361,6 +583,19 @@ pub mod exponent_core {
         wrapper_buy_yt::handler(ctx, yt_out, max_base_amount, mint_sy_accounts_length)
     }
 
+    #[instruction(discriminator = [44])]
+    pub fn wrapper_buy_yt_exact_base_in<'info>(
+        ctx: Context<'_, '_, '_, 'info, WrapperBuyYt<'info>>,
+        // exact amount of base asset the trader wants to spend
+        base_in: u64,
+        // min amount of YT the trader is willing to receive
+        min_yt_out: u64,
+        // The number of accounts to be used for minting SY
+        mint_sy_accounts_length: u8,
+    ) -> Result<()> {
+        wrapper_buy_yt_exact_base_in::handler(ctx, base_in, min_yt_out, mint_sy_accounts_length)
+    }
+
     #[instruction(discriminator = [32])]
     pub fn wrapper_sell_yt<'info>(
         ctx: Context<'_, '_, '_, 'info, WrapperSellYt<'info>>,
@@ -457,6 +692,16 @@ pub mod exponent_core {
         wrapper_merge::handler(ctx, amount_py, redeem_sy_accounts_until)
     }
 
+    /// Redeem PT for base once the vault has expired
+    #[instruction(discriminator = [50])]
+    pub fn wrapper_redeem_pt<'info>(
+        ctx: Context<'_, '_, '_, 'info, WrapperRedeemPt<'info>>,
+        amount_pt: u64,
+        redeem_sy_accounts_until: u8,
+    ) -> Result<()> {
+        wrapper_redeem_pt::handler(ctx, amount_pt, redeem_sy_accounts_until)
+    }
+
     #[instruction(discriminator = [40])]
     pub fn realloc_market<'info>(
         ctx: Context<'_, '_, '_, 'info, ReallocMarket<'info>>,
@@ -465,6 +710,16 @@ pub mod exponent_core {
         realloc_market::handler(ctx, additional_bytes)
     }
 
+    #[instruction(discriminator = [66])]
+    pub fn migrate_market(ctx: Context<MigrateMarket>) -> Result<()> {
+        migrate_market::handler(ctx)
+    }
+
+    #[instruction(discriminator = [68])]
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        migrate_vault::handler(ctx)
+    }
+
     #[instruction(discriminator = [41])]
     pub fn add_lp_tokens_metadata(
         ctx: Context<AddLpTokensMetadata>,
@@ -5,3 +5,5 @@ pub const AUTHORITY_SEED: &[u8] = b"authority";
 pub const MINT_PT_SEED: &[u8] = b"mint_pt";
 pub const MINT_YT_SEED: &[u8] = b"mint_yt";
 pub const ESCROW_YT_SEED: &[u8] = b"escrow_yt";
+pub const COMPOUNDING_CONFIG_SEED: &[u8] = b"compounding_config";
+pub const POSITION_DELEGATE_SEED: &[u8] = b"position_delegate";
@@ -0,0 +1,42 @@
+use crate::error::ExponentCoreError;
+use anchor_lang::prelude::*;
+
+/// Highest tip a position owner may offer keepers, in basis points of the compounded interest
+pub const MAX_KEEPER_TIP_BPS: u16 = 100;
+
+/// Opt-in for permissionless compounding of a YieldTokenPosition's interest
+///
+/// While this account exists, any keeper may collect the position's staged SY, strip it and re-deposit the YT
+#[account]
+#[derive(Debug, Default)]
+pub struct CompoundingConfig {
+    /// Position whose interest is compounded
+    pub yield_position: Pubkey,
+
+    /// Token account that receives the PT stripped from the interest
+    pub pt_dst: Pubkey,
+
+    /// Share of the collected interest paid to the keeper
+    pub keeper_tip_bps: u16,
+}
+
+impl CompoundingConfig {
+    pub const SIZE_OF: usize =
+        // discriminator
+        8 +
+        // yield_position
+        32 +
+        // pt_dst
+        32 +
+        // keeper_tip_bps
+        2;
+
+    pub fn validate_keeper_tip(keeper_tip_bps: u16) -> Result<()> {
+        require!(
+            keeper_tip_bps <= MAX_KEEPER_TIP_BPS,
+            ExponentCoreError::KeeperTipTooHigh
+        );
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use super::{EmissionIndexes, MarketTwo, PersonalYieldTracker, PersonalYieldTrackers};
+use crate::error::ExponentCoreError;
 use anchor_lang::prelude::*;
 
 #[account]
@@ -78,25 +79,48 @@ impl LpPosition {
         &mut self,
         emission_indexes: &EmissionIndexes,
         farm_indexes: &EmissionIndexes,
-    ) {
+    ) -> Result<()> {
         self.emissions
-            .ensure_trackers_and_earn_all(emission_indexes, self.lp_balance);
+            .ensure_trackers_and_earn_all(emission_indexes, self.lp_balance)?;
 
         self.farms
-            .ensure_trackers_and_earn_all(farm_indexes, self.lp_balance);
+            .ensure_trackers_and_earn_all(farm_indexes, self.lp_balance)
     }
 
-    pub fn rm_lp(&mut self, amount: u64) {
+    /// A position is empty once it holds no LP and no staged emissions or farm rewards
+    pub fn is_empty(&self) -> bool {
+        self.lp_balance == 0
+            && self.emissions.trackers.iter().all(|t| t.staged == 0)
+            && self.farms.trackers.iter().all(|t| t.staged == 0)
+    }
+
+    /// Move `amount` LP, and the same share of staged emissions and farm rewards, into `dst`
+    /// Both positions must be staged to the market's indexes first, so that they earn alike from here on
+    pub fn split_into(&mut self, dst: &mut LpPosition, amount: u64) -> Result<()> {
+        let balance = self.lp_balance;
+        self.rm_lp(amount)?;
+        dst.add_lp(amount)?;
+
+        self.emissions
+            .split_into(&mut dst.emissions, amount, balance)?;
+        self.farms.split_into(&mut dst.farms, amount, balance)?;
+
+        Ok(())
+    }
+
+    pub fn rm_lp(&mut self, amount: u64) -> Result<()> {
         self.lp_balance = self
             .lp_balance
             .checked_sub(amount)
-            .expect("insufficent LP token balance");
+            .ok_or(ExponentCoreError::InsufficientLpBalance)?;
+        Ok(())
     }
 
-    pub fn add_lp(&mut self, amount: u64) {
+    pub fn add_lp(&mut self, amount: u64) -> Result<()> {
         self.lp_balance = self
             .lp_balance
             .checked_add(amount)
-            .expect("lp balance overflow");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        Ok(())
     }
 }
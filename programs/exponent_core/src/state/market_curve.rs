@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use exponent_time_curve::{
+    curve::{Curve, LogitCurve},
+    math::{AddLiquidityResult, CurveError, RemoveLiquidityResult, TradeResult},
+    num::Num,
+};
+
+/// Tag for the invariant a market prices trades on
+/// Markets created before the tag existed read it as zero, which is the logit curve
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CurveKind {
+    #[default]
+    Logit,
+}
+
+impl CurveKind {
+    /// Build the market's curve at `sec_remaining`, anchored so that the pool is at `last_ln_implied_rate`
+    pub fn curve<N: Num>(
+        self,
+        rate_scalar_root: N,
+        pt: u64,
+        asset: u64,
+        last_ln_implied_rate: N,
+        sec_remaining: u64,
+    ) -> MarketCurve<N> {
+        match self {
+            CurveKind::Logit => MarketCurve::Logit(LogitCurve::new(
+                rate_scalar_root,
+                pt,
+                asset,
+                last_ln_implied_rate,
+                sec_remaining,
+            )),
+        }
+    }
+
+    /// Calculate the amount of LP tokens received, and SY & PT put in
+    pub fn add_liquidity<N: Num>(
+        self,
+        intent_sy: u64,
+        intent_pt: u64,
+        market_total_lp: u64,
+        market_total_sy: u64,
+        market_total_pt: u64,
+    ) -> AddLiquidityResult {
+        match self {
+            CurveKind::Logit => LogitCurve::<N>::add_liquidity(
+                intent_sy,
+                intent_pt,
+                market_total_lp,
+                market_total_sy,
+                market_total_pt,
+            ),
+        }
+    }
+
+    /// Calculate the amount of SY and PT received for LP tokens in
+    pub fn rm_liquidity<N: Num>(
+        self,
+        lp_in: u64,
+        market_total_lp: u64,
+        market_total_sy: u64,
+        market_total_pt: u64,
+    ) -> RemoveLiquidityResult {
+        match self {
+            CurveKind::Logit => LogitCurve::<N>::rm_liquidity(
+                lp_in,
+                market_total_lp,
+                market_total_sy,
+                market_total_pt,
+            ),
+        }
+    }
+}
+
+/// A market's curve, dispatched on its CurveKind
+#[derive(Clone, Copy, Debug)]
+pub enum MarketCurve<N: Num> {
+    Logit(LogitCurve<N>),
+}
+
+impl<N: Num> Curve<N> for MarketCurve<N> {
+    fn sec_remaining(&self) -> u64 {
+        match self {
+            MarketCurve::Logit(c) => c.sec_remaining(),
+        }
+    }
+
+    fn exchange_rate(&self, pt: u64, asset: u64) -> N {
+        match self {
+            MarketCurve::Logit(c) => c.exchange_rate(pt, asset),
+        }
+    }
+
+    fn trade(
+        &self,
+        market_pt: u64,
+        market_asset: u64,
+        fee_rate: N,
+        net_trader_pt: N,
+        is_current_flash_swap: bool,
+    ) -> std::result::Result<TradeResult<N>, CurveError> {
+        match self {
+            MarketCurve::Logit(c) => c.trade(
+                market_pt,
+                market_asset,
+                fee_rate,
+                net_trader_pt,
+                is_current_flash_swap,
+            ),
+        }
+    }
+
+    fn ln_implied_rate(&self, pt: u64, asset: u64) -> N {
+        match self {
+            MarketCurve::Logit(c) => c.ln_implied_rate(pt, asset),
+        }
+    }
+
+    fn pt_for_exchange_rate(&self, asset: u64, exchange_rate: N) -> Option<N> {
+        match self {
+            MarketCurve::Logit(c) => c.pt_for_exchange_rate(asset, exchange_rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zeroed_bytes_read_as_logit() {
+        // markets created before the tag existed have zeroed space where it is read from
+        assert_eq!(CurveKind::try_from_slice(&[0]).unwrap(), CurveKind::Logit);
+        assert_eq!(CurveKind::Logit.try_to_vec().unwrap().len(), 1);
+    }
+}
@@ -1,10 +1,25 @@
 use anchor_lang::prelude::*;
 use dec_num::DNum;
-use exponent_time_curve::math::{exchange_rate_from_ln_implied_rate, fee_rate};
-use precise_number::Number;
+use exponent_time_curve::{
+    curve::Curve,
+    math::{
+        exchange_rate_from_ln_implied_rate, fee_rate, largest_within,
+        net_trader_pt_for_exchange_rate, pt_out_for_asset_in,
+    },
+    num::Num,
+};
+use precise_number::{Number, SignedNumber};
 use sy_common::PositionState;
 
-use crate::{cpi_common::CpiAccounts, error::ExponentCoreError};
+use crate::{
+    cpi_common::CpiAccounts,
+    error::ExponentCoreError,
+    state::{CurveKind, MarketCurve},
+    utils::{
+        curve_num::{CurveNum, MarketNum},
+        py_to_sy_ceil,
+    },
+};
 
 /// Minimum size of market operations
 /// Used to protect against rounding errors
@@ -87,6 +102,49 @@ pub struct MarketTwo {
 
     /// Unique seed id for the market
     pub seed_id: [u8; 1],
+
+    // Fields below were appended after launch, in the order they were added
+    // Markets created before them are extended by migrate_market
+    /// Observations of the ln implied rate, for time-weighted rates
+    pub rate_oracle: RateOracle,
+
+    /// Invariant the market prices trades on
+    pub curve_kind: CurveKind,
+
+    /// Fee on top of the base fee that rises with how far trades move the implied rate
+    pub dynamic_fee: DynamicFee,
+
+    /// Fee for YT trades, which are priced apart from PT trades
+    /// YT trades are charged the PT fee if this is not set
+    pub yt_fee: Option<YtFee>,
+}
+
+/// MarketTwo as laid out at launch, before any field was appended
+#[derive(AnchorDeserialize)]
+struct LaunchMarketTwo {
+    address_lookup_table: Pubkey,
+    mint_pt: Pubkey,
+    mint_sy: Pubkey,
+    vault: Pubkey,
+    mint_lp: Pubkey,
+    token_lp_escrow: Pubkey,
+    token_pt_escrow: Pubkey,
+    token_sy_escrow: Pubkey,
+    token_fee_treasury_sy: Pubkey,
+    fee_treasury_sy_bps: u16,
+    self_address: Pubkey,
+    signer_bump: [u8; 1],
+    status_flags: u8,
+    sy_program: Pubkey,
+    financials: MarketFinancials,
+    emissions: MarketEmissions,
+    lp_farm: LpFarm,
+    max_lp_supply: u64,
+    lp_escrow_amount: u64,
+    cpi_accounts: CpiAccounts,
+    is_current_flash_swap: bool,
+    liquidity_net_balance_limits: LiquidityNetBalanceLimits,
+    seed_id: [u8; 1],
 }
 
 /// Financial parameters for the market
@@ -141,6 +199,7 @@ impl MarketTwo {
         cpi_accounts: &CpiAccounts,
         emissions_len: usize,
         farm_emissions_len: usize,
+        oracle_capacity: usize,
     ) -> usize {
         // Get size of dynamic vectors in the CpiAccounts struct
         let cpi_accounts_size = cpi_accounts.try_to_vec().unwrap().len();
@@ -214,7 +273,85 @@ impl MarketTwo {
         LiquidityNetBalanceLimits::SIZE_OF +
 
         // Seed id
-        1
+        1 +
+
+        // rate_oracle
+        RateOracle::size_of(oracle_capacity) +
+
+        // curve_kind
+        1 +
+
+        // dynamic_fee
+        DynamicFee::SIZE_OF +
+
+        // yt_fee
+        1 + YtFee::SIZE_OF
+    }
+
+    /// Bytes that markets created before the appended fields are missing
+    pub const MIGRATION_SIZE: usize =
+        // rate_oracle
+        RateOracle::size_of(0) +
+        // curve_kind
+        1 +
+        // dynamic_fee
+        DynamicFee::SIZE_OF +
+        // yt_fee
+        1 + YtFee::SIZE_OF;
+
+    /// Read a market created before the appended fields, from account data that does not hold them
+    /// The appended fields take their defaults, whatever bytes follow the launch layout
+    pub fn from_unmigrated(data: &[u8]) -> Result<Self> {
+        require!(
+            data.starts_with(Self::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        let launch = LaunchMarketTwo::deserialize(&mut &data[Self::DISCRIMINATOR.len()..])
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+
+        Ok(Self {
+            address_lookup_table: launch.address_lookup_table,
+            mint_pt: launch.mint_pt,
+            mint_sy: launch.mint_sy,
+            vault: launch.vault,
+            mint_lp: launch.mint_lp,
+            token_lp_escrow: launch.token_lp_escrow,
+            token_pt_escrow: launch.token_pt_escrow,
+            token_sy_escrow: launch.token_sy_escrow,
+            token_fee_treasury_sy: launch.token_fee_treasury_sy,
+            fee_treasury_sy_bps: launch.fee_treasury_sy_bps,
+            self_address: launch.self_address,
+            signer_bump: launch.signer_bump,
+            status_flags: launch.status_flags,
+            sy_program: launch.sy_program,
+            financials: launch.financials,
+            emissions: launch.emissions,
+            lp_farm: launch.lp_farm,
+            max_lp_supply: launch.max_lp_supply,
+            lp_escrow_amount: launch.lp_escrow_amount,
+            cpi_accounts: launch.cpi_accounts,
+            is_current_flash_swap: launch.is_current_flash_swap,
+            liquidity_net_balance_limits: launch.liquidity_net_balance_limits,
+            seed_id: launch.seed_id,
+            rate_oracle: RateOracle::default(),
+            curve_kind: CurveKind::default(),
+            dynamic_fee: DynamicFee::default(),
+            yt_fee: None,
+        })
+    }
+
+    /// Let the rate oracle grow into the part of an `account_len` byte account the market does not reserve
+    /// The market reserves room for its largest encoding, so the oracle never takes space a setting may need
+    pub fn grow_rate_oracle(&mut self, account_len: usize) {
+        let reserved = Self::size_of(
+            &self.cpi_accounts,
+            self.emissions.trackers.len(),
+            self.lp_farm.farm_emissions.len(),
+            self.rate_oracle.capacity.into(),
+        );
+
+        self.rate_oracle.grow(account_len.saturating_sub(reserved));
     }
 
     pub fn is_expired(&self, now: u64) -> bool {
@@ -225,6 +362,12 @@ impl MarketTwo {
         !self.is_expired(now)
     }
 
+    /// Follow the vault to an early maturity, leaving liquidity withdrawals as the only enabled action
+    pub fn wind_down(&mut self, expiration_ts: u64) {
+        self.financials.expiration_ts = self.financials.expiration_ts.min(expiration_ts);
+        self.status_flags &= STATUS_CAN_WITHDRAW_LIQUIDITY;
+    }
+
     pub fn new(
         self_address: Pubkey,
         signer_bump: [u8; 1],
@@ -259,17 +402,20 @@ impl MarketTwo {
             .checked_sub(Clock::get().unwrap().unix_timestamp as u64)
             .expect("Vault expired");
         // current rate scalar is based on time remaining
-        let rate_scalar =
-            exponent_time_curve::math::rate_scalar::<f64>(rate_scalar_root.into(), sec_remaining);
+        let rate_scalar = exponent_time_curve::math::rate_scalar::<MarketNum>(
+            MarketNum::from_f64(rate_scalar_root),
+            sec_remaining,
+        );
 
         // calculate implied rate (APY) based on state of curve
         let ln_implied_rate = exponent_time_curve::math::ln_implied_rate(
             pt_init,
             asset,
             rate_scalar,
-            init_rate_anchor.into(),
+            MarketNum::from_f64(init_rate_anchor),
             sec_remaining,
-        );
+        )
+        .into_f64();
 
         let emissions = MarketEmissions::default();
 
@@ -316,6 +462,10 @@ impl MarketTwo {
                 window_start_net_balance: 0,
             },
             seed_id: [seed_id],
+            curve_kind: CurveKind::Logit,
+            dynamic_fee: DynamicFee::default(),
+            yt_fee: None,
+            rate_oracle: RateOracle::with_capacity(RateOracle::DEFAULT_CAPACITY),
         }
     }
 
@@ -338,6 +488,43 @@ impl MarketTwo {
         }
     }
 
+    /// Record the ln implied rate that has held since the last observation
+    /// Must be called before a trade moves the rate
+    pub fn record_rate_observation(&mut self, now: u64) {
+        self.rate_oracle
+            .observe(now, self.financials.last_ln_implied_rate);
+    }
+
+    /// Time-weighted average ln implied rate over the `window` seconds before `now`
+    /// Returns None if the oracle does not reach back far enough
+    pub fn twap_ln_implied_rate(&self, now: u64, window: u64) -> Option<f64> {
+        let spot = self.financials.last_ln_implied_rate;
+        let start = now.checked_sub(window)?;
+
+        let cumulative_end = self.rate_oracle.ln_implied_rate_cumulative_at(now, spot)?;
+        let cumulative_start = self
+            .rate_oracle
+            .ln_implied_rate_cumulative_at(start, spot)?;
+
+        Some((cumulative_end - cumulative_start) / window as f64)
+    }
+
+    /// Fees a trade at `now` is charged with
+    /// The dynamic fee measures a trade's move from the average rate over its window,
+    /// or from the last seen rate if the oracle does not reach back that far
+    pub fn trade_fees(&self, now: u64) -> TradeFees {
+        let reference_ln_implied_rate = self
+            .twap_ln_implied_rate(now, self.dynamic_fee.window_seconds.into())
+            .unwrap_or(self.financials.last_ln_implied_rate);
+
+        TradeFees {
+            treasury_sy_bps: self.fee_treasury_sy_bps,
+            dynamic_fee: self.dynamic_fee,
+            yt_fee: self.yt_fee,
+            reference_ln_implied_rate,
+        }
+    }
+
     pub fn add_farm(&mut self, token_rate: u64, expiry_ts: u32, token_mint: &Pubkey) {
         self.lp_farm.farm_emissions.push(FarmEmission {
             mint: *token_mint,
@@ -364,32 +551,36 @@ impl MarketFinancials {
         16;
 
     /// Used for direct manipulation when swapping with borrowed funds
-    pub fn dec_pt_balance(&mut self, amt: u64) {
+    pub fn dec_pt_balance(&mut self, amt: u64) -> Result<()> {
         self.pt_balance = self
             .pt_balance
             .checked_sub(amt)
-            .expect("pt balance underflow");
+            .ok_or(ExponentCoreError::InsufficientPtLiquidity)?;
+        Ok(())
     }
 
-    pub fn inc_pt_balance(&mut self, amt: u64) {
+    pub fn inc_pt_balance(&mut self, amt: u64) -> Result<()> {
         self.pt_balance = self
             .pt_balance
             .checked_add(amt)
-            .expect("pt balance overflow");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        Ok(())
     }
 
-    pub fn dec_sy_balance(&mut self, amt: u64) {
+    pub fn dec_sy_balance(&mut self, amt: u64) -> Result<()> {
         self.sy_balance = self
             .sy_balance
             .checked_sub(amt)
-            .expect("sy balance underflow");
+            .ok_or(ExponentCoreError::InsufficientSyLiquidity)?;
+        Ok(())
     }
 
-    pub fn inc_sy_balance(&mut self, amt: u64) {
+    pub fn inc_sy_balance(&mut self, amt: u64) -> Result<()> {
         self.sy_balance = self
             .sy_balance
             .checked_add(amt)
-            .expect("sy balance overflow");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        Ok(())
     }
 
     fn sec_remaining(&self, now: u64) -> u64 {
@@ -402,7 +593,7 @@ impl MarketFinancials {
     }
 
     /// Calculate the current rate anchor
-    fn current_rate_anchor(&self, sy_exchange_rate: Number, now: u64) -> f64 {
+    fn current_rate_anchor<N: CurveNum>(&self, sy_exchange_rate: Number, now: u64) -> N {
         let sec_remaining = self.sec_remaining(now);
         let asset = self.asset_balance(sy_exchange_rate).floor_u64();
         let current_rate_scalar = self.current_rate_scalar(now);
@@ -410,41 +601,88 @@ impl MarketFinancials {
             self.pt_balance,
             asset,
             current_rate_scalar,
-            self.last_ln_implied_rate.into(),
+            N::from_f64(self.last_ln_implied_rate),
             sec_remaining,
         )
     }
 
     /// Calculate the current rate scalar
-    fn current_rate_scalar(&self, now: u64) -> f64 {
+    fn current_rate_scalar<N: CurveNum>(&self, now: u64) -> N {
         let sec_remaining = self.sec_remaining(now);
-        exponent_time_curve::math::rate_scalar::<f64>(self.rate_scalar_root, sec_remaining)
+        exponent_time_curve::math::rate_scalar::<N>(
+            N::from_f64(self.rate_scalar_root),
+            sec_remaining,
+        )
+    }
+
+    /// Build the market's curve at the current time, anchored to the last seen implied rate
+    fn curve<N: CurveNum>(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        now: u64,
+    ) -> MarketCurve<N> {
+        curve_kind.curve(
+            N::from_f64(self.rate_scalar_root),
+            self.pt_balance,
+            self.asset_balance(sy_exchange_rate).floor_u64(),
+            N::from_f64(self.last_ln_implied_rate),
+            self.sec_remaining(now),
+        )
     }
 
     /// Calculate the current fee rate base on the decay from the initial fee rate
-    fn cur_fee_rate(&self, now: u64) -> f64 {
-        fee_rate(self.ln_fee_rate_root.into(), self.sec_remaining(now))
+    fn cur_fee_rate<N: CurveNum>(&self, now: u64) -> N {
+        fee_rate(N::from_f64(self.ln_fee_rate_root), self.sec_remaining(now))
     }
 
-    /// Calculate SY change from PT trade
-    /// And update the state of the market
-    /// - change sy balance
-    /// - change pt balance
-    /// - change last_ln_implied_rate
+    /// The ln fee rate root and treasury split of a trade's base fee
+    /// YT flash swaps are charged the market's YT fee if it has one
+    fn base_fee(&self, is_current_flash_swap: bool, fees: &TradeFees) -> (f64, u16) {
+        match fees.yt_fee {
+            Some(yt_fee) if is_current_flash_swap => {
+                (yt_fee.ln_fee_rate_root, yt_fee.treasury_sy_bps)
+            }
+            _ => (self.ln_fee_rate_root, fees.treasury_sy_bps),
+        }
+    }
+
+    /// Calculate SY change from PT trade without updating the market
     ///
     /// # Arguments
+    /// - `curve_kind` - The curve the market prices trades on
     /// - `sy_exchange_rate` - The exchange rate of the SY token to the base asset
     /// - `net_trader_pt` - The net PT change to the trader
     /// - `now` - The current unix timestamp
-    /// - `fee_treasury_sy_bps` - The treasury fee in basis points
-    pub fn trade_pt(
-        &mut self,
+    /// - `fees` - The treasury split, dynamic fee and YT fee of the market
+    pub fn quote_trade_pt(
+        &self,
+        curve_kind: CurveKind,
         sy_exchange_rate: Number,
         net_trader_pt: i64,
         now: u64,
         is_current_flash_swap: bool,
-        fee_treasury_sy_bps: u16,
-    ) -> TradeResult {
+        fees: TradeFees,
+    ) -> Result<TradeResult> {
+        self.quote_trade_pt_with::<MarketNum>(
+            curve_kind,
+            sy_exchange_rate,
+            net_trader_pt,
+            now,
+            is_current_flash_swap,
+            fees,
+        )
+    }
+
+    fn quote_trade_pt_with<N: CurveNum>(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        net_trader_pt: i64,
+        now: u64,
+        is_current_flash_swap: bool,
+        fees: TradeFees,
+    ) -> Result<TradeResult> {
         // if the net pt to the trader is positive, he is buying
         let is_buy = net_trader_pt > 0;
         // Get the market liquidity in terms of base asset
@@ -459,80 +697,372 @@ impl MarketFinancials {
             asset_balance.floor_u64()
         };
 
-        // Pre-compute the current rate scalar and rate anchor
-        let current_rate_scalar = self.current_rate_scalar(now);
-        let current_rate_anchor = self.current_rate_anchor(sy_exchange_rate, now);
-        let current_fee_rate = self.cur_fee_rate(now);
+        let curve = self.curve::<N>(curve_kind, sy_exchange_rate, now);
+        let trade = |fee_rate: N| {
+            curve
+                .trade(
+                    self.pt_balance,
+                    asset_balance,
+                    fee_rate,
+                    N::from_i64(net_trader_pt),
+                    is_current_flash_swap,
+                )
+                .map_err(ExponentCoreError::from)
+        };
 
-        // Calculate the trade result
-        let trade_result = exponent_time_curve::math::trade(
-            self.pt_balance,
-            asset_balance,
-            current_rate_scalar,
-            current_rate_anchor,
-            current_fee_rate,
-            net_trader_pt as f64,
-            is_current_flash_swap,
-        );
+        let (ln_fee_rate_root, treasury_sy_bps) = self.base_fee(is_current_flash_swap, &fees);
+        let sec_remaining = self.sec_remaining(now);
+
+        // Calculate the trade result with the base fee
+        let base_result = trade(fee_rate(N::from_f64(ln_fee_rate_root), sec_remaining))?;
+        let base_sy_fee = sy_fee_from_asset_fee(base_result.asset_fee, sy_exchange_rate)?;
+
+        // The dynamic fee is priced from where the trade at the base fee leaves the implied rate
+        let dynamic_ln_fee_rate_root = if fees.dynamic_fee.is_enabled() {
+            let ln_implied_rate = curve
+                .ln_implied_rate(
+                    self.pt_balance
+                        .checked_add_signed(-net_trader_pt)
+                        .ok_or(ExponentCoreError::InsufficientPtLiquidity)?,
+                    (N::from_u64(asset_balance) - base_result.net_trader_asset).to_u64(),
+                )
+                .into_f64();
+
+            fees.dynamic_fee
+                .ln_fee_rate_root(ln_implied_rate, fees.reference_ln_implied_rate)
+        } else {
+            0.0
+        };
+
+        let trade_result = if dynamic_ln_fee_rate_root > 0.0 {
+            trade(fee_rate(
+                N::from_f64(ln_fee_rate_root + dynamic_ln_fee_rate_root),
+                sec_remaining,
+            ))?
+        } else {
+            base_result
+        };
 
         // calc the abs magnitude of the trade in
         let net_trader_sy =
-            net_trader_sy_from_net_trader_asset(trade_result.net_trader_asset, sy_exchange_rate);
-
-        // the actual change to the market's sy balance is the same as the net change to the trader
-        // if (eventually) a platform fee is taken from the trade, then the market's change in SY balance needs to account for this withdrawal
-        let market_sy_change = net_trader_sy.abs() as u64;
+            net_trader_sy_from_net_trader_asset(trade_result.net_trader_asset, sy_exchange_rate)?;
 
         // Convert fee to SY units
-        let sy_fee = sy_fee_from_asset_fee(trade_result.asset_fee, sy_exchange_rate);
+        let sy_fee = sy_fee_from_asset_fee(trade_result.asset_fee, sy_exchange_rate)?;
 
         // Calculate treasury fee amount
-        let treasury_fee_amount = (sy_fee * fee_treasury_sy_bps as u64) / 10_000;
+        let treasury_fee_amount = (sy_fee * treasury_sy_bps as u64) / 10_000;
+
+        Ok(TradeResult {
+            sy_fee,
+            dynamic_sy_fee: sy_fee.saturating_sub(base_sy_fee),
+            net_trader_sy,
+            net_trader_pt,
+            treasury_fee_amount,
+        })
+    }
+
+    /// Calculate SY change from PT trade
+    /// And update the state of the market
+    /// - change sy balance
+    /// - change pt balance
+    /// - change last_ln_implied_rate
+    ///
+    /// # Arguments
+    /// - `curve_kind` - The curve the market prices trades on
+    /// - `sy_exchange_rate` - The exchange rate of the SY token to the base asset
+    /// - `net_trader_pt` - The net PT change to the trader
+    /// - `now` - The current unix timestamp
+    /// - `fees` - The treasury split, dynamic fee and YT fee of the market
+    pub fn trade_pt(
+        &mut self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        net_trader_pt: i64,
+        now: u64,
+        is_current_flash_swap: bool,
+        fees: TradeFees,
+    ) -> Result<TradeResult> {
+        self.trade_pt_with::<MarketNum>(
+            curve_kind,
+            sy_exchange_rate,
+            net_trader_pt,
+            now,
+            is_current_flash_swap,
+            fees,
+        )
+    }
+
+    fn trade_pt_with<N: CurveNum>(
+        &mut self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        net_trader_pt: i64,
+        now: u64,
+        is_current_flash_swap: bool,
+        fees: TradeFees,
+    ) -> Result<TradeResult> {
+        // if the net pt to the trader is positive, he is buying
+        let is_buy = net_trader_pt > 0;
+
+        // Pre-compute the current curve
+        // the new implied rate must be anchored to the pre-trade state
+        let curve = self.curve::<N>(curve_kind, sy_exchange_rate, now);
+
+        let trade_result = self.quote_trade_pt_with::<N>(
+            curve_kind,
+            sy_exchange_rate,
+            net_trader_pt,
+            now,
+            is_current_flash_swap,
+            fees,
+        )?;
+
+        // the actual change to the market's sy balance is the same as the net change to the trader
+        // if (eventually) a platform fee is taken from the trade, then the market's change in SY balance needs to account for this withdrawal
+        let market_sy_change = trade_result.net_trader_sy.unsigned_abs();
 
         // Handle changes to market liquidity balances
         if is_buy {
             // Buying PT
 
             // market PT balance goes down
-            self.dec_pt_balance(net_trader_pt as u64);
+            self.dec_pt_balance(net_trader_pt.unsigned_abs())?;
 
             // market SY balance goes up
-            self.inc_sy_balance(market_sy_change);
+            self.inc_sy_balance(market_sy_change)?;
         } else {
             // Selling PT
 
             // market PT balance goes up
-            self.inc_pt_balance((-net_trader_pt) as u64);
+            self.inc_pt_balance(net_trader_pt.unsigned_abs())?;
 
             // market SY balance goes down
-            self.dec_sy_balance(market_sy_change);
+            self.dec_sy_balance(market_sy_change)?;
         }
 
         // Deduct treasury fee from SY balance
-        self.dec_sy_balance(treasury_fee_amount);
+        self.dec_sy_balance(trade_result.treasury_fee_amount)?;
 
         // set the new ln implied rate based on the new proportion AFTER all balance adjustments
-        let new_ln_implied_rate = exponent_time_curve::math::ln_implied_rate(
+        let new_ln_implied_rate = curve.ln_implied_rate(
             self.pt_balance,
             self.asset_balance(sy_exchange_rate).floor_u64(),
-            current_rate_scalar,
-            current_rate_anchor,
-            self.sec_remaining(now),
         );
 
-        self.last_ln_implied_rate = new_ln_implied_rate.into();
+        self.last_ln_implied_rate = new_ln_implied_rate.into_f64();
 
-        TradeResult {
-            sy_fee,
-            net_trader_sy,
+        Ok(trade_result)
+    }
+
+    /// Calculate the amount of SY a trader must pay to buy `pt_out`, including fees
+    /// The market state is not mutated
+    pub fn sy_in_for_pt_out(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        pt_out: u64,
+        now: u64,
+        is_current_flash_swap: bool,
+        fees: TradeFees,
+    ) -> Result<u64> {
+        let net_trader_pt: i64 = pt_out
+            .try_into()
+            .map_err(|_| ExponentCoreError::MathOverflow)?;
+
+        // the treasury cut comes out of the fee, so it does not change what the trader pays
+        let trade_result = self.quote_trade_pt(
+            curve_kind,
+            sy_exchange_rate,
             net_trader_pt,
-            treasury_fee_amount,
+            now,
+            is_current_flash_swap,
+            TradeFees {
+                treasury_sy_bps: 0,
+                ..fees
+            },
+        )?;
+
+        Ok(trade_result.net_trader_sy.unsigned_abs())
+    }
+
+    /// Find the largest amount of PT that can be bought by spending at most `sy_in`
+    /// The market state is not mutated
+    pub fn pt_out_for_sy_in(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        sy_in: u64,
+        now: u64,
+        is_current_flash_swap: bool,
+        fees: TradeFees,
+    ) -> u64 {
+        // trade_pt charges ceil(ceil(asset) / rate) SY on a buy
+        // so the largest asset amount that fits within sy_in is floor(sy_in * rate)
+        let asset_in = (Number::from_natural_u64(sy_in) * sy_exchange_rate).floor_u64();
+
+        // same rounding as trade_pt when buying PT
+        let asset_balance = self.asset_balance(sy_exchange_rate).ceil_u64();
+
+        let (ln_fee_rate_root, _) = self.base_fee(is_current_flash_swap, &fees);
+
+        let pt_out = pt_out_for_asset_in::<MarketNum>(
+            &self.curve(curve_kind, sy_exchange_rate, now),
+            self.pt_balance,
+            asset_balance,
+            fee_rate(
+                MarketNum::from_f64(ln_fee_rate_root),
+                self.sec_remaining(now),
+            ),
+            MarketNum::from_u64(asset_in),
+            is_current_flash_swap,
+        );
+
+        let excess = |pt_out: u64| {
+            self.sy_in_for_pt_out(
+                curve_kind,
+                sy_exchange_rate,
+                pt_out,
+                now,
+                is_current_flash_swap,
+                fees,
+            )
+            .ok()
+            .map(|cost| MarketNum::from_u64(cost) - MarketNum::from_u64(sy_in))
+        };
+
+        // the solver works on the continuous curve with the base fee, so its answer can be more than the exact trade path allows
+        if pt_out == 0 || excess(pt_out).is_some_and(|e| e <= MarketNum::zero()) {
+            return pt_out;
+        }
+
+        // close in from below on what the exact trade path, with its rounding and any dynamic fee, fits within sy_in
+        // rounding alone is settled by the first probe, one below the solver's answer
+        // each probe quotes the trade, so the solve is capped at MAX_SOLVER_PROBES quotes
+        largest_within(MarketNum::from_u64(sy_in), pt_out, Some(pt_out - 1), excess)
+    }
+
+    /// Calculate the net SY a trader spends to buy `yt_out` through buy_yt
+    /// The trader strips SY into PT & YT, and the PT is sold back into the market as a flash swap
+    /// Returns None if the market cannot absorb the PT being sold
+    /// The market state is not mutated
+    pub fn sy_in_for_yt_out(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        yt_out: u64,
+        now: u64,
+        fees: TradeFees,
+    ) -> Option<u64> {
+        // selling as much PT as there is asset pushes the PT proportion off the curve
+        if yt_out >= self.asset_balance(sy_exchange_rate).floor_u64() {
+            return None;
         }
+
+        let net_trader_pt: i64 = yt_out.try_into().ok()?;
+        let trade_result = self
+            .quote_trade_pt(
+                curve_kind,
+                sy_exchange_rate,
+                -net_trader_pt,
+                now,
+                true,
+                fees,
+            )
+            .ok()?;
+
+        // the market must be able to pay out the SY and the treasury fee
+        let sy_received = trade_result.net_trader_sy.unsigned_abs();
+        if sy_received.checked_add(trade_result.treasury_fee_amount)? > self.sy_balance {
+            return None;
+        }
+
+        let sy_to_strip = py_to_sy_ceil(sy_exchange_rate, yt_out);
+        Some(sy_to_strip.saturating_sub(sy_received))
+    }
+
+    /// Find the largest amount of YT that can be bought through buy_yt by spending at most `sy_in`
+    /// The market state is not mutated
+    pub fn yt_out_for_sy_in(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        sy_in: u64,
+        now: u64,
+        fees: TradeFees,
+    ) -> u64 {
+        // spot estimate, which overshoots since the PT sale slips down the curve and pays a fee
+        let yt_price_in_asset =
+            MarketNum::one() - MarketNum::one() / self.exchange_rate_with::<MarketNum>(now);
+        let estimate = MarketNum::from_number_checked(sy_exchange_rate).and_then(|rate| {
+            (MarketNum::from_u64(sy_in) * rate / yt_price_in_asset).to_u64_checked()
+        });
+
+        // the cost of YT grows with size, and selling as much PT as there is asset is off the curve
+        // each probe quotes the PT sale, so the solve is capped at MAX_SOLVER_PROBES quotes
+        largest_within(
+            MarketNum::from_u64(sy_in),
+            self.asset_balance(sy_exchange_rate).floor_u64(),
+            estimate,
+            |yt_out| {
+                self.sy_in_for_yt_out(curve_kind, sy_exchange_rate, yt_out, now, fees)
+                    .map(|cost| MarketNum::from_u64(cost) - MarketNum::from_u64(sy_in))
+            },
+        )
+    }
+
+    /// Find the net PT a trader must buy (negative to sell) to move the implied rate to `target_ln_implied_rate`
+    /// The solve runs on the curve with the base fee left in the pool,
+    /// so the rate the trade records can differ from the target by the treasury's cut of the fee, any dynamic fee and rounding.
+    /// The market state is not mutated
+    pub fn net_trader_pt_for_ln_implied_rate(
+        &self,
+        curve_kind: CurveKind,
+        sy_exchange_rate: Number,
+        target_ln_implied_rate: f64,
+        now: u64,
+        is_current_flash_swap: bool,
+    ) -> i64 {
+        let target_exchange_rate = exchange_rate_from_ln_implied_rate::<MarketNum>(
+            MarketNum::from_f64(target_ln_implied_rate),
+            self.sec_remaining(now),
+        );
+
+        // same asset balance the rate anchor is found from, so no trade means no change in rate
+        net_trader_pt_for_exchange_rate::<MarketNum>(
+            &self.curve(curve_kind, sy_exchange_rate, now),
+            self.pt_balance,
+            self.asset_balance(sy_exchange_rate).floor_u64(),
+            self.cur_fee_rate(now),
+            target_exchange_rate,
+            is_current_flash_swap,
+        )
+    }
+
+    /// Current rate scalar of the logit curve
+    pub fn rate_scalar(&self, now: u64) -> f64 {
+        self.current_rate_scalar::<MarketNum>(now).into_f64()
+    }
+
+    /// Current rate anchor of the logit curve, which keeps the implied rate continuous
+    pub fn rate_anchor(&self, sy_exchange_rate: Number, now: u64) -> f64 {
+        self.current_rate_anchor::<MarketNum>(sy_exchange_rate, now)
+            .into_f64()
+    }
+
+    /// Current base fee rate, after decay from the initial fee rate
+    pub fn fee_rate(&self, now: u64) -> f64 {
+        self.cur_fee_rate::<MarketNum>(now).into_f64()
     }
 
     pub fn exchange_rate(&self, unix_timestamp: u64) -> f64 {
-        exchange_rate_from_ln_implied_rate::<f64>(
-            self.last_ln_implied_rate.into(),
+        self.exchange_rate_with::<MarketNum>(unix_timestamp)
+            .into_f64()
+    }
+
+    fn exchange_rate_with<N: CurveNum>(&self, unix_timestamp: u64) -> N {
+        exchange_rate_from_ln_implied_rate::<N>(
+            N::from_f64(self.last_ln_implied_rate),
             self.sec_remaining(unix_timestamp),
         )
     }
@@ -564,14 +1094,15 @@ impl MarketFinancials {
 
     pub fn add_liquidity(
         &mut self,
+        curve_kind: CurveKind,
         sy_intent: u64,
         pt_intent: u64,
         lp_supply: u64,
-    ) -> LiqAddResult {
+    ) -> Result<LiqAddResult> {
         // assert!(sy_intent >= MIN_TX_SIZE, "SY intent too small");
         // assert!(pt_intent >= MIN_TX_SIZE, "PT intent too small");
 
-        let r = exponent_time_curve::math::add_liquidity::<f64>(
+        let r = curve_kind.add_liquidity::<MarketNum>(
             sy_intent,
             pt_intent,
             lp_supply,
@@ -579,111 +1110,79 @@ impl MarketFinancials {
             self.pt_balance,
         );
 
-        self.inc_pt_balance(r.pt_in);
-        self.inc_sy_balance(r.sy_in);
+        self.inc_pt_balance(r.pt_in)?;
+        self.inc_sy_balance(r.sy_in)?;
 
-        LiqAddResult {
+        Ok(LiqAddResult {
             pt_in: r.pt_in,
             sy_in: r.sy_in,
             lp_out: r.lp_tokens_out,
-        }
+        })
     }
 
-    pub fn rm_liquidity(&mut self, lp_in: u64, lp_supply: u64) -> LiqRmResult {
+    pub fn rm_liquidity(
+        &mut self,
+        curve_kind: CurveKind,
+        lp_in: u64,
+        lp_supply: u64,
+    ) -> Result<LiqRmResult> {
         // assert!(lp_in >= MIN_TX_SIZE, "LP intent too small");
-        assert!(lp_in <= lp_supply, "LP intent too large");
+        require!(lp_in <= lp_supply, ExponentCoreError::InsufficientLpBalance);
 
-        let r = exponent_time_curve::math::rm_liquidity::<f64>(
+        let r = curve_kind.rm_liquidity::<MarketNum>(
             lp_in,
             lp_supply,
             self.sy_balance,
             self.pt_balance,
         );
 
-        self.dec_pt_balance(r.pt_out);
-        self.dec_sy_balance(r.sy_out);
+        self.dec_pt_balance(r.pt_out)?;
+        self.dec_sy_balance(r.sy_out)?;
 
-        LiqRmResult {
+        Ok(LiqRmResult {
             pt_out: r.pt_out,
             sy_out: r.sy_out,
-        }
+        })
     }
 
     /// Calc amount of SY owned by LP tokens
-    pub fn lp_to_sy(&self, lp_amount: u64, lp_supply: u64) -> u64 {
-        exponent_time_curve::math::lp_to_sy::<f64>(
-            lp_amount,
-            lp_supply,
-            self.sy_balance,
-            self.pt_balance,
-        )
+    pub fn lp_to_sy(&self, curve_kind: CurveKind, lp_amount: u64, lp_supply: u64) -> u64 {
+        curve_kind
+            .rm_liquidity::<MarketNum>(lp_amount, lp_supply, self.sy_balance, self.pt_balance)
+            .sy_out
     }
 }
 
-fn sy_magnitude_from_net_trader_asset(net_trader_asset: f64, sy_exchange_rate: Number) -> u64 {
-    // taking the floor before the absolute value is important
-    // if net_trader_asset is negative, we want to floor down towards -inf
-    // the reason for this is that: the trader is buying PT with asset, and so should be charged more asset
-
-    // if net_trader_asset is positive, we want to floor down towards 0
-    // this is because the trader is selling PT for asset, and so should be paid less asset
-
-    // the floor function returns the largest integer less than or equal to the number
+/// Convert the trader's net change in asset into their net change in SY
+fn net_trader_sy_from_net_trader_asset<N: CurveNum>(
+    net_trader_asset: N,
+    sy_exchange_rate: Number,
+) -> Result<i64> {
+    // both roundings go toward -inf, which is always against the trader
+    // if net_trader_asset is negative, the trader is buying PT with asset, and so should be charged more
+    // if net_trader_asset is positive, the trader is selling PT for asset, and so should be paid less
     // Example: -8.45 goes to -9
-
-    let is_negative = net_trader_asset.is_sign_negative();
-
-    let asset_magnitude: u64 =
-        f64_to_u64_checked(net_trader_asset.floor().abs()).expect("f64 overflow for u64");
-
-    let sy_magnitude = Number::from_natural_u64(asset_magnitude) / sy_exchange_rate;
-
-    if is_negative {
-        sy_magnitude.ceil_u64()
-    } else {
-        sy_magnitude.floor_u64()
-    }
-}
-
-fn f64_to_u64_checked(value: f64) -> Option<u64> {
-    // Check for invalid values: NaN, infinity, or negative numbers
-    if !value.is_finite() || value < 0.0 {
-        return None;
-    }
-
-    // Check if the value exceeds the maximum u64 value
-    if value > u64::MAX as f64 {
-        return None; // Overflow
-    }
-
-    // Perform the conversion safely
-    Some(value as u64)
-}
-
-fn net_trader_sy_from_net_trader_asset(net_trader_asset: f64, sy_exchange_rate: Number) -> i64 {
-    let sy_magnitude = sy_magnitude_from_net_trader_asset(net_trader_asset, sy_exchange_rate);
-    // buying PT means the trader is losing SY
-    let is_buy = net_trader_asset.is_sign_negative();
-
-    if is_buy {
-        // the trader is buying PT
-        // so their net sy change is negative
-        <u64 as TryInto<i64>>::try_into(sy_magnitude).expect("u64 overflow for i64") * -1
-    } else {
-        // the trader is selling PT
-        // so their net sy change is positive
-        sy_magnitude.try_into().expect("u64 overflow for i64")
-    }
+    let net_trader_asset = SignedNumber::from(
+        net_trader_asset
+            .floor()
+            .to_i64_checked()
+            .ok_or(ExponentCoreError::MathOverflow)?,
+    );
+
+    Ok((net_trader_asset / SignedNumber::from(sy_exchange_rate)).floor_i64())
 }
 
 /// Convert fee units from asset to SY units
-fn sy_fee_from_asset_fee(asset_fee: f64, sy_exchange_rate: Number) -> u64 {
-    let sy_exchange_rate = sy_exchange_rate.to_f64().unwrap();
+fn sy_fee_from_asset_fee<N: CurveNum>(asset_fee: N, sy_exchange_rate: Number) -> Result<u64> {
+    let sy_exchange_rate =
+        N::from_number_checked(sy_exchange_rate).ok_or(ExponentCoreError::MathOverflow)?;
     let sy_fee = (asset_fee / sy_exchange_rate).floor();
-    f64_to_u64_checked(sy_fee).expect("f64 overflow for u64")
+    Ok(sy_fee
+        .to_u64_checked()
+        .ok_or(ExponentCoreError::MathOverflow)?)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TradeResult {
     /// The change to trader's PT balance and market's PT liquidity
     pub net_trader_pt: i64,
@@ -691,10 +1190,26 @@ pub struct TradeResult {
     pub net_trader_sy: i64,
     /// The part of the trade that was a fee
     pub sy_fee: u64,
+    /// The part of sy_fee charged by the dynamic fee, with the rest being the base fee
+    pub dynamic_sy_fee: u64,
     /// The treasury fee amount that was deducted from SY balance
     pub treasury_fee_amount: u64,
 }
 
+/// Fees a trade is charged on top of the base fee's curve pricing
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TradeFees {
+    /// Share of the PT trade fee that goes to the treasury, in basis points
+    pub treasury_sy_bps: u16,
+
+    pub dynamic_fee: DynamicFee,
+
+    pub yt_fee: Option<YtFee>,
+
+    /// The ln implied rate a trade's move is measured from for the dynamic fee
+    pub reference_ln_implied_rate: f64,
+}
+
 /// Binary serialized DNum
 #[derive(Clone, Copy, Default, Debug, AnchorDeserialize, AnchorSerialize)]
 pub struct AnchorDecNum(pub [u8; 16]);
@@ -756,7 +1271,7 @@ impl LiquidityNetBalanceLimits {
         // Calculate what the new balance would be after the proposed change
         let new_balance = current_net_balance
             .checked_add_signed(proposed_change)
-            .unwrap();
+            .ok_or(ExponentCoreError::MathOverflow)?;
 
         // Calculate absolute and percentage changes from window start
         let start_balance = self.window_start_net_balance;
@@ -786,6 +1301,200 @@ impl LiquidityNetBalanceLimits {
     }
 }
 
+/// Fee on top of the base fee, which rises with how far a trade moves the ln implied rate from its recent average
+/// The dynamic part is added to ln_fee_rate_root, so it decays toward expiry the same way as the base fee
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, Default)]
+pub struct DynamicFee {
+    /// Amount added to ln_fee_rate_root per unit of ln implied rate moved
+    /// Zero turns the dynamic fee off
+    pub sensitivity: f64,
+
+    /// Most that the dynamic fee can add to ln_fee_rate_root
+    pub max_ln_fee_rate_root: f64,
+
+    /// Seconds of rate history the average rate is taken over
+    pub window_seconds: u32,
+}
+
+impl DynamicFee {
+    pub const SIZE_OF: usize =
+        // sensitivity
+        8 +
+        // max_ln_fee_rate_root
+        8 +
+        // window_seconds
+        4;
+
+    pub fn is_enabled(&self) -> bool {
+        self.sensitivity > 0.0 && self.max_ln_fee_rate_root > 0.0
+    }
+
+    /// Amount added to ln_fee_rate_root for a trade that leaves the rate at `ln_implied_rate`
+    pub fn ln_fee_rate_root(&self, ln_implied_rate: f64, reference_ln_implied_rate: f64) -> f64 {
+        let moved = (ln_implied_rate - reference_ln_implied_rate).abs();
+        (moved * self.sensitivity).min(self.max_ln_fee_rate_root)
+    }
+}
+
+/// Fee charged on YT trades in place of the PT fee
+/// YT flow tends to cost LPs more than PT flow, so it can be priced higher
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct YtFee {
+    /// Fee rate root for YT trades, which decays toward expiry like ln_fee_rate_root
+    pub ln_fee_rate_root: f64,
+
+    /// Share of the YT trade fee that goes to the treasury, in basis points
+    pub treasury_sy_bps: u16,
+}
+
+impl YtFee {
+    pub const SIZE_OF: usize =
+        // ln_fee_rate_root
+        8 +
+        // treasury_sy_bps
+        2;
+}
+
+/// Ring buffer of cumulative ln implied rate observations
+#[derive(AnchorDeserialize, AnchorSerialize, Default, Clone)]
+pub struct RateOracle {
+    /// Index of the most recent observation
+    pub head: u16,
+
+    /// Number of observations the market account has room for
+    /// Raised by realloc_market as the account grows
+    pub capacity: u16,
+
+    /// Observations in ring order, where the oldest follows `head`
+    /// Grows until `capacity` is reached, then overwrites the oldest
+    pub observations: Vec<RateObservation>,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Default, Clone, Copy, Debug)]
+pub struct RateObservation {
+    pub timestamp: u64,
+
+    /// Running time integral of the ln implied rate, in rate-seconds
+    pub ln_implied_rate_cumulative: f64,
+}
+
+impl RateObservation {
+    pub const SIZE_OF: usize =
+        // timestamp
+        8 +
+        // ln_implied_rate_cumulative
+        8;
+}
+
+impl RateOracle {
+    /// Observations a new market has room for, so its TWAP covers recent trades without a realloc_market
+    pub const DEFAULT_CAPACITY: u16 = 64;
+
+    pub fn with_capacity(capacity: u16) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub const fn size_of(capacity: usize) -> usize {
+        // head
+        2 +
+        // capacity
+        2 +
+        // vec len
+        4 + capacity * RateObservation::SIZE_OF
+    }
+
+    /// Raise the capacity by as many observations as fit in `free_bytes` beyond the current capacity
+    pub fn grow(&mut self, free_bytes: usize) {
+        let capacity = self.capacity as usize + free_bytes / RateObservation::SIZE_OF;
+        self.capacity = capacity.min(u16::MAX as usize) as u16;
+    }
+
+    /// Record that `ln_implied_rate` has held from the last observation until `now`
+    /// At most one observation is written per timestamp
+    pub fn observe(&mut self, now: u64, ln_implied_rate: f64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let Some(last) = self.observations.get(self.head as usize).copied() else {
+            self.observations.push(RateObservation {
+                timestamp: now,
+                ln_implied_rate_cumulative: 0.0,
+            });
+            self.head = 0;
+            return;
+        };
+
+        if now <= last.timestamp {
+            return;
+        }
+
+        let observation = RateObservation {
+            timestamp: now,
+            ln_implied_rate_cumulative: last.ln_implied_rate_cumulative
+                + ln_implied_rate * (now - last.timestamp) as f64,
+        };
+
+        // Only grow into new slots once the ring has wrapped around to the end, to keep the order intact
+        let next = self.head as usize + 1;
+        if next < self.observations.len() {
+            self.observations[next] = observation;
+            self.head = next as u16;
+        } else if self.observations.len() < self.capacity as usize {
+            self.observations.push(observation);
+            self.head = next as u16;
+        } else {
+            self.observations[0] = observation;
+            self.head = 0;
+        }
+    }
+
+    /// Observation by age, where 0 is the oldest
+    fn nth_oldest(&self, n: usize) -> RateObservation {
+        let len = self.observations.len();
+        self.observations[(self.head as usize + 1 + n) % len]
+    }
+
+    /// Cumulative ln implied rate at `ts`, where `ln_implied_rate` is the rate since the last observation
+    /// Returns None if `ts` is before the oldest observation
+    pub fn ln_implied_rate_cumulative_at(&self, ts: u64, ln_implied_rate: f64) -> Option<f64> {
+        let newest = *self.observations.get(self.head as usize)?;
+        if ts >= newest.timestamp {
+            return Some(
+                newest.ln_implied_rate_cumulative
+                    + ln_implied_rate * (ts - newest.timestamp) as f64,
+            );
+        }
+
+        if ts < self.nth_oldest(0).timestamp {
+            return None;
+        }
+
+        // Find the two observations around `ts`
+        let mut lo = 0;
+        let mut hi = self.observations.len() - 1;
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if self.nth_oldest(mid).timestamp <= ts {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        // The rate was constant between the two observations
+        let before = self.nth_oldest(lo);
+        let after = self.nth_oldest(hi);
+        let rate = (after.ln_implied_rate_cumulative - before.ln_implied_rate_cumulative)
+            / (after.timestamp - before.timestamp) as f64;
+
+        Some(before.ln_implied_rate_cumulative + rate * (ts - before.timestamp) as f64)
+    }
+}
+
 #[derive(AnchorDeserialize, AnchorSerialize, Default, Clone)]
 pub struct MarketEmissions {
     pub trackers: Vec<MarketEmission>,
@@ -944,3 +1653,728 @@ impl FarmEmission {
         self.index += increase_amount;
     }
 }
+
+#[cfg(test)]
+mod decimal_curve_tests {
+    use super::*;
+    use crate::utils::test_rng::XorShift;
+    use exponent_time_curve::math::post_trade_exchange_rate;
+
+    const CASES: usize = 2_000;
+
+    struct Case {
+        financials: MarketFinancials,
+        sy_exchange_rate: Number,
+        now: u64,
+        net_trader_pt: i64,
+    }
+
+    fn random_case(rng: &mut XorShift) -> Option<Case> {
+        let now = 1_700_000_000;
+        let sec_remaining = rng.range(86_400, 2 * 365 * 86_400);
+
+        let sy_exchange_rate =
+            Number::from_ratio(rng.range(1_000_000, 3_000_000) as u128, 1_000_000);
+        let pt_balance = rng.range(1_000_000, 1_000_000_000_000);
+        let sy_balance = rng.range(pt_balance / 10, pt_balance);
+
+        let financials = MarketFinancials {
+            expiration_ts: now + sec_remaining,
+            pt_balance,
+            sy_balance,
+            ln_fee_rate_root: 0.0001 + rng.unit() * 0.01,
+            last_ln_implied_rate: 0.01 + rng.unit() * 0.4,
+            rate_scalar_root: 5.0 + rng.unit() * 200.0,
+        };
+
+        // trade up to 5% of the PT liquidity, in either direction
+        let size = rng.range(1_000, pt_balance / 20) as i64;
+        let net_trader_pt = if rng.next_u64() & 1 == 0 { size } else { -size };
+
+        // skip states where the f64 curve itself rejects the trade
+        let asset = financials.asset_balance(sy_exchange_rate).floor_u64();
+        let er = post_trade_exchange_rate::<f64>(
+            pt_balance,
+            asset,
+            financials.current_rate_scalar(now),
+            financials.current_rate_anchor(sy_exchange_rate, now),
+            net_trader_pt as f64,
+        );
+        if er.is_nan() || er <= 1.0001 {
+            return None;
+        }
+
+        Some(Case {
+            financials,
+            sy_exchange_rate,
+            now,
+            net_trader_pt,
+        })
+    }
+
+    fn cases() -> impl Iterator<Item = Case> {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        std::iter::from_fn(move || loop {
+            if let Some(case) = random_case(&mut rng) {
+                return Some(case);
+            }
+        })
+        .take(CASES)
+    }
+
+    #[test]
+    fn trade_pt_matches_f64() {
+        for case in cases() {
+            let mut float = case.financials.clone();
+            let mut decimal = case.financials.clone();
+
+            let f = float
+                .trade_pt_with::<f64>(
+                    CurveKind::Logit,
+                    case.sy_exchange_rate,
+                    case.net_trader_pt,
+                    case.now,
+                    false,
+                    TradeFees {
+                        treasury_sy_bps: 2_000,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let d = decimal
+                .trade_pt_with::<DNum>(
+                    CurveKind::Logit,
+                    case.sy_exchange_rate,
+                    case.net_trader_pt,
+                    case.now,
+                    false,
+                    TradeFees {
+                        treasury_sy_bps: 2_000,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+
+            // integer outputs may only differ by rounding at the last unit
+            let sy_diff = f.net_trader_sy.abs_diff(d.net_trader_sy);
+            assert!(
+                sy_diff <= 1 + f.net_trader_sy.unsigned_abs() / 1_000_000_000,
+                "net_trader_sy diverged: f64 {} decimal {}",
+                f.net_trader_sy,
+                d.net_trader_sy
+            );
+            assert!(
+                f.sy_fee.abs_diff(d.sy_fee) <= 1 + f.sy_fee / 1_000_000_000,
+                "sy_fee diverged: f64 {} decimal {}",
+                f.sy_fee,
+                d.sy_fee
+            );
+
+            let rate_diff = (float.last_ln_implied_rate - decimal.last_ln_implied_rate).abs();
+            assert!(
+                rate_diff < 1e-9,
+                "ln implied rate diverged: f64 {} decimal {}",
+                float.last_ln_implied_rate,
+                decimal.last_ln_implied_rate
+            );
+        }
+    }
+
+    #[test]
+    fn flash_swap_fee_matches_f64() {
+        for case in cases() {
+            let f = case
+                .financials
+                .quote_trade_pt_with::<f64>(
+                    CurveKind::Logit,
+                    case.sy_exchange_rate,
+                    case.net_trader_pt,
+                    case.now,
+                    true,
+                    TradeFees::default(),
+                )
+                .unwrap();
+            let d = case
+                .financials
+                .quote_trade_pt_with::<DNum>(
+                    CurveKind::Logit,
+                    case.sy_exchange_rate,
+                    case.net_trader_pt,
+                    case.now,
+                    true,
+                    TradeFees::default(),
+                )
+                .unwrap();
+
+            assert!(
+                f.sy_fee.abs_diff(d.sy_fee) <= 1 + f.sy_fee / 1_000_000_000,
+                "flash swap sy_fee diverged: f64 {} decimal {}",
+                f.sy_fee,
+                d.sy_fee
+            );
+        }
+    }
+
+    #[test]
+    fn exchange_rate_matches_f64() {
+        for case in cases() {
+            let f = case.financials.exchange_rate_with::<f64>(case.now);
+            let d = case
+                .financials
+                .exchange_rate_with::<DNum>(case.now)
+                .into_f64();
+
+            assert!(
+                ((f - d) / f).abs() < 1e-12,
+                "exchange rate diverged: f64 {} decimal {}",
+                f,
+                d
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod dynamic_fee_tests {
+    use super::*;
+
+    struct Case {
+        financials: MarketFinancials,
+        sy_exchange_rate: Number,
+        now: u64,
+        net_trader_pt: i64,
+    }
+
+    /// Markets across balances and rates, each bought from and sold to by a small and a large trade
+    fn cases() -> impl Iterator<Item = Case> {
+        let now = 1_700_000_000;
+
+        [
+            (1_000_000_000_000, 800_000_000_000, 0.1),
+            (5_000_000_000, 4_000_000_000, 0.02),
+            (900_000_000_000, 200_000_000_000, 0.3),
+        ]
+        .into_iter()
+        .flat_map(move |(pt_balance, sy_balance, last_ln_implied_rate)| {
+            let financials = MarketFinancials {
+                expiration_ts: now + 120 * 86_400,
+                pt_balance,
+                sy_balance,
+                ln_fee_rate_root: 0.002,
+                last_ln_implied_rate,
+                rate_scalar_root: 30.0,
+            };
+
+            [
+                pt_balance as i64 / 1_000,
+                -(pt_balance as i64 / 1_000),
+                pt_balance as i64 / 20,
+                -(pt_balance as i64 / 20),
+            ]
+            .into_iter()
+            .map(move |net_trader_pt| Case {
+                financials: financials.clone(),
+                sy_exchange_rate: Number::from_ratio(11, 10),
+                now,
+                net_trader_pt,
+            })
+        })
+    }
+
+    #[test]
+    fn dynamic_fee_adds_to_base_fee() {
+        let fees = TradeFees {
+            treasury_sy_bps: 2_000,
+            dynamic_fee: DynamicFee {
+                sensitivity: 0.5,
+                max_ln_fee_rate_root: 0.01,
+                window_seconds: 3_600,
+            },
+            ..Default::default()
+        };
+
+        for case in cases() {
+            let quote = |fees| {
+                case.financials
+                    .quote_trade_pt(
+                        CurveKind::Logit,
+                        case.sy_exchange_rate,
+                        case.net_trader_pt,
+                        case.now,
+                        false,
+                        fees,
+                    )
+                    .unwrap()
+            };
+            let base = quote(TradeFees {
+                dynamic_fee: DynamicFee::default(),
+                ..fees
+            });
+            let dynamic = quote(fees);
+
+            assert_eq!(base.dynamic_sy_fee, 0);
+            assert!(dynamic.dynamic_sy_fee > 0);
+            assert_eq!(dynamic.sy_fee, base.sy_fee + dynamic.dynamic_sy_fee);
+            // the trader pays the dynamic fee in either direction
+            assert!(dynamic.net_trader_sy < base.net_trader_sy);
+
+            // SY in quotes still fit their budget with the dynamic fee
+            if case.net_trader_pt > 0 {
+                let sy_in = base.net_trader_sy.unsigned_abs();
+                let pt_out = case.financials.pt_out_for_sy_in(
+                    CurveKind::Logit,
+                    case.sy_exchange_rate,
+                    sy_in,
+                    case.now,
+                    false,
+                    fees,
+                );
+                let sy_spent = case
+                    .financials
+                    .sy_in_for_pt_out(
+                        CurveKind::Logit,
+                        case.sy_exchange_rate,
+                        pt_out,
+                        case.now,
+                        false,
+                        fees,
+                    )
+                    .unwrap();
+
+                assert!(sy_spent <= sy_in);
+                assert!(pt_out < case.net_trader_pt as u64);
+            }
+        }
+    }
+
+    /// A dynamic fee far above the base fee leaves the base fee solve far over budget,
+    /// and the capped solve on the exact trade path still closes in on the PT the budget buys
+    #[test]
+    fn sy_in_quote_closes_in_under_a_large_dynamic_fee() {
+        let fees = TradeFees {
+            dynamic_fee: DynamicFee {
+                sensitivity: 50.0,
+                max_ln_fee_rate_root: 0.5,
+                window_seconds: 3_600,
+            },
+            ..Default::default()
+        };
+
+        for case in cases().filter(|case| case.net_trader_pt > 0) {
+            let sy_in_for_pt_out = |pt_out| {
+                case.financials.sy_in_for_pt_out(
+                    CurveKind::Logit,
+                    case.sy_exchange_rate,
+                    pt_out,
+                    case.now,
+                    false,
+                    fees,
+                )
+            };
+            let pt_wanted = case.net_trader_pt as u64;
+            let sy_in = sy_in_for_pt_out(pt_wanted).unwrap();
+
+            let pt_out = case.financials.pt_out_for_sy_in(
+                CurveKind::Logit,
+                case.sy_exchange_rate,
+                sy_in,
+                case.now,
+                false,
+                fees,
+            );
+
+            assert!(sy_in_for_pt_out(pt_out).unwrap() <= sy_in);
+            assert!(pt_out >= pt_wanted - pt_wanted / 10_000);
+        }
+    }
+}
+
+#[cfg(test)]
+mod yt_fee_tests {
+    use super::*;
+
+    struct Case {
+        financials: MarketFinancials,
+        sy_exchange_rate: Number,
+        now: u64,
+        net_trader_pt: i64,
+    }
+
+    /// Markets bought from and sold to through the PT trade a YT flash swap makes
+    fn cases() -> impl Iterator<Item = Case> {
+        let now = 1_700_000_000;
+
+        [
+            (1_000_000_000_000, 800_000_000_000, 0.1),
+            (900_000_000_000, 200_000_000_000, 0.3),
+        ]
+        .into_iter()
+        .flat_map(move |(pt_balance, sy_balance, last_ln_implied_rate)| {
+            let financials = MarketFinancials {
+                expiration_ts: now + 120 * 86_400,
+                pt_balance,
+                sy_balance,
+                ln_fee_rate_root: 0.002,
+                last_ln_implied_rate,
+                rate_scalar_root: 30.0,
+            };
+
+            [pt_balance as i64 / 100, -(pt_balance as i64 / 100)]
+                .into_iter()
+                .map(move |net_trader_pt| Case {
+                    financials: financials.clone(),
+                    sy_exchange_rate: Number::from_ratio(11, 10),
+                    now,
+                    net_trader_pt,
+                })
+        })
+    }
+
+    #[test]
+    fn yt_fee_prices_flash_swaps_only() {
+        let yt_fees = TradeFees {
+            treasury_sy_bps: 2_000,
+            yt_fee: Some(YtFee {
+                ln_fee_rate_root: 0.05,
+                treasury_sy_bps: 5_000,
+            }),
+            ..Default::default()
+        };
+        let pt_fees = TradeFees {
+            yt_fee: None,
+            ..yt_fees
+        };
+
+        for case in cases() {
+            let quote = |is_current_flash_swap, fees| {
+                case.financials
+                    .quote_trade_pt(
+                        CurveKind::Logit,
+                        case.sy_exchange_rate,
+                        case.net_trader_pt,
+                        case.now,
+                        is_current_flash_swap,
+                        fees,
+                    )
+                    .unwrap()
+            };
+
+            // PT trades do not see the YT fee
+            let pt = quote(false, pt_fees);
+            let pt_with_yt_fee = quote(false, yt_fees);
+            assert_eq!(pt_with_yt_fee.sy_fee, pt.sy_fee);
+            assert_eq!(pt_with_yt_fee.treasury_fee_amount, pt.treasury_fee_amount);
+
+            // YT trades are charged the YT fee root and treasury split
+            let yt = quote(true, yt_fees);
+            assert!(yt.sy_fee > quote(true, pt_fees).sy_fee);
+            assert_eq!(yt.treasury_fee_amount, yt.sy_fee * 5_000 / 10_000);
+        }
+    }
+}
+
+#[cfg(test)]
+mod yt_out_for_sy_in_tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_largest_yt_within_budget() {
+        let now = 1_700_000_000;
+        let sy_exchange_rate = Number::from_ratio(11, 10);
+        let fees = TradeFees::default();
+
+        for (pt_balance, sy_balance, last_ln_implied_rate) in [
+            (1_000_000_000_000, 800_000_000_000, 0.1),
+            (5_000_000_000, 40_000_000_000, 0.02),
+            (900_000_000_000, 10_000_000_000, 0.3),
+        ] {
+            let financials = MarketFinancials {
+                expiration_ts: now + 120 * 86_400,
+                pt_balance,
+                sy_balance,
+                ln_fee_rate_root: 0.002,
+                last_ln_implied_rate,
+                rate_scalar_root: 30.0,
+            };
+            let cost = |yt_out| {
+                financials.sy_in_for_yt_out(CurveKind::Logit, sy_exchange_rate, yt_out, now, fees)
+            };
+
+            // SY rounding makes the cost zigzag by a unit, so budgets are kept well above that
+            for sy_in in [1_000_000, sy_balance / 100, sy_balance / 10, sy_balance] {
+                let fits = |yt_out| cost(yt_out).is_some_and(|cost| cost <= sy_in);
+
+                // reference answer by bisecting the whole asset balance
+                let (mut lo, mut hi) = (0, financials.asset_balance(sy_exchange_rate).floor_u64());
+                while hi - lo > 1 {
+                    let mid = lo + (hi - lo) / 2;
+                    if fits(mid) {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+
+                let yt_out = financials.yt_out_for_sy_in(
+                    CurveKind::Logit,
+                    sy_exchange_rate,
+                    sy_in,
+                    now,
+                    fees,
+                );
+
+                // the zigzag and the bisection the curve's limit needs leave the answer within a millionth
+                assert!(yt_out == 0 || fits(yt_out));
+                assert!(yt_out.abs_diff(lo) <= lo / 1_000_000 + 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod rate_oracle_tests {
+    use super::*;
+
+    fn oracle(capacity: u16) -> RateOracle {
+        RateOracle::with_capacity(capacity)
+    }
+
+    fn twap(oracle: &RateOracle, spot: f64, now: u64, window: u64) -> Option<f64> {
+        let end = oracle.ln_implied_rate_cumulative_at(now, spot)?;
+        let start = oracle.ln_implied_rate_cumulative_at(now - window, spot)?;
+        Some((end - start) / window as f64)
+    }
+
+    #[test]
+    fn time_weights_piecewise_rates() {
+        let mut o = oracle(8);
+        // 0.10 from t=100, 0.20 from t=200, 0.40 from t=300
+        o.observe(100, 0.05);
+        o.observe(200, 0.10);
+        o.observe(300, 0.20);
+
+        assert!((twap(&o, 0.40, 400, 300).unwrap() - (0.10 + 0.20 + 0.40) / 3.0).abs() < 1e-12);
+        // window starts between observations
+        assert!(
+            (twap(&o, 0.40, 400, 150).unwrap() - (0.20 * 50.0 + 0.40 * 100.0) / 150.0).abs()
+                < 1e-12
+        );
+        // window after the last observation is the spot rate
+        assert!((twap(&o, 0.40, 400, 50).unwrap() - 0.40).abs() < 1e-12);
+        // window before the oldest observation
+        assert!(twap(&o, 0.40, 400, 301).is_none());
+    }
+
+    #[test]
+    fn one_observation_per_timestamp() {
+        let mut o = oracle(8);
+        o.observe(100, 0.1);
+        o.observe(100, 0.5);
+        o.observe(150, 0.2);
+        o.observe(150, 0.9);
+
+        assert_eq!(o.observations.len(), 2);
+        assert!((o.observations[1].ln_implied_rate_cumulative - 0.2 * 50.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn wraps_and_grows_in_order() {
+        let mut o = oracle(3);
+        for t in 1..=5 {
+            o.observe(t * 10, 0.1);
+        }
+        assert_eq!(o.observations.len(), 3);
+        assert!(twap(&o, 0.1, 50, 20).is_some());
+        assert!(twap(&o, 0.1, 50, 21).is_none());
+
+        // growing mid-ring keeps overwriting until the ring reaches the end again
+        o.grow(2 * RateObservation::SIZE_OF);
+        assert_eq!(o.capacity, 5);
+        for t in 6..=12 {
+            o.observe(t * 10, 0.1);
+        }
+        assert_eq!(o.observations.len(), 5);
+        for n in 1..5 {
+            assert!(o.nth_oldest(n - 1).timestamp < o.nth_oldest(n).timestamp);
+        }
+        assert!((twap(&o, 0.1, 120, 40).unwrap() - 0.1).abs() < 1e-12);
+        assert!(twap(&o, 0.1, 120, 41).is_none());
+    }
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+
+    fn market() -> MarketTwo {
+        MarketTwo {
+            address_lookup_table: Pubkey::new_unique(),
+            mint_pt: Pubkey::new_unique(),
+            mint_sy: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            mint_lp: Pubkey::new_unique(),
+            token_lp_escrow: Pubkey::new_unique(),
+            token_pt_escrow: Pubkey::new_unique(),
+            token_sy_escrow: Pubkey::new_unique(),
+            token_fee_treasury_sy: Pubkey::new_unique(),
+            fee_treasury_sy_bps: 2_000,
+            self_address: Pubkey::new_unique(),
+            signer_bump: [254],
+            status_flags: ALL_FLAGS,
+            sy_program: Pubkey::new_unique(),
+            financials: MarketFinancials {
+                expiration_ts: 1_800_000_000,
+                pt_balance: 1_000_000,
+                sy_balance: 900_000,
+                ln_fee_rate_root: 0.001,
+                last_ln_implied_rate: 0.08,
+                rate_scalar_root: 50.0,
+            },
+            emissions: MarketEmissions::default(),
+            lp_farm: LpFarm::default(),
+            max_lp_supply: u64::MAX,
+            lp_escrow_amount: 500,
+            cpi_accounts: CpiAccounts::default(),
+            is_current_flash_swap: false,
+            liquidity_net_balance_limits: LiquidityNetBalanceLimits::default(),
+            seed_id: [3],
+            rate_oracle: RateOracle::default(),
+            curve_kind: CurveKind::Logit,
+            dynamic_fee: DynamicFee::default(),
+            yt_fee: None,
+        }
+    }
+
+    fn account_data(market: &MarketTwo) -> Vec<u8> {
+        let mut data = vec![];
+        market.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Account data of `market` as laid out at launch, before any field was appended
+    fn baseline_data(market: &MarketTwo) -> Vec<u8> {
+        let mut data = account_data(market);
+        // an empty oracle, the curve kind, a zero dynamic fee and no YT fee
+        data.truncate(data.len() - (RateOracle::size_of(0) + 1 + DynamicFee::SIZE_OF + 1));
+        data
+    }
+
+    fn assert_same_launch_fields(a: &MarketTwo, b: &MarketTwo) {
+        assert_eq!(a.vault, b.vault);
+        assert_eq!(a.sy_program, b.sy_program);
+        assert_eq!(a.financials.pt_balance, b.financials.pt_balance);
+        assert_eq!(a.financials.sy_balance, b.financials.sy_balance);
+        assert_eq!(a.lp_escrow_amount, b.lp_escrow_amount);
+        assert_eq!(a.seed_id, b.seed_id);
+    }
+
+    #[test]
+    fn baseline_sized_market_migrates_to_defaults() {
+        let m = market();
+        let new_size = MarketTwo::size_of(&m.cpi_accounts, 0, 0, 0);
+
+        // allocated at the size launch markets were given, with stale bytes past the old layout
+        let mut data = baseline_data(&m);
+        let launch_size = new_size - MarketTwo::MIGRATION_SIZE;
+        assert!(data.len() <= launch_size);
+        data.resize(launch_size, 0xff);
+
+        let migrated = MarketTwo::from_unmigrated(&data).unwrap();
+
+        assert_same_launch_fields(&migrated, &m);
+        assert_eq!(migrated.curve_kind, CurveKind::Logit);
+        assert!(!migrated.dynamic_fee.is_enabled());
+        assert_eq!(migrated.yt_fee, None);
+        assert_eq!(migrated.rate_oracle.capacity, 0);
+
+        // the migrated size holds the largest encoding of the appended fields
+        let mut migrated = migrated;
+        migrated.yt_fee = Some(YtFee {
+            ln_fee_rate_root: 0.002,
+            treasury_sy_bps: 1_000,
+        });
+        let mut account = vec![0; new_size];
+        migrated.try_serialize(&mut &mut account[..]).unwrap();
+
+        let loaded = MarketTwo::try_deserialize(&mut &account[..]).unwrap();
+        assert_same_launch_fields(&loaded, &m);
+        assert_eq!(loaded.yt_fee, migrated.yt_fee);
+    }
+
+    #[test]
+    fn market_with_only_the_oracle_appended_keeps_its_oracle() {
+        let m = market();
+        let mut oracle = RateOracle {
+            capacity: 4,
+            ..Default::default()
+        };
+        for t in 1..=6 {
+            oracle.observe(t * 100, 0.05 * t as f64);
+        }
+
+        // the market as it was once the oracle had been appended and grown
+        let mut data = baseline_data(&m);
+        data.extend(oracle.try_to_vec().unwrap());
+
+        // the fields appended after the oracle are added zeroed
+        data.extend([0; 1 + DynamicFee::SIZE_OF + 1 + YtFee::SIZE_OF]);
+        let loaded = MarketTwo::try_deserialize(&mut &data[..]).unwrap();
+
+        assert_same_launch_fields(&loaded, &m);
+        assert_eq!(
+            loaded.rate_oracle.try_to_vec().unwrap(),
+            oracle.try_to_vec().unwrap()
+        );
+        assert_eq!(loaded.curve_kind, CurveKind::Logit);
+        assert!(!loaded.dynamic_fee.is_enabled());
+        assert_eq!(loaded.yt_fee, None);
+    }
+
+    #[test]
+    fn oracle_grows_only_into_unreserved_space() {
+        let mut m = market();
+        let account_len = MarketTwo::size_of(&m.cpi_accounts, 0, 0, 0)
+            + 3 * RateObservation::SIZE_OF
+            + RateObservation::SIZE_OF / 2;
+
+        m.grow_rate_oracle(account_len);
+        assert_eq!(m.rate_oracle.capacity, 3);
+
+        // growing again into the same account finds no more room
+        m.grow_rate_oracle(account_len);
+        assert_eq!(m.rate_oracle.capacity, 3);
+
+        // a full oracle and a YT fee still fit
+        for t in 1..=3 {
+            m.rate_oracle.observe(t * 100, 0.05);
+        }
+        m.yt_fee = Some(YtFee::default());
+        let mut account = vec![0; account_len];
+        m.try_serialize(&mut &mut account[..]).unwrap();
+    }
+
+    #[test]
+    fn new_market_space_holds_a_full_default_oracle() {
+        let mut m = market();
+        m.rate_oracle = RateOracle::with_capacity(RateOracle::DEFAULT_CAPACITY);
+        // the space init_market_two allocates
+        let account_len =
+            MarketTwo::size_of(&m.cpi_accounts, 0, 0, RateOracle::DEFAULT_CAPACITY.into());
+
+        for t in 1..=u64::from(RateOracle::DEFAULT_CAPACITY) + 1 {
+            m.rate_oracle.observe(t * 100, 0.05);
+        }
+        assert_eq!(
+            m.rate_oracle.observations.len(),
+            usize::from(RateOracle::DEFAULT_CAPACITY)
+        );
+
+        m.yt_fee = Some(YtFee::default());
+        let mut account = vec![0; account_len];
+        m.try_serialize(&mut &mut account[..]).unwrap();
+
+        // nothing is left over for the oracle to grow into
+        m.grow_rate_oracle(account_len);
+        assert_eq!(m.rate_oracle.capacity, RateOracle::DEFAULT_CAPACITY);
+    }
+}
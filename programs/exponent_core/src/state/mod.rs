@@ -1,12 +1,20 @@
+pub mod compounding_config;
 pub mod cpi_common;
 pub mod lp_position;
+pub mod market_curve;
 pub mod market_two;
 pub mod personal_yield_tracker;
+pub mod position_delegate;
 pub mod vault;
+#[cfg(test)]
+mod vault_invariant_tests;
 pub mod yield_token_position;
 
+pub use compounding_config::*;
 pub use lp_position::*;
+pub use market_curve::*;
 pub use market_two::*;
 pub use personal_yield_tracker::*;
+pub use position_delegate::*;
 pub use vault::*;
 pub use yield_token_position::*;
@@ -1,3 +1,4 @@
+use crate::{error::ExponentCoreError, utils::math::pro_rata_staged};
 use anchor_lang::prelude::*;
 use precise_number::Number;
 
@@ -40,18 +41,28 @@ impl PersonalYieldTrackers {
     }
 
     /// Stage all earnings for emission trackers
-    fn earn_all(&mut self, emission_indexes: &EmissionIndexes, lp_balance_user: u64) {
+    fn earn_all(&mut self, emission_indexes: &EmissionIndexes, lp_balance_user: u64) -> Result<()> {
         for (pos, emission_index) in emission_indexes.iter().enumerate() {
             let emission_index = *emission_index;
             let tracker = &mut self.trackers[pos];
             let earned_emission = tracker.calc_earned_emissions(emission_index, lp_balance_user);
 
             tracker.last_seen_index = emission_index;
-            tracker.staged = tracker
-                .staged
-                .checked_add(earned_emission)
-                .expect("overflow on staging emission");
+            tracker.inc_staged(earned_emission)?;
         }
+
+        Ok(())
+    }
+
+    /// Move the share of staged earnings that goes with `amount` out of `balance` into `dst`
+    pub fn split_into(&mut self, dst: &mut Self, amount: u64, balance: u64) -> Result<()> {
+        for (src, dst) in self.trackers.iter_mut().zip(dst.trackers.iter_mut()) {
+            let staged = pro_rata_staged(src.staged, amount, balance);
+            src.dec_staged(staged)?;
+            dst.inc_staged(staged)?;
+        }
+
+        Ok(())
     }
 
     /// Public method for earning all emissions & ensuring there are sufficient trackers
@@ -59,9 +70,9 @@ impl PersonalYieldTrackers {
         &mut self,
         emission_indexes: &EmissionIndexes,
         lp_balance_user: u64,
-    ) {
+    ) -> Result<()> {
         self.ensure_trackers(emission_indexes);
-        self.earn_all(emission_indexes, lp_balance_user);
+        self.earn_all(emission_indexes, lp_balance_user)
     }
 }
 
@@ -78,10 +89,19 @@ impl PersonalYieldTracker {
         earned.floor_u64()
     }
 
-    pub fn dec_staged(&mut self, amount: u64) {
+    pub fn inc_staged(&mut self, amount: u64) -> Result<()> {
+        self.staged = self
+            .staged
+            .checked_add(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn dec_staged(&mut self, amount: u64) -> Result<()> {
         self.staged = self
             .staged
             .checked_sub(amount)
-            .expect("insufficient staged balance");
+            .ok_or(ExponentCoreError::AmountLargerThanStaged)?;
+        Ok(())
     }
 }
@@ -0,0 +1,136 @@
+use crate::error::ExponentCoreError;
+use anchor_lang::prelude::*;
+
+/// Operator of a YieldTokenPosition or LpPosition, which can claim the position's earnings on behalf of its owner
+///
+/// The operator has no withdrawal rights, and its claims are forced to pay into accounts owned by the beneficiary
+#[account]
+#[derive(Debug, Default)]
+pub struct PositionDelegate {
+    /// Position the operator acts on
+    pub position: Pubkey,
+
+    /// Address allowed to collect interest, emissions and farm rewards of the position
+    pub operator: Pubkey,
+
+    /// Owner of the token accounts the operator's claims pay into, or the position owner if unset
+    pub beneficiary: Option<Pubkey>,
+}
+
+impl PositionDelegate {
+    pub const SIZE_OF: usize =
+        // discriminator
+        8 +
+        // position
+        32 +
+        // operator
+        32 +
+        // beneficiary
+        1 + 32;
+
+    /// Find the delegate of `position` among `accounts`
+    pub fn find(position: Pubkey, accounts: &[AccountInfo]) -> Option<Self> {
+        accounts
+            .iter()
+            .filter(|a| a.owner == &crate::ID)
+            .filter_map(|a| {
+                let data = a.try_borrow_data().ok()?;
+                Self::try_deserialize(&mut &data[..]).ok()
+            })
+            .find(|d| d.position == position)
+    }
+
+    /// Verify that `signer` may claim the earnings of a position into a token account owned by `dst_owner`
+    ///
+    /// The position owner may claim into any account.
+    /// Anyone else must be the operator, with the position's delegate passed in `remaining_accounts`,
+    /// and must pay into an account owned by the beneficiary, or by the position owner if there is no beneficiary.
+    pub fn verify_claim(
+        position: Pubkey,
+        position_owner: Pubkey,
+        signer: Pubkey,
+        dst_owner: Pubkey,
+        remaining_accounts: &[AccountInfo],
+    ) -> Result<()> {
+        if signer == position_owner {
+            return Ok(());
+        }
+
+        let delegate = Self::find(position, remaining_accounts)
+            .ok_or(ExponentCoreError::NotPositionOperator)?;
+
+        require_keys_eq!(
+            delegate.operator,
+            signer,
+            ExponentCoreError::NotPositionOperator
+        );
+
+        require_keys_eq!(
+            dst_owner,
+            delegate.beneficiary.unwrap_or(position_owner),
+            ExponentCoreError::InvalidClaimDestination
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWNER: Pubkey = Pubkey::new_from_array([1; 32]);
+
+    /// Verify a claim on `position`, with `delegate` passed in the remaining accounts
+    fn verify(
+        delegate: &PositionDelegate,
+        position: Pubkey,
+        signer: Pubkey,
+        dst_owner: Pubkey,
+    ) -> Result<()> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![];
+        delegate.try_serialize(&mut data).unwrap();
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &crate::ID,
+            false,
+            0,
+        );
+
+        PositionDelegate::verify_claim(position, OWNER, signer, dst_owner, &[info])
+    }
+
+    #[test]
+    fn operator_claims_pay_into_beneficiary() {
+        let position = Pubkey::new_unique();
+        let operator = Pubkey::new_unique();
+        let beneficiary = Pubkey::new_unique();
+        let mut delegate = PositionDelegate {
+            position,
+            operator,
+            beneficiary: None,
+        };
+
+        // the owner claims into any account, and strangers cannot claim
+        assert!(verify(&delegate, position, OWNER, beneficiary).is_ok());
+        assert!(verify(&delegate, position, beneficiary, beneficiary).is_err());
+
+        // without a beneficiary, the operator pays into the owner's accounts
+        assert!(verify(&delegate, position, operator, OWNER).is_ok());
+        assert!(verify(&delegate, position, operator, operator).is_err());
+
+        delegate.beneficiary = Some(beneficiary);
+        assert!(verify(&delegate, position, operator, beneficiary).is_ok());
+        assert!(verify(&delegate, position, operator, OWNER).is_err());
+
+        // the delegate of another position does not count
+        let other_position = Pubkey::new_unique();
+        assert!(verify(&delegate, other_position, operator, beneficiary).is_err());
+    }
+}
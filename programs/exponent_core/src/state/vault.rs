@@ -11,6 +11,7 @@ pub const STATUS_CAN_DEPOSIT_YT: u8 = 0b0000_0100;
 pub const STATUS_CAN_WITHDRAW_YT: u8 = 0b0000_1000;
 pub const STATUS_CAN_COLLECT_INTEREST: u8 = 0b0001_0000;
 pub const STATUS_CAN_COLLECT_EMISSIONS: u8 = 0b0010_0000;
+pub const STATUS_CAN_EMERGENCY_WITHDRAW: u8 = 0b0100_0000;
 
 #[derive(Default, Debug)]
 #[account]
@@ -99,6 +100,54 @@ pub struct Vault {
     pub claim_limits: ClaimLimits,
 
     pub max_py_supply: u64,
+
+    // Fields below were appended after launch, in the order they were added
+    // Vaults created before them are extended by migrate_vault
+    /// Share of staged interest withheld by the settled emergency
+    /// Each unit of staged interest pays out `1 - interest_haircut` SY
+    pub interest_haircut: Number,
+
+    /// Final SY exchange rate when the emergency was settled, or zero if none was
+    /// Interest earned up to it takes the haircut, whether it is staged before or after the settlement
+    pub emergency_settlement_index: Number,
+
+    /// Seed ids of the markets created for the vault, one bit per seed id
+    pub linked_markets: [u8; 32],
+}
+
+/// Vault as laid out at launch, before any field was appended
+#[derive(AnchorDeserialize)]
+struct LaunchVault {
+    sy_program: Pubkey,
+    mint_sy: Pubkey,
+    mint_yt: Pubkey,
+    mint_pt: Pubkey,
+    escrow_yt: Pubkey,
+    escrow_sy: Pubkey,
+    yield_position: Pubkey,
+    address_lookup_table: Pubkey,
+    start_ts: u32,
+    duration: u32,
+    signer_seed: Pubkey,
+    authority: Pubkey,
+    signer_bump: [u8; 1],
+    last_seen_sy_exchange_rate: Number,
+    all_time_high_sy_exchange_rate: Number,
+    final_sy_exchange_rate: Number,
+    total_sy_in_escrow: u64,
+    sy_for_pt: u64,
+    pt_supply: u64,
+    treasury_sy: u64,
+    uncollected_sy: u64,
+    treasury_sy_token_account: Pubkey,
+    interest_bps_fee: u16,
+    min_op_size_strip: u64,
+    min_op_size_merge: u64,
+    status: u8,
+    emissions: Vec<EmissionInfo>,
+    cpi_accounts: CpiAccounts,
+    claim_limits: ClaimLimits,
+    max_py_supply: u64,
 }
 
 impl Vault {
@@ -141,8 +190,26 @@ impl Vault {
         Number::from_ratio(self.sy_for_pt.into(), self.pt_supply.into())
     }
 
-    pub fn collect_treasury_interest(&mut self, amount: u64) {
-        self.treasury_sy = self.treasury_sy.checked_sub(amount).unwrap();
+    /// The redemption rate for PT holders exiting without a fresh SY rate
+    /// PT backing is measured at the all time high, so PT does not take the SY owed to YT if the SY rate recovers
+    /// Outside emergency mode this is the same as `pt_redemption_rate`
+    pub fn pt_emergency_redemption_rate(&self) -> Number {
+        let sy_for_pt = sy_backing_for_pt(
+            self.all_time_high_sy_exchange_rate,
+            self.pt_supply,
+            self.active_sy(),
+        );
+
+        Number::from_ratio(sy_for_pt.into(), self.pt_supply.into())
+    }
+
+    pub fn collect_treasury_interest(&mut self, amount: u64) -> Result<()> {
+        self.treasury_sy = self
+            .treasury_sy
+            .checked_sub(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
     /// Static size of the struct in borsh, ignoring the dynamic CpiAccounts field
@@ -234,6 +301,15 @@ impl Vault {
         // max_py_supply
         8 +
 
+        // interest_haircut
+        Number::SIZEOF +
+
+        // emergency_settlement_index
+        Number::SIZEOF +
+
+        // linked_markets
+        32 +
+
         // emissions vec
         emissions_length * EmissionInfo::size_of() +
 
@@ -241,6 +317,63 @@ impl Vault {
         ClaimLimits::size_of()
     }
 
+    /// Bytes that vaults created before the appended fields are missing
+    pub const MIGRATION_SIZE: usize =
+        // interest_haircut
+        Number::SIZEOF +
+        // emergency_settlement_index
+        Number::SIZEOF +
+        // linked_markets
+        32;
+
+    /// Read a vault created before the appended fields, from account data that does not hold them
+    /// The appended fields take their defaults, whatever bytes follow the launch layout
+    pub fn from_unmigrated(data: &[u8]) -> Result<Self> {
+        require!(
+            data.starts_with(Self::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        let launch = LaunchVault::deserialize(&mut &data[Self::DISCRIMINATOR.len()..])
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+
+        Ok(Self {
+            sy_program: launch.sy_program,
+            mint_sy: launch.mint_sy,
+            mint_yt: launch.mint_yt,
+            mint_pt: launch.mint_pt,
+            escrow_yt: launch.escrow_yt,
+            escrow_sy: launch.escrow_sy,
+            yield_position: launch.yield_position,
+            address_lookup_table: launch.address_lookup_table,
+            start_ts: launch.start_ts,
+            duration: launch.duration,
+            signer_seed: launch.signer_seed,
+            authority: launch.authority,
+            signer_bump: launch.signer_bump,
+            last_seen_sy_exchange_rate: launch.last_seen_sy_exchange_rate,
+            all_time_high_sy_exchange_rate: launch.all_time_high_sy_exchange_rate,
+            final_sy_exchange_rate: launch.final_sy_exchange_rate,
+            total_sy_in_escrow: launch.total_sy_in_escrow,
+            sy_for_pt: launch.sy_for_pt,
+            pt_supply: launch.pt_supply,
+            treasury_sy: launch.treasury_sy,
+            uncollected_sy: launch.uncollected_sy,
+            treasury_sy_token_account: launch.treasury_sy_token_account,
+            interest_bps_fee: launch.interest_bps_fee,
+            min_op_size_strip: launch.min_op_size_strip,
+            min_op_size_merge: launch.min_op_size_merge,
+            status: launch.status,
+            emissions: launch.emissions,
+            cpi_accounts: launch.cpi_accounts,
+            claim_limits: launch.claim_limits,
+            max_py_supply: launch.max_py_supply,
+            interest_haircut: Number::ZERO,
+            emergency_settlement_index: Number::ZERO,
+            linked_markets: [0; 32],
+        })
+    }
+
     pub fn check_status_flags(&self, required_flags: u8) -> bool {
         self.status & required_flags == required_flags
     }
@@ -269,9 +402,12 @@ impl Vault {
     }
 
     /// Calculate the surplus SY appreciation to store in the lambo fund
+    /// Appreciation is measured from the all time high, since recovering from below it only restores PT backing
+    /// After a dip, PT is owed more SY than at the all time high, so measuring from the last seen rate
+    /// would move SY that backs PT into the treasury
     fn calc_sy_surplus(&self, cur_sy_state: &SyState) -> u64 {
         calc_sy_appreciation(
-            self.last_seen_sy_exchange_rate,
+            self.all_time_high_sy_exchange_rate,
             cur_sy_state.exchange_rate,
             self.active_sy(),
         )
@@ -289,11 +425,15 @@ impl Vault {
     }
 
     /// Increase the lambo fund for emissions
-    fn increase_emission_lambo_fund(&mut self, surpluses: Vec<u64>) {
+    fn increase_emission_lambo_fund(&mut self, surpluses: Vec<u64>) -> Result<()> {
         for (i, s) in surpluses.iter().enumerate() {
-            self.emissions[i].treasury_emission =
-                self.emissions[i].treasury_emission.checked_add(*s).unwrap()
+            self.emissions[i].treasury_emission = self.emissions[i]
+                .treasury_emission
+                .checked_add(*s)
+                .ok_or(ExponentCoreError::MathOverflow)?;
         }
+
+        Ok(())
     }
 
     pub fn is_min_op_size_strip(&self, amount: u64) -> bool {
@@ -305,7 +445,7 @@ impl Vault {
     }
 
     /// Calculate the amount of SY that is not set aside in the treasury nor set aside in the uncollected_sy for YT holders
-    fn active_sy(&self) -> u64 {
+    pub(crate) fn active_sy(&self) -> u64 {
         self.total_sy_in_escrow
             .checked_sub(self.treasury_sy)
             .and_then(|x| x.checked_sub(self.uncollected_sy))
@@ -325,22 +465,55 @@ impl Vault {
         self.sy_for_pt = self.sy_backing_pt();
     }
 
+    /// SY paid out for `staged` units of staged interest, after the haircut from settled emergencies
+    pub fn staged_interest_sy(&self, staged: u64) -> u64 {
+        if self.interest_haircut == Number::ZERO {
+            return staged;
+        }
+
+        (Number::from_natural_u64(staged) * (Number::ONE - self.interest_haircut)).floor_u64()
+    }
+
+    /// SY owed for `amount_sy` of interest earned up to the settled emergency, after its haircut
+    /// Rounds up, so the uncollected SY covers what the interest pays out once staged
+    pub fn settled_interest_sy(&self, amount_sy: u64) -> u64 {
+        if self.interest_haircut == Number::ZERO {
+            return amount_sy;
+        }
+
+        (Number::from_natural_u64(amount_sy) * (Number::ONE - self.interest_haircut)).ceil_u64()
+    }
+
+    /// Units of staged interest that pay out at most `amount_sy` SY, after the haircut from settled emergencies
+    pub fn sy_to_staged_interest(&self, amount_sy: u64) -> Result<u64> {
+        if self.interest_haircut == Number::ZERO {
+            return Ok(amount_sy);
+        }
+
+        let staged = (Number::from_natural_u64(amount_sy) / (Number::ONE - self.interest_haircut))
+            .floor_u128()
+            .try_into()
+            .map_err(|_| ExponentCoreError::MathOverflow)?;
+
+        Ok(staged)
+    }
+
     /// Public function to update the vault from a new SY state
     /// If the vault is expired, then update lambo fund from total SY held in escrow
     /// Update the last seen exchange rate, and all time high exchange rate
     /// Update the emission indexes and final indexes
     /// And then update the amount of SY backing for PT holders
-    pub fn update_from_sy_state(&mut self, sy_state: &SyState, now: u32) {
+    pub fn update_from_sy_state(&mut self, sy_state: &SyState, now: u32) -> Result<()> {
         // check if vault will collect treasury SY from post-maturity SY appreciation
         if self.can_collect_sy_lambo(now, sy_state) {
             let surplus_sy = self.calc_sy_surplus(&sy_state);
-            self.inc_treasury_sy(surplus_sy);
+            self.inc_treasury_sy(surplus_sy)?;
         }
 
         // check if vault will collect treasury emissions from post-maturity emissions
         if self.can_collect_emission_lambo(now) {
             let surplus_emissions = self.calc_emission_surpluses(&sy_state);
-            self.increase_emission_lambo_fund(surplus_emissions);
+            self.increase_emission_lambo_fund(surplus_emissions)?;
         }
 
         let cur_rate = sy_state.exchange_rate;
@@ -364,6 +537,49 @@ impl Vault {
 
         // After changing the rate, we need to update the amount of SY backing for PT holders
         self.set_sy_for_pt();
+
+        Ok(())
+    }
+
+    /// Bring maturity forward so the vault is expired from `now` on
+    /// The vault is updated from `sy_state` while it is still active, which fixes the final exchange rate and emission indexes
+    /// at the current SY state, as at natural maturity
+    pub fn force_expire(&mut self, sy_state: &SyState, now: u32) -> Result<()> {
+        require!(self.is_active(now), ExponentCoreError::VaultIsNotActive);
+
+        // expired means strictly past start_ts + duration, which no duration gives in the second the vault starts
+        require!(
+            now > self.start_ts,
+            ExponentCoreError::VaultCannotExpireAtStart
+        );
+
+        self.update_from_sy_state(sy_state, now)?;
+
+        self.duration = now - self.start_ts - 1;
+
+        Ok(())
+    }
+
+    /// Record a market created for the vault
+    pub fn link_market(&mut self, seed_id: u8) {
+        self.linked_markets[seed_id as usize / 8] |= 1 << (seed_id % 8);
+    }
+
+    pub fn is_market_linked(&self, seed_id: u8) -> bool {
+        self.linked_markets[seed_id as usize / 8] & (1 << (seed_id % 8)) != 0
+    }
+
+    /// Number of markets created for the vault
+    pub fn linked_market_count(&self) -> usize {
+        self.linked_markets
+            .iter()
+            .map(|b| b.count_ones() as usize)
+            .sum()
+    }
+
+    /// Timestamp after which the vault is expired
+    pub fn expiration_ts(&self) -> u32 {
+        self.start_ts + self.duration
     }
 
     pub fn add_emission(
@@ -383,62 +599,81 @@ impl Vault {
         ));
     }
 
-    pub fn inc_total_sy_in_escrow(&mut self, amount: u64) {
+    pub fn inc_total_sy_in_escrow(&mut self, amount: u64) -> Result<()> {
         self.total_sy_in_escrow = self
             .total_sy_in_escrow
             .checked_add(amount)
-            .expect("overflow adding SY to escrow for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
-    pub fn dec_total_sy_in_escrow(&mut self, amount: u64) {
+    pub fn dec_total_sy_in_escrow(&mut self, amount: u64) -> Result<()> {
         self.total_sy_in_escrow = self
             .total_sy_in_escrow
             .checked_sub(amount)
-            .expect("underflow subtracting SY from escrow for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
-    pub fn inc_pt_supply(&mut self, amount: u64) {
+    pub fn inc_pt_supply(&mut self, amount: u64) -> Result<()> {
         self.pt_supply = self
             .pt_supply
             .checked_add(amount)
-            .expect("overflow adding PT supply for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        require!(
+            self.pt_supply <= self.max_py_supply,
+            ExponentCoreError::MaxPySupplyExceeded
+        );
 
-        assert!(self.pt_supply <= self.max_py_supply);
+        Ok(())
     }
 
-    pub fn dec_pt_supply(&mut self, amount: u64) {
+    pub fn dec_pt_supply(&mut self, amount: u64) -> Result<()> {
         self.pt_supply = self
             .pt_supply
             .checked_sub(amount)
-            .expect("underflow subtracting PT supply for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
-    pub fn inc_treasury_sy(&mut self, amount: u64) {
+    pub fn inc_treasury_sy(&mut self, amount: u64) -> Result<()> {
         self.treasury_sy = self
             .treasury_sy
             .checked_add(amount)
-            .expect("overflow adding treasury SY for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
-    pub fn dec_treasury_sy(&mut self, amount: u64) {
+    pub fn dec_treasury_sy(&mut self, amount: u64) -> Result<()> {
         self.treasury_sy = self
             .treasury_sy
             .checked_sub(amount)
-            .expect("underflow subtracting treasury SY for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
-    pub fn inc_uncollected_sy(&mut self, amount: u64) {
+    pub fn inc_uncollected_sy(&mut self, amount: u64) -> Result<()> {
         self.uncollected_sy = self
             .uncollected_sy
             .checked_add(amount)
-            .expect("overflow adding uncollected SY for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 
-    pub fn dec_uncollected_sy(&mut self, amount: u64) {
+    pub fn dec_uncollected_sy(&mut self, amount: u64) -> Result<()> {
         self.uncollected_sy = self
             .uncollected_sy
             .checked_sub(amount)
-            .expect("underflow subtracting uncollected SY for vault");
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 }
 
@@ -535,10 +770,12 @@ impl EmissionInfo {
         }
     }
 
-    pub fn calculate_reward_amount(self, sy_amount: u64, current_index: &Number) -> u64 {
-        let delta = current_index.checked_sub(&self.last_seen_index).unwrap();
+    pub fn calculate_reward_amount(self, sy_amount: u64, current_index: &Number) -> Result<u64> {
+        let delta = current_index
+            .checked_sub(&self.last_seen_index)
+            .ok_or(ExponentCoreError::MathOverflow)?;
         let earned = delta * Number::from_natural_u64(sy_amount);
-        earned.floor_u64()
+        Ok(earned.floor_u64())
     }
 
     pub fn size_of() -> usize {
@@ -559,8 +796,13 @@ impl EmissionInfo {
     }
 
     /// Settle the staged interest
-    pub fn collect_treasury_emission(&mut self, amount: u64) {
-        self.treasury_emission = self.treasury_emission.checked_sub(amount).unwrap();
+    pub fn collect_treasury_emission(&mut self, amount: u64) -> Result<()> {
+        self.treasury_emission = self
+            .treasury_emission
+            .checked_sub(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+
+        Ok(())
     }
 }
 
@@ -579,3 +821,180 @@ fn sy_backing_for_pt(sy_exchange_rate: Number, pt_supply: u64, sy_in_escrow: u64
 
     sy
 }
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+
+    fn vault() -> Vault {
+        Vault {
+            sy_program: Pubkey::new_unique(),
+            mint_sy: Pubkey::new_unique(),
+            mint_pt: Pubkey::new_unique(),
+            yield_position: Pubkey::new_unique(),
+            start_ts: 1_700_000_000,
+            duration: 10_000_000,
+            signer_bump: [254],
+            last_seen_sy_exchange_rate: Number::from_ratio(11, 10),
+            all_time_high_sy_exchange_rate: Number::from_ratio(12, 10),
+            final_sy_exchange_rate: Number::from_ratio(11, 10),
+            total_sy_in_escrow: 1_000_000,
+            sy_for_pt: 800_000,
+            pt_supply: 900_000,
+            treasury_sy: 1_000,
+            uncollected_sy: 50_000,
+            interest_bps_fee: 500,
+            status: STATUS_CAN_STRIP | STATUS_CAN_MERGE,
+            emissions: vec![EmissionInfo::new(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Number::from_ratio(3, 2),
+                100,
+            )],
+            max_py_supply: u64::MAX,
+            ..Default::default()
+        }
+    }
+
+    fn assert_same_launch_fields(a: &Vault, b: &Vault) {
+        assert_eq!(a.sy_program, b.sy_program);
+        assert_eq!(a.yield_position, b.yield_position);
+        assert_eq!(
+            a.all_time_high_sy_exchange_rate,
+            b.all_time_high_sy_exchange_rate
+        );
+        assert_eq!(a.total_sy_in_escrow, b.total_sy_in_escrow);
+        assert_eq!(a.uncollected_sy, b.uncollected_sy);
+        assert_eq!(a.emissions.len(), b.emissions.len());
+        assert_eq!(a.max_py_supply, b.max_py_supply);
+    }
+
+    #[test]
+    fn baseline_sized_vault_migrates_to_defaults() {
+        let mut v = vault();
+        v.interest_haircut = Number::from_ratio(1, 10);
+        v.emergency_settlement_index = Number::from_ratio(11, 10);
+        v.link_market(0);
+        v.link_market(255);
+        let new_size = Vault::size_of_static(v.emissions.len()) + v.cpi_accounts.size_of();
+
+        // the vault as laid out at launch, which was allocated at exactly its size
+        let mut data = vec![];
+        v.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), new_size);
+        data.truncate(new_size - Vault::MIGRATION_SIZE);
+
+        assert!(Vault::try_deserialize(&mut &data[..]).is_err());
+
+        let migrated = Vault::from_unmigrated(&data).unwrap();
+        assert_same_launch_fields(&migrated, &v);
+        assert_eq!(migrated.interest_haircut, Number::ZERO);
+        assert_eq!(migrated.emergency_settlement_index, Number::ZERO);
+        assert_eq!(migrated.linked_market_count(), 0);
+
+        let mut account = vec![0; new_size];
+        migrated.try_serialize(&mut &mut account[..]).unwrap();
+
+        let loaded = Vault::try_deserialize(&mut &account[..]).unwrap();
+        assert_same_launch_fields(&loaded, &v);
+        assert_eq!(loaded.interest_haircut, Number::ZERO);
+    }
+
+    #[test]
+    fn linked_markets_are_recorded_by_seed_id() {
+        let mut v = vault();
+        for seed_id in [0, 7, 8, 255, 7] {
+            v.link_market(seed_id);
+        }
+
+        assert_eq!(v.linked_market_count(), 4);
+        assert!(v.is_market_linked(8));
+        assert!(v.is_market_linked(255));
+        assert!(!v.is_market_linked(1));
+        assert!(!v.is_market_linked(254));
+    }
+}
+
+#[cfg(test)]
+mod surplus_tests {
+    use super::*;
+
+    const START_TS: u32 = 1_000;
+    const DURATION: u32 = 10_000_000;
+
+    fn sy_state(exchange_rate: Number) -> SyState {
+        SyState {
+            exchange_rate,
+            emission_indexes: vec![],
+        }
+    }
+
+    /// A vault whose escrow exactly backs PT at the all time high when it expires
+    fn expired_vault(all_time_high: Number, pt_supply: u64) -> Vault {
+        let mut vault = Vault {
+            start_ts: START_TS,
+            duration: DURATION,
+            last_seen_sy_exchange_rate: all_time_high,
+            all_time_high_sy_exchange_rate: all_time_high,
+            final_sy_exchange_rate: all_time_high,
+            pt_supply,
+            total_sy_in_escrow: (Number::from_natural_u64(pt_supply) / all_time_high).floor_u64(),
+            ..Default::default()
+        };
+        vault.set_sy_for_pt();
+        vault
+    }
+
+    #[test]
+    fn recovery_from_a_dip_leaves_pt_backed() {
+        let all_time_high = Number::from_ratio(12, 10);
+        let dip = Number::from_ratio(10, 10);
+        let recovery = Number::from_ratio(13, 10);
+        let pt_supply = 1_200_000_000;
+        let expired = START_TS + DURATION + 1;
+
+        let mut vault = expired_vault(all_time_high, pt_supply);
+
+        // a dip collects nothing
+        vault.update_from_sy_state(&sy_state(dip), expired).unwrap();
+        assert_eq!(vault.treasury_sy, 0);
+
+        let active_sy = vault.active_sy();
+        let surplus_from_last_seen = calc_sy_appreciation(dip, recovery, active_sy);
+        let surplus_from_all_time_high = calc_sy_appreciation(all_time_high, recovery, active_sy);
+
+        vault
+            .update_from_sy_state(&sy_state(recovery), expired)
+            .unwrap();
+
+        // only the appreciation past the all time high is collected
+        let sy_owed_to_pt = (Number::from_natural_u64(pt_supply) / recovery).floor_u64();
+        assert_eq!(vault.treasury_sy, surplus_from_all_time_high);
+        assert_eq!(vault.sy_for_pt, sy_owed_to_pt);
+        assert!(vault.active_sy() >= sy_owed_to_pt);
+
+        // the last seen measure would have taken part of what PT is owed
+        assert!(active_sy - surplus_from_last_seen < sy_owed_to_pt);
+    }
+
+    #[test]
+    fn dip_without_a_new_high_collects_nothing() {
+        let all_time_high = Number::from_ratio(12, 10);
+        let expired = START_TS + DURATION + 1;
+
+        let mut vault = expired_vault(all_time_high, 1_200_000_000);
+
+        vault
+            .update_from_sy_state(&sy_state(Number::from_ratio(10, 10)), expired)
+            .unwrap();
+        vault
+            .update_from_sy_state(&sy_state(Number::from_ratio(11, 10)), expired)
+            .unwrap();
+        vault
+            .update_from_sy_state(&sy_state(all_time_high), expired)
+            .unwrap();
+
+        assert_eq!(vault.treasury_sy, 0);
+        assert_eq!(vault.active_sy(), vault.sy_for_pt);
+    }
+}
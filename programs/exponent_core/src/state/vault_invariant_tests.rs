@@ -0,0 +1,816 @@
+//! Randomized, model-based checks of vault accounting
+//!
+//! Each run drives a random sequence of vault operations through the same state functions the instruction handlers use,
+//! while tracking SY, PT and YT flows independently of the vault. The accounting invariants are checked after every step.
+
+use crate::{
+    error::ExponentCoreError,
+    instructions::{
+        handle_collect_interest, handle_compound_interest, handle_deposit_yt,
+        handle_emergency_withdraw_interest, handle_emergency_withdraw_pt, handle_merge,
+        handle_resolve_emergency, handle_settle_emergency, handle_split_yield_position,
+        handle_stage_yt_yield, handle_strip, handle_withdraw_yt,
+    },
+    state::{
+        ClaimLimits, Vault, YieldTokenPosition, MAX_KEEPER_TIP_BPS, STATUS_CAN_COLLECT_INTEREST,
+        STATUS_CAN_EMERGENCY_WITHDRAW, STATUS_CAN_MERGE, STATUS_CAN_STRIP,
+    },
+    utils::test_rng::XorShift,
+};
+use anchor_lang::prelude::*;
+use precise_number::Number;
+use sy_common::SyState;
+
+const RUNS: usize = 100;
+const STEPS: usize = 300;
+const USERS: usize = 4;
+const MAX_EMISSIONS: usize = 3;
+const START_TS: u32 = 1_000;
+const DURATION: u32 = 10_000_000;
+
+/// Exits that resolve_emergency turns off until the emergency is settled
+const EXIT_FLAGS: u8 =
+    STATUS_CAN_MERGE | STATUS_CAN_COLLECT_INTEREST | STATUS_CAN_EMERGENCY_WITHDRAW;
+
+/// How the SY exchange rate moves on each rate step
+#[derive(Clone, Copy)]
+struct RateProfile {
+    p_flat: f64,
+    p_fall: f64,
+    max_rise_bps: u64,
+    max_fall_bps: u64,
+}
+
+const RISING_RATES: RateProfile = RateProfile {
+    p_flat: 0.2,
+    p_fall: 0.0,
+    max_rise_bps: 50,
+    max_fall_bps: 0,
+};
+
+const VOLATILE_RATES: RateProfile = RateProfile {
+    p_flat: 0.2,
+    p_fall: 0.3,
+    max_rise_bps: 100,
+    max_fall_bps: 100,
+};
+
+#[derive(Clone, Default)]
+struct User {
+    /// PT held in the user's wallet
+    pt: u64,
+    /// YT held in the user's wallet (tracked by the vault's own yield position)
+    yt: u64,
+    /// The user's deposited YT
+    position: YieldTokenPosition,
+}
+
+#[derive(Clone)]
+struct Harness {
+    vault: Vault,
+    vault_position: YieldTokenPosition,
+    users: Vec<User>,
+    sy_state: SyState,
+    now: u32,
+    /// SY the vault should hold, from the SY that moved in and out
+    escrow: u64,
+    /// Total YT minted and not burned
+    yt_supply: u64,
+}
+
+impl Harness {
+    fn new(rng: &mut XorShift) -> Self {
+        let rate = Number::from_ratio(rng.range(1_000_000, 2_000_000).into(), 1_000_000);
+
+        let vault = Vault {
+            start_ts: START_TS,
+            duration: DURATION,
+            last_seen_sy_exchange_rate: rate,
+            all_time_high_sy_exchange_rate: rate,
+            final_sy_exchange_rate: rate,
+            interest_bps_fee: rng.range(0, 1_000) as u16,
+            status: STATUS_CAN_STRIP | EXIT_FLAGS,
+            max_py_supply: u64::MAX,
+            claim_limits: ClaimLimits {
+                max_claim_amount_per_window: u64::MAX / 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        Self {
+            vault,
+            vault_position: YieldTokenPosition::default(),
+            users: vec![User::default(); USERS],
+            sy_state: SyState {
+                exchange_rate: rate,
+                emission_indexes: vec![],
+            },
+            now: START_TS,
+            escrow: 0,
+            yt_supply: 0,
+        }
+    }
+
+    /// Apply an operation atomically, discarding all of its effects if it fails (like a reverted transaction)
+    fn apply(&mut self, op: impl FnOnce(&mut Self) -> Result<()>) {
+        let mut next = self.clone();
+        if op(&mut next).is_ok() {
+            *self = next;
+        }
+    }
+
+    /// Mirrors the update that every vault instruction does before its own logic
+    fn sync(&mut self) {
+        self.vault
+            .update_from_sy_state(&self.sy_state, self.now)
+            .unwrap();
+        self.vault_position
+            .earn_all_with_tracking(&mut self.vault)
+            .unwrap();
+        self.vault.set_sy_for_pt();
+    }
+
+    fn strip(&mut self, user: usize, amount_sy: u64) -> Result<()> {
+        require!(
+            self.vault.is_active(self.now),
+            ExponentCoreError::VaultIsNotActive
+        );
+
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_STRIP),
+            ExponentCoreError::StrippingDisabled
+        );
+
+        let amount_py = handle_strip(
+            &mut self.vault,
+            &mut self.vault_position,
+            self.now,
+            &self.sy_state,
+            amount_sy,
+        )?;
+
+        self.escrow += amount_sy;
+        self.yt_supply += amount_py;
+        self.users[user].pt += amount_py;
+        self.users[user].yt += amount_py;
+
+        Ok(())
+    }
+
+    fn merge(&mut self, user: usize, amount_py: u64) -> Result<()> {
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_MERGE),
+            ExponentCoreError::MergingDisabled
+        );
+
+        let is_active = self.vault.is_active(self.now);
+
+        let amount_sy = handle_merge(
+            &mut self.vault,
+            &mut self.vault_position,
+            self.now,
+            &self.sy_state,
+            amount_py,
+        )?;
+
+        let u = &mut self.users[user];
+        u.pt -= amount_py;
+        if is_active {
+            u.yt -= amount_py;
+            self.yt_supply -= amount_py;
+        }
+        self.escrow -= amount_sy;
+
+        Ok(())
+    }
+
+    fn deposit_yt(&mut self, user: usize, amount: u64) -> Result<()> {
+        require!(
+            self.vault.is_active(self.now),
+            ExponentCoreError::VaultExpired
+        );
+
+        let u = &mut self.users[user];
+        handle_deposit_yt(
+            &mut self.vault,
+            &mut self.vault_position,
+            &mut u.position,
+            &self.sy_state,
+            self.now,
+            amount,
+        )?;
+        u.yt -= amount;
+
+        Ok(())
+    }
+
+    fn withdraw_yt(&mut self, user: usize, amount: u64) -> Result<()> {
+        require!(
+            self.vault.is_active(self.now),
+            ExponentCoreError::VaultExpired
+        );
+
+        let u = &mut self.users[user];
+        handle_withdraw_yt(
+            &mut self.vault,
+            &mut self.vault_position,
+            &mut u.position,
+            &self.sy_state,
+            self.now,
+            amount,
+        )?;
+        u.yt += amount;
+
+        Ok(())
+    }
+
+    fn split_position(&mut self, src: usize, dst: usize, amount: u64) -> Result<()> {
+        if src == dst {
+            return Ok(());
+        }
+
+        let mut src_position = self.users[src].position.clone();
+        let mut dst_position = self.users[dst].position.clone();
+        let staged_before = src_position.interest.staged + dst_position.interest.staged;
+
+        handle_split_yield_position(
+            &mut self.vault,
+            &mut self.vault_position,
+            &mut src_position,
+            &mut dst_position,
+            &self.sy_state,
+            self.now,
+            amount,
+        )?;
+        // staging can only add to the interest, and splitting moves it without loss
+        assert!(src_position.interest.staged + dst_position.interest.staged >= staged_before);
+
+        self.users[src].position = src_position;
+        self.users[dst].position = dst_position;
+
+        Ok(())
+    }
+
+    fn stage_yield(&mut self, user: usize) -> Result<()> {
+        handle_stage_yt_yield(
+            &mut self.vault,
+            &mut self.vault_position,
+            &mut self.users[user].position,
+            &self.sy_state,
+            self.now,
+        )
+    }
+
+    /// Collect interest the way the instruction does: the amount is bounded by what was staged before earning
+    fn collect_interest(&mut self, user: usize, amount_staged: u64) -> Result<()> {
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_COLLECT_INTEREST),
+            ExponentCoreError::CollectingInterestDisabled
+        );
+
+        let position = &mut self.users[user].position;
+        position.earn_all_with_tracking(&mut self.vault)?;
+
+        let amount_sy = self.vault.staged_interest_sy(amount_staged);
+        let (user_sy, fee_sy) = handle_collect_interest(&mut self.vault, position, amount_staged)?;
+        assert_eq!(user_sy + fee_sy, amount_sy);
+        self.escrow -= amount_sy;
+
+        Ok(())
+    }
+
+    /// Compound interest the way the crank does: the treasury fee and keeper tip leave escrow, the rest is stripped into the position
+    fn compound_interest(&mut self, user: usize, keeper_tip_bps: u16) -> Result<()> {
+        require!(
+            self.vault.is_active(self.now),
+            ExponentCoreError::VaultIsNotActive
+        );
+
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_COLLECT_INTEREST),
+            ExponentCoreError::CollectingInterestDisabled
+        );
+
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_STRIP),
+            ExponentCoreError::StrippingDisabled
+        );
+
+        let u = &mut self.users[user];
+        let amounts = handle_compound_interest(
+            &mut self.vault,
+            &mut self.vault_position,
+            &mut u.position,
+            self.now,
+            &self.sy_state,
+            keeper_tip_bps,
+        )?;
+        assert_eq!(
+            amounts.amount_to_treasury + amounts.amount_to_keeper + amounts.amount_sy_stripped,
+            amounts.amount_sy_collected
+        );
+        assert_eq!(u.position.interest.staged, 0);
+
+        u.pt += amounts.amount_py;
+        self.yt_supply += amounts.amount_py;
+        self.escrow -= amounts.amount_to_treasury + amounts.amount_to_keeper;
+
+        Ok(())
+    }
+
+    /// Emergency withdrawals do not see the SY state, so the vault is not synced first
+    fn emergency_withdraw_pt(&mut self, user: usize, amount_pt: u64) -> Result<()> {
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_EMERGENCY_WITHDRAW),
+            ExponentCoreError::EmergencyWithdrawDisabled
+        );
+
+        let is_active = self.vault.is_active(self.now);
+        let amount_sy = handle_emergency_withdraw_pt(
+            &mut self.vault,
+            &mut self.vault_position,
+            self.now,
+            amount_pt,
+        )?;
+
+        self.escrow -= amount_sy;
+        self.users[user].pt -= amount_pt;
+        if is_active {
+            self.users[user].yt -= amount_pt;
+            self.yt_supply -= amount_pt;
+        }
+
+        Ok(())
+    }
+
+    fn emergency_withdraw_interest(&mut self, user: usize, amount_staged: u64) -> Result<()> {
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_EMERGENCY_WITHDRAW),
+            ExponentCoreError::EmergencyWithdrawDisabled
+        );
+
+        let amount_sy = self.vault.staged_interest_sy(amount_staged);
+        let (user_sy, fee_sy) = handle_emergency_withdraw_interest(
+            &mut self.vault,
+            &mut self.users[user].position,
+            amount_staged,
+            self.now,
+        )?;
+        assert_eq!(user_sy + fee_sy, amount_sy);
+        self.escrow -= amount_sy;
+
+        Ok(())
+    }
+
+    fn collect_treasury_interest(&mut self, amount_sy: u64) -> Result<()> {
+        self.vault.collect_treasury_interest(amount_sy)?;
+        self.vault.dec_total_sy_in_escrow(amount_sy)?;
+        self.escrow -= amount_sy;
+
+        Ok(())
+    }
+
+    fn collect_vault_position_interest(&mut self, amount_staged: u64) -> Result<()> {
+        require!(
+            self.vault.check_status_flags(STATUS_CAN_COLLECT_INTEREST),
+            ExponentCoreError::CollectingInterestDisabled
+        );
+
+        let amount_sy = self.vault.staged_interest_sy(amount_staged);
+        self.vault_position.interest.collect(amount_staged)?;
+        self.vault.dec_uncollected_sy(amount_sy)?;
+        self.vault.dec_total_sy_in_escrow(amount_sy)?;
+        self.escrow -= amount_sy;
+
+        Ok(())
+    }
+
+    fn resolve_emergency(&mut self) -> Result<()> {
+        handle_resolve_emergency(
+            &mut self.vault,
+            &mut self.vault_position,
+            &self.sy_state,
+            self.now,
+        )?;
+
+        Ok(())
+    }
+
+    /// Settle a resolved emergency and re-enable exits, whether or not every position has staged
+    /// Stripping stays off, since PT may be under-backed
+    fn settle_emergency(&mut self) -> Result<()> {
+        handle_settle_emergency(
+            &mut self.vault,
+            &mut self.vault_position,
+            &self.sy_state,
+            self.now,
+        )?;
+        self.vault.status |= EXIT_FLAGS;
+
+        Ok(())
+    }
+
+    fn force_expire(&mut self) -> Result<()> {
+        self.vault.force_expire(&self.sy_state, self.now)
+    }
+
+    fn move_rate(&mut self, rng: &mut XorShift, profile: RateProfile) {
+        let roll = rng.unit();
+        let bps = if roll < profile.p_flat {
+            10_000
+        } else if roll < profile.p_flat + profile.p_fall {
+            10_000 - rng.range(1, profile.max_fall_bps + 1)
+        } else {
+            10_000 + rng.range(1, profile.max_rise_bps + 1)
+        };
+
+        self.sy_state.exchange_rate =
+            self.sy_state.exchange_rate * Number::from_ratio(bps.into(), 10_000);
+
+        for index in self.sy_state.emission_indexes.iter_mut() {
+            *index += Number::from_ratio(rng.range(0, 1_000).into(), 1_000_000);
+        }
+
+        self.sync();
+    }
+
+    fn add_emission(&mut self, rng: &mut XorShift) {
+        if self.vault.emissions.len() >= MAX_EMISSIONS {
+            return;
+        }
+
+        // emission indexes start from zero on the SY program
+        self.sy_state.emission_indexes.push(Number::ZERO);
+        self.vault.add_emission(
+            Pubkey::default(),
+            &self.sy_state,
+            Pubkey::default(),
+            rng.range(0, 1_000) as u16,
+        );
+    }
+
+    fn step(&mut self, rng: &mut XorShift, profile: RateProfile) {
+        let user = rng.range(0, USERS as u64) as usize;
+        let u = &self.users[user];
+
+        match rng.range(0, 100) {
+            0..=19 => {
+                let amount_sy = rng.range(1, 1_000_000_000_000);
+                self.apply(|h| h.strip(user, amount_sy));
+            }
+            20..=34 => {
+                let max = if self.vault.is_active(self.now) {
+                    u.pt.min(u.yt)
+                } else {
+                    u.pt
+                };
+                let amount_py = portion(rng, max);
+                self.apply(|h| h.merge(user, amount_py));
+            }
+            35..=49 => {
+                let amount = portion(rng, u.yt);
+                self.apply(|h| h.deposit_yt(user, amount));
+            }
+            50..=59 => {
+                let amount = portion(rng, u.position.yt_balance);
+                self.apply(|h| h.withdraw_yt(user, amount));
+            }
+            60..=65 => {
+                let amount_staged = portion(rng, u.position.interest.staged);
+                self.apply(|h| h.collect_interest(user, amount_staged));
+            }
+            66..=67 => {
+                let dst = rng.range(0, USERS as u64) as usize;
+                let amount = portion(rng, u.position.yt_balance);
+                self.apply(|h| h.split_position(user, dst, amount));
+            }
+            68..=70 => self.apply(|h| h.stage_yield(user)),
+            71..=73 => {
+                let keeper_tip_bps = rng.range(0, MAX_KEEPER_TIP_BPS as u64 + 1) as u16;
+                self.apply(|h| h.compound_interest(user, keeper_tip_bps));
+            }
+            74..=75 => {
+                let amount_sy = portion(rng, self.vault.treasury_sy);
+                self.apply(|h| h.collect_treasury_interest(amount_sy));
+            }
+            76..=77 => {
+                let amount_staged = portion(rng, self.vault_position.interest.staged);
+                self.apply(|h| h.collect_vault_position_interest(amount_staged));
+            }
+            78..=89 => self.move_rate(rng, profile),
+            90 if rng.chance(0.5) => {
+                let max = if self.vault.is_active(self.now) {
+                    u.pt.min(u.yt)
+                } else {
+                    u.pt
+                };
+                let amount_pt = portion(rng, max);
+                self.apply(|h| h.emergency_withdraw_pt(user, amount_pt));
+            }
+            90 => {
+                let amount_staged = portion(rng, u.position.interest.staged);
+                self.apply(|h| h.emergency_withdraw_interest(user, amount_staged));
+            }
+            91 if rng.chance(0.5) => self.apply(|h| h.resolve_emergency()),
+            91 => self.apply(|h| h.settle_emergency()),
+            92..=97 => self.now += rng.range(1, (DURATION / 50).into()) as u32,
+            98 if rng.chance(0.5) => self.apply(|h| h.force_expire()),
+            98 => self.now = self.now.max(START_TS + DURATION + 1),
+            _ => self.add_emission(rng),
+        }
+    }
+
+    /// Expire the vault, pay out all interest and redeem all PT
+    fn settle(&mut self) {
+        // rates recover past the all time high, so the vault leaves emergency mode
+        if self.vault.is_in_emergency_mode() {
+            self.sy_state.exchange_rate =
+                self.vault.all_time_high_sy_exchange_rate * Number::from_ratio(10_001, 10_000);
+        }
+        self.now = self.now.max(START_TS + DURATION + 1);
+        self.sync();
+        self.check_invariants();
+
+        // a resolved emergency is settled before anyone exits, and only the first one shares its loss with YT
+        if !self.vault.check_status_flags(EXIT_FLAGS) {
+            if self.vault.emergency_settlement_index == Number::ZERO {
+                self.settle_emergency().unwrap();
+            } else {
+                self.vault.status |= EXIT_FLAGS;
+            }
+            self.check_invariants();
+        }
+
+        for user in 0..USERS {
+            self.stage_yield(user).unwrap();
+            let staged = self.users[user].position.interest.staged;
+            self.collect_interest(user, staged).unwrap();
+            let pt = self.users[user].pt;
+            self.merge(user, pt).unwrap();
+            self.check_invariants();
+        }
+
+        let staged = self.vault_position.interest.staged;
+        self.collect_vault_position_interest(staged).unwrap();
+        self.check_invariants();
+
+        let v = &self.vault;
+        assert_eq!(v.pt_supply, 0);
+        assert_eq!(v.sy_for_pt, 0);
+        if v.interest_haircut == Number::ZERO {
+            assert_eq!(v.uncollected_sy, 0);
+        } else {
+            // with a haircut, each staging and collection rounds in the vault's favour by less than one
+            // a step stages at most every position once and collects once
+            let max_dust = ((STEPS + USERS) * (USERS + 2)) as u64;
+            assert!(v.uncollected_sy <= max_dust);
+        }
+        assert!(v.total_sy_in_escrow >= v.treasury_sy);
+    }
+
+    fn check_invariants(&self) {
+        let v = &self.vault;
+
+        assert!(
+            v.sy_balance_invariant(),
+            "escrow {} does not cover sy_for_pt {} + treasury {} + uncollected {}",
+            v.total_sy_in_escrow,
+            v.sy_for_pt,
+            v.treasury_sy,
+            v.uncollected_sy
+        );
+        assert_eq!(
+            v.total_sy_in_escrow, self.escrow,
+            "escrow drifted from SY flows"
+        );
+
+        // uncollected SY covers the interest staged across all positions, and is exactly that without a haircut
+        let staged = self.vault_position.interest.staged
+            + self
+                .users
+                .iter()
+                .map(|u| u.position.interest.staged)
+                .sum::<u64>();
+        let staged_sy = v.staged_interest_sy(staged);
+        if v.interest_haircut == Number::ZERO {
+            assert_eq!(
+                v.uncollected_sy, staged_sy,
+                "uncollected SY drifted from staged interest"
+            );
+        } else {
+            assert!(
+                v.uncollected_sy >= staged_sy,
+                "uncollected SY {} does not cover staged interest {}",
+                v.uncollected_sy,
+                staged_sy
+            );
+        }
+
+        // PT backing never exceeds the PT value or the unreserved SY
+        // It is full outside emergency mode, unless an emergency was resolved, which leaves stripping off
+        // Number keeps 12 decimal places, so interest accrual can over-credit YT by a few units on large supplies
+        let active_sy = v.total_sy_in_escrow - v.treasury_sy - v.uncollected_sy;
+        let pt_value_sy =
+            (Number::from_natural_u64(v.pt_supply) / v.last_seen_sy_exchange_rate).floor_u64();
+        assert!(v.sy_for_pt <= active_sy);
+        assert!(v.sy_for_pt <= pt_value_sy);
+        if !v.is_in_emergency_mode() && v.check_status_flags(STATUS_CAN_STRIP) {
+            let dust = v.pt_supply / 1_000_000_000_000 + 2;
+            assert!(
+                v.sy_for_pt + dust >= pt_value_sy,
+                "PT is under-backed outside emergency mode: {} < {}",
+                v.sy_for_pt,
+                pt_value_sy
+            );
+        }
+
+        // PT and YT supply is conserved
+        let wallet_pt = self.users.iter().map(|u| u.pt).sum::<u64>();
+        let wallet_yt = self.users.iter().map(|u| u.yt).sum::<u64>();
+        let deposited_yt = self
+            .users
+            .iter()
+            .map(|u| u.position.yt_balance)
+            .sum::<u64>();
+        assert_eq!(wallet_pt, v.pt_supply);
+        assert_eq!(self.vault_position.yt_balance, wallet_yt);
+        assert_eq!(wallet_yt + deposited_yt, self.yt_supply);
+        if v.is_active(self.now) {
+            assert_eq!(self.yt_supply, v.pt_supply);
+        }
+    }
+}
+
+/// Some amount in [1, max] (or zero if max is zero), biased towards the full amount
+fn portion(rng: &mut XorShift, max: u64) -> u64 {
+    if max == 0 || rng.chance(0.2) {
+        max
+    } else {
+        rng.range(1, max + 1)
+    }
+}
+
+fn run(seed: u64, profile: RateProfile) {
+    let mut rng = XorShift(seed);
+
+    for _ in 0..RUNS {
+        let mut harness = Harness::new(&mut rng);
+        for _ in 0..STEPS {
+            harness.step(&mut rng, profile);
+            harness.check_invariants();
+        }
+        harness.settle();
+    }
+}
+
+#[test]
+fn invariants_hold_with_rising_rates() {
+    run(0x2545_f491_4f6c_dd1d, RISING_RATES);
+}
+
+#[test]
+fn invariants_hold_with_volatile_rates() {
+    run(0x9e37_79b9_7f4a_7c15, VOLATILE_RATES);
+}
+
+/// After a loss is resolved, no one exits until YT has staged its interest and the loss is settled
+/// Settling then cuts PT backing and uncollected interest by the same share of their value
+#[test]
+fn settled_emergency_shares_the_loss_pro_rata() {
+    let mut h = Harness::new(&mut XorShift(0x5851_f42d_4c95_7f2d));
+    let rate = h.sy_state.exchange_rate;
+
+    h.strip(0, 1_000_000_000).unwrap();
+    h.strip(1, 1_000_000_000).unwrap();
+    let yt = h.users[0].yt;
+    h.deposit_yt(0, yt).unwrap();
+
+    // the wallet YT stages its interest at the peak, the deposited YT has not staged any when the rate falls
+    h.sy_state.exchange_rate = rate * Number::from_ratio(12, 10);
+    h.sync();
+    h.sy_state.exchange_rate = rate * Number::from_ratio(11, 10);
+    h.sync();
+    assert!(h.vault.is_in_emergency_mode());
+
+    h.resolve_emergency().unwrap();
+    h.check_invariants();
+
+    // PT and staged interest cannot leave ahead of the interest still to be staged
+    assert!(h.clone().merge(1, 1).is_err());
+    assert!(h.clone().emergency_withdraw_pt(1, 1).is_err());
+    assert!(h.clone().collect_vault_position_interest(1).is_err());
+
+    for user in 0..USERS {
+        h.stage_yield(user).unwrap();
+    }
+    assert!(h.users[0].position.interest.staged > 0);
+    assert!(h.clone().merge(1, 1).is_err());
+
+    let available_sy = h.vault.total_sy_in_escrow - h.vault.treasury_sy;
+    let pt_claim_sy = (Number::from_natural_u64(h.vault.pt_supply)
+        / h.vault.last_seen_sy_exchange_rate)
+        .floor_u64();
+    let uncollected_claim_sy = h.vault.uncollected_sy;
+    let staged = h.users[0].position.interest.staged;
+
+    let share_kept = available_sy as f64 / (pt_claim_sy + uncollected_claim_sy) as f64;
+    assert!(share_kept < 1.0);
+
+    h.settle_emergency().unwrap();
+    h.check_invariants();
+
+    // each claim keeps its share, to within the rounding of a unit
+    let kept = |claim_sy: u64| claim_sy as f64 * share_kept;
+    assert!((kept(pt_claim_sy) - h.vault.sy_for_pt as f64).abs() <= 1.0);
+    assert!((kept(uncollected_claim_sy) - h.vault.uncollected_sy as f64).abs() <= 1.0);
+
+    // staged interest pays out its share, and PT exits again
+    let escrow = h.escrow;
+    h.collect_interest(0, staged).unwrap();
+    let paid_sy = escrow - h.escrow;
+    assert!((kept(staged) - paid_sy as f64).abs() <= 1.0);
+
+    let pt = h.users[1].pt.min(h.users[1].yt);
+    h.merge(1, pt).unwrap();
+    h.check_invariants();
+
+    h.settle();
+}
+
+/// Interest earned before the settlement takes the same haircut whether it is staged before or after it
+/// A vault settles only one emergency, so the haircut does not compound
+#[test]
+fn interest_staged_after_settlement_takes_the_same_haircut() {
+    let mut h = Harness::new(&mut XorShift(0x2127_599b_f432_5c37));
+    let rate = h.sy_state.exchange_rate;
+
+    // the wallet YT, which stages at the peak, holds most of the supply, so the loss outweighs the unstaged interest
+    h.strip(0, 1_000_000_000).unwrap();
+    h.strip(1, 10_000_000_000).unwrap();
+    h.strip(2, 1_000_000_000).unwrap();
+    for user in [0, 2] {
+        let yt = h.users[user].yt;
+        h.deposit_yt(user, yt).unwrap();
+    }
+
+    h.sy_state.exchange_rate = rate * Number::from_ratio(12, 10);
+    h.sync();
+    h.sy_state.exchange_rate = rate * Number::from_ratio(11, 10);
+    h.sync();
+    h.resolve_emergency().unwrap();
+
+    // only one of the two identical positions stages before the loss is settled
+    h.stage_yield(0).unwrap();
+    h.settle_emergency().unwrap();
+    h.check_invariants();
+    assert!(h.vault.interest_haircut > Number::ZERO);
+
+    h.stage_yield(2).unwrap();
+    h.check_invariants();
+
+    let staged = h.users[0].position.interest.staged;
+    assert_eq!(h.users[2].position.interest.staged, staged);
+
+    let mut paid_sy = vec![];
+    for user in [0, 2] {
+        let escrow = h.escrow;
+        h.collect_interest(user, staged).unwrap();
+        paid_sy.push(escrow - h.escrow);
+    }
+    assert_eq!(paid_sy[0], paid_sy[1]);
+    assert!(paid_sy[0] < staged);
+
+    assert_eq!(
+        h.settle_emergency().unwrap_err(),
+        ExponentCoreError::EmergencyAlreadySettled.into()
+    );
+
+    h.settle();
+}
+
+/// Forced expiry fixes the final state at the SY state it is given, not at the last one the vault saw
+#[test]
+fn force_expire_takes_the_current_sy_state() {
+    let mut h = Harness::new(&mut XorShift(0xd1b5_4a32_d192_ed03));
+    h.now += 100;
+    h.sy_state.exchange_rate = h.sy_state.exchange_rate * Number::from_ratio(11, 10);
+
+    h.force_expire().unwrap();
+
+    assert!(h.vault.is_expired(h.now));
+    assert_eq!(h.vault.expiration_ts(), h.now - 1);
+    assert_eq!(h.vault.final_sy_exchange_rate, h.sy_state.exchange_rate);
+}
+
+/// A vault cannot be expired in the second it starts, since expired means strictly past the expiration
+#[test]
+fn force_expire_at_start_has_its_own_error() {
+    let mut h = Harness::new(&mut XorShift(0xd1b5_4a32_d192_ed03));
+    assert_eq!(h.now, START_TS);
+
+    assert_eq!(
+        h.force_expire().unwrap_err(),
+        ExponentCoreError::VaultCannotExpireAtStart.into()
+    );
+}
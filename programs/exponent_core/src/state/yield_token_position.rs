@@ -1,4 +1,8 @@
-use crate::{utils::math::calc_share_value, Vault};
+use crate::{
+    error::ExponentCoreError,
+    utils::math::{calc_share_value, pro_rata_staged},
+    Vault,
+};
 use anchor_lang::prelude::*;
 use precise_number::Number;
 
@@ -31,6 +35,7 @@ pub struct YieldTokenTracker {
     pub last_seen_index: Number,
 
     /// Staged tokens that may be withdrawn
+    /// For interest, each unit pays out `Vault::staged_interest_sy`, which is one SY unless an emergency was settled with a loss
     pub staged: u64,
 }
 
@@ -56,18 +61,53 @@ impl YieldTokenPosition {
         4 + (num_emissions * YieldTokenTracker::size_of())
     }
 
-    pub fn inc_yt_balance(&mut self, amount: u64) {
-        self.yt_balance = self.yt_balance.checked_add(amount).unwrap();
+    pub fn inc_yt_balance(&mut self, amount: u64) -> Result<()> {
+        self.yt_balance = self
+            .yt_balance
+            .checked_add(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        Ok(())
     }
 
-    pub fn dec_yt_balance(&mut self, amount: u64) {
-        self.yt_balance = self.yt_balance.checked_sub(amount).unwrap();
+    /// A position is empty once it holds no YT and no staged interest or emissions
+    pub fn is_empty(&self) -> bool {
+        self.yt_balance == 0
+            && self.interest.staged == 0
+            && self.emissions.iter().all(|e| e.staged == 0)
     }
 
-    fn earn_sy_interest(&mut self, vault: &mut Vault) {
+    /// Move `amount` YT, and the same share of staged interest and emissions, into `dst`
+    /// Both positions must be staged to the vault's indexes first, so that they earn alike from here on
+    pub fn split_into(&mut self, dst: &mut YieldTokenPosition, amount: u64) -> Result<()> {
+        let balance = self.yt_balance;
+        self.dec_yt_balance(amount)?;
+        dst.inc_yt_balance(amount)?;
+
+        let interest = pro_rata_staged(self.interest.staged, amount, balance);
+        self.interest.dec_staged(interest)?;
+        dst.interest.inc_staged(interest)?;
+
+        for (src, dst) in self.emissions.iter_mut().zip(dst.emissions.iter_mut()) {
+            let emission = pro_rata_staged(src.staged, amount, balance);
+            src.dec_staged(emission)?;
+            dst.inc_staged(emission)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn dec_yt_balance(&mut self, amount: u64) -> Result<()> {
+        self.yt_balance = self
+            .yt_balance
+            .checked_sub(amount)
+            .ok_or(ExponentCoreError::InsufficientYtBalance)?;
+        Ok(())
+    }
+
+    fn earn_sy_interest(&mut self, vault: &mut Vault) -> Result<()> {
         // If vault is in emergency mode, do nothing
         if vault.is_in_emergency_mode() {
-            return;
+            return Ok(());
         }
 
         // use the final_sy_exchange_rate on the vault, since this is the last snapshot
@@ -78,31 +118,45 @@ impl YieldTokenPosition {
         // according to the final rate of the vault
         let sy_earned = self.calc_earned_sy(rate);
 
+        // the part earned up to a settled emergency shares its loss, however late it is staged
+        // with no settled emergency, the index is zero and nothing is earned up to it
+        let sy_settled = self.calc_earned_sy(rate.min(vault.emergency_settlement_index));
+        let sy_unsettled = sy_earned.saturating_sub(sy_settled);
+
         // if the current rate is higher than the final rate, scale down the earned SY
-        let sy_earned = scale_sy_to_current_rate(sy_earned, rate, vault.last_seen_sy_exchange_rate);
+        let current_rate = vault.last_seen_sy_exchange_rate;
+        let sy_settled = scale_sy_to_current_rate(sy_settled, rate, current_rate);
+        let sy_unsettled = scale_sy_to_current_rate(sy_unsettled, rate, current_rate);
 
-        // stage the earned SY
-        self.interest.inc_staged(sy_earned);
+        // stage the earned SY, in units of staged interest that pay out the SY less any haircut
+        // settled interest is staged one unit per SY, so it takes the haircut, and the rest is grossed up to escape it
+        let staged = sy_settled
+            .checked_add(vault.sy_to_staged_interest(sy_unsettled)?)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        self.interest.inc_staged(staged)?;
 
-        // update the vault's uncollected SY
-        vault.inc_uncollected_sy(sy_earned);
+        // update the vault's uncollected SY with what the staged interest pays out
+        vault.inc_uncollected_sy(vault.settled_interest_sy(sy_settled))?;
+        vault.inc_uncollected_sy(sy_unsettled)?;
 
         // update the last seen index
         self.interest.last_seen_index = rate;
+
+        Ok(())
     }
 
     /// Stage earned SY and rewards
-    fn earn_all(&mut self, vault: &mut Vault) {
-        self.earn_sy_interest(vault);
+    fn earn_all(&mut self, vault: &mut Vault) -> Result<()> {
+        self.earn_sy_interest(vault)?;
 
-        self.earn_emissions(vault);
+        self.earn_emissions(vault)
     }
 
     fn total_sy_balance(&self, vault: &Vault) -> u64 {
         self.interest.staged + py_to_sy(vault.final_sy_exchange_rate, self.yt_balance)
     }
 
-    fn earn_emissions(&mut self, vault: &Vault) {
+    fn earn_emissions(&mut self, vault: &Vault) -> Result<()> {
 
         let sy_balance = self.total_sy_balance(vault);
 
@@ -111,9 +165,11 @@ impl YieldTokenPosition {
             let earned_emission =
                 calc_share_value(e.last_seen_index, emission.final_index, sy_balance);
 
-            e.inc_staged(earned_emission);
+            e.inc_staged(earned_emission)?;
             e.last_seen_index = emission.final_index;
         }
+
+        Ok(())
     }
 
     /// Ensure that there is 1 tracker per emission index
@@ -129,7 +185,7 @@ impl YieldTokenPosition {
     }
 
     /// Main public function for updating the staged earnings for the position
-    pub fn earn_all_with_tracking(&mut self, vault: &mut Vault) {
+    pub fn earn_all_with_tracking(&mut self, vault: &mut Vault) -> Result<()> {
         self.ensure_trackers(vault);
 
         self.earn_all(vault)
@@ -171,8 +227,8 @@ impl YieldTokenTracker {
     }
 
     /// Settle the staged interest
-    pub fn collect(&mut self, amount: u64) {
-        self.staged = self.staged.checked_sub(amount).unwrap();
+    pub fn collect(&mut self, amount: u64) -> Result<()> {
+        self.dec_staged(amount)
     }
 
     pub fn new(last_seen_index: Number, staged: u64) -> Self {
@@ -182,12 +238,20 @@ impl YieldTokenTracker {
         }
     }
 
-    pub fn inc_staged(&mut self, amount: u64) {
-        self.staged = self.staged.checked_add(amount).unwrap();
+    pub fn inc_staged(&mut self, amount: u64) -> Result<()> {
+        self.staged = self
+            .staged
+            .checked_add(amount)
+            .ok_or(ExponentCoreError::MathOverflow)?;
+        Ok(())
     }
 
-    pub fn dec_staged(&mut self, amount: u64) {
-        self.staged = self.staged.checked_sub(amount).unwrap();
+    pub fn dec_staged(&mut self, amount: u64) -> Result<()> {
+        self.staged = self
+            .staged
+            .checked_sub(amount)
+            .ok_or(ExponentCoreError::AmountLargerThanStaged)?;
+        Ok(())
     }
 }
 
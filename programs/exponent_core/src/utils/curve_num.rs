@@ -0,0 +1,100 @@
+use dec_num::DNum;
+use exponent_time_curve::num::Num;
+use precise_number::Number;
+
+/// Number type used for the market's curve math
+/// f64 by default, or the fixed-point DNum with the `decimal-curve` feature
+#[cfg(not(feature = "decimal-curve"))]
+pub type MarketNum = f64;
+
+/// Number type used for the market's curve math
+/// f64 by default, or the fixed-point DNum with the `decimal-curve` feature
+#[cfg(feature = "decimal-curve")]
+pub type MarketNum = DNum;
+
+/// Conversions and rounding the market needs on top of the curve's number type
+pub trait CurveNum: Num {
+    /// Load a curve parameter stored on the market as f64
+    fn from_f64(value: f64) -> Self;
+
+    /// Store a curve value on the market as f64
+    fn into_f64(self) -> f64;
+
+    /// Convert a precise number, returning None for values the number type cannot hold
+    fn from_number_checked(value: Number) -> Option<Self>;
+
+    fn floor(&self) -> Self;
+
+    /// Convert to u64, returning None for values that are negative, not finite, or too large
+    fn to_u64_checked(&self) -> Option<u64>;
+
+    /// Convert a whole number to i64, returning None for values that are not finite or too large
+    fn to_i64_checked(&self) -> Option<i64>;
+}
+
+impl CurveNum for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn into_f64(self) -> f64 {
+        self
+    }
+
+    fn from_number_checked(value: Number) -> Option<Self> {
+        value.to_f64()
+    }
+
+    fn floor(&self) -> Self {
+        f64::floor(*self)
+    }
+
+    fn to_u64_checked(&self) -> Option<u64> {
+        // Check for invalid values: NaN, infinity, or negative numbers
+        if !self.is_finite() || *self < 0.0 {
+            return None;
+        }
+
+        // Check if the value exceeds the maximum u64 value
+        if *self > u64::MAX as f64 {
+            return None;
+        }
+
+        Some(*self as u64)
+    }
+
+    fn to_i64_checked(&self) -> Option<i64> {
+        // i64::MAX is not exactly representable, and rounds up to 2^63
+        if !self.is_finite() || *self < i64::MIN as f64 || *self >= i64::MAX as f64 {
+            return None;
+        }
+
+        Some(*self as i64)
+    }
+}
+
+impl CurveNum for DNum {
+    fn from_f64(value: f64) -> Self {
+        DNum::from(value)
+    }
+
+    fn into_f64(self) -> f64 {
+        DNum::to_f64(&self).unwrap()
+    }
+
+    fn from_number_checked(value: Number) -> Option<Self> {
+        Some(DNum::from_precise_number(&value))
+    }
+
+    fn floor(&self) -> Self {
+        DNum::floor(*self)
+    }
+
+    fn to_u64_checked(&self) -> Option<u64> {
+        self.checked_to_u64()
+    }
+
+    fn to_i64_checked(&self) -> Option<i64> {
+        self.checked_to_i64()
+    }
+}
@@ -1,5 +1,15 @@
 use precise_number::Number;
 
+/// Share of `staged` earnings that goes with `amount` out of a `balance`
+/// Moving the whole balance moves everything that is staged
+pub fn pro_rata_staged(staged: u64, amount: u64, balance: u64) -> u64 {
+    if amount >= balance {
+        return staged;
+    }
+
+    (staged as u128 * amount as u128 / balance as u128) as u64
+}
+
 /// Calculate earned emissions based on the final index
 pub fn calc_share_value(last_seen_index: Number, cur_index: Number, share_balance: u64) -> u64 {
     if cur_index <= last_seen_index {
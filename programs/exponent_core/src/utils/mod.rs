@@ -1,6 +1,9 @@
+pub mod curve_num;
 pub mod math;
 mod pda;
 pub mod sy_cpi;
+#[cfg(test)]
+pub mod test_rng;
 
 pub use pda::*;
 pub use sy_cpi::*;